@@ -10,6 +10,7 @@ pub struct Portfolio {
     pub im: u128,
     pub mm: u128,
     pub exposures: Vec<(u16, u16, i64)>, // (slab_idx, instrument_idx, qty)
+    pub entry_prices: Vec<u64>,          // average entry price per exposure (1e6 scale)
     pub exposure_count: u16,
 }
 
@@ -34,8 +35,7 @@ pub fn calculate_health(
 /// Equity = base_equity + sum(position_pnl)
 /// where position_pnl = qty * (current_price - entry_price) / 1e6
 ///
-/// For v0, we simplify by using mark-to-market:
-/// Equity = base_equity + sum(qty * current_price) / 1e6
+/// Realized PnL is already settled into the on-chain equity.
 pub fn calculate_equity(
     portfolio: &Portfolio,
     oracle_prices: &HashMap<u16, i64>,
@@ -53,11 +53,12 @@ pub fn calculate_equity(
         // Get oracle price for instrument
         let price = oracle_prices.get(&instrument_idx).copied().unwrap_or(0);
 
-        // Calculate notional value (simplified: qty * price / 1e6)
-        // In production, this would account for entry price
-        let notional = (qty as i128 * price as i128) / 1_000_000;
+        let entry = portfolio.entry_prices.get(i).copied().unwrap_or(0);
 
-        equity += notional;
+        // Unrealized PnL against the average entry price
+        let unrealized = (qty as i128 * (price as i128 - entry as i128)) / 1_000_000;
+
+        equity += unrealized;
     }
 
     equity
@@ -91,6 +92,7 @@ pub fn parse_portfolio(data: &[u8]) -> Result<Portfolio> {
         im: 0,
         mm: 0,
         exposures: Vec::new(),
+        entry_prices: Vec::new(),
         exposure_count: 0,
     })
 }
@@ -106,6 +108,7 @@ mod tests {
             im: 110_000_000,
             mm: 100_000_000,    // $100
             exposures: vec![],
+            entry_prices: vec![],
             exposure_count: 0,
         };
 
@@ -123,6 +126,7 @@ mod tests {
             im: 110_000_000,
             mm: 100_000_000,     // $100
             exposures: vec![],
+            entry_prices: vec![],
             exposure_count: 0,
         };
 
@@ -147,6 +151,7 @@ mod tests {
                 (0, 0, 10_000_000),  // Long 10 units at instrument 0
                 (1, 1, -5_000_000),  // Short 5 units at instrument 1
             ],
+            entry_prices: vec![45_000_000, 100_000_000], // $45, $100
             exposure_count: 2,
         };
 
@@ -157,10 +162,10 @@ mod tests {
         let equity = calculate_equity(&portfolio, &oracle_prices);
 
         // Base equity: $100
-        // Long position: 10 * $50 / 1e6 = $500
-        // Short position: -5 * $100 / 1e6 = -$500
-        // Total: $100 + $500 - $500 = $100
-        assert_eq!(equity, 100_000_000);
+        // Long position: 10 * ($50 - $45) = $50
+        // Short position: -5 * ($100 - $100) = $0
+        // Total: $100 + $50 + $0 = $150
+        assert_eq!(equity, 150_000_000);
     }
 
     #[test]
@@ -170,6 +175,7 @@ mod tests {
             im: 110_000_000,
             mm: 100_000_000,
             exposures: vec![],
+            entry_prices: vec![],
             exposure_count: 0,
         };
 
@@ -186,6 +192,7 @@ mod tests {
             im: 110_000_000,
             mm: 90_000_000,
             exposures: vec![],
+            entry_prices: vec![],
             exposure_count: 0,
         };
 
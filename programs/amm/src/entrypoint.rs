@@ -1,7 +1,7 @@
 //! Program entrypoint
 
-use crate::instructions;
-use percolator_common::{PercolatorError, Side};
+use crate::{instructions, CurveMode, SampleMode, SAMPLE_LEVELS};
+use percolator_common::{CommitFillData, PercolatorError};
 use pinocchio::{
     account_info::AccountInfo,
     entrypoint,
@@ -31,6 +31,10 @@ pub fn process_instruction(
             // Initialize: lp_owner(32) + router_id(32) + instrument(32) +
             //             mark_px(8) + taker_fee_bps(8) + contract_size(8) + bump(1) +
             //             x_reserve(8) + y_reserve(8)
+            //             [+ curve_mode(1) + liquidity_depth(8)]
+            //             [+ min_fee_bps(2) + max_fee_bps(2)]
+            // Trailing curve fields are optional; omitted = ConstantProduct
+            // Trailing fee band is optional; omitted = static taker_fee_bps
             if data.len() < 137 {
                 return Err(PercolatorError::InvalidInstruction.into());
             }
@@ -45,6 +49,25 @@ pub fn process_instruction(
             let x_reserve = i64::from_le_bytes(data[121..129].try_into().unwrap());
             let y_reserve = i64::from_le_bytes(data[129..137].try_into().unwrap());
 
+            let (curve_mode, liquidity_depth) = if data.len() >= 146 {
+                let Some(mode) = CurveMode::from_u8(data[137]) else {
+                    msg!("Error: Unknown curve mode");
+                    return Err(PercolatorError::InvalidInstruction.into());
+                };
+                (mode, i64::from_le_bytes(data[138..146].try_into().unwrap()))
+            } else {
+                (CurveMode::ConstantProduct, 0)
+            };
+
+            let (min_fee_bps, max_fee_bps) = if data.len() >= 150 {
+                (
+                    u16::from_le_bytes(data[146..148].try_into().unwrap()),
+                    u16::from_le_bytes(data[148..150].try_into().unwrap()),
+                )
+            } else {
+                (0, 0)
+            };
+
             instructions::process_initialize(
                 accounts,
                 lp_owner,
@@ -54,21 +77,66 @@ pub fn process_instruction(
                 taker_fee_bps,
                 contract_size,
                 bump,
-                x_reserve,
-                y_reserve,
+                instructions::InitParams {
+                    x_reserve,
+                    y_reserve,
+                    curve_mode,
+                    liquidity_depth,
+                    min_fee_bps,
+                    max_fee_bps,
+                },
             )
         }
         1 => {
-            // commit_fill: side(1) + qty(8) + limit_px(8)
-            if data.len() < 17 {
+            // commit_fill: expected_seqno(4) + side(1) + qty(8) + limit_px(8)
+            // Same layout as the slab; the router encodes it with CommitFillData
+            let fill = CommitFillData::decode(data)?;
+
+            instructions::process_commit_fill(accounts, fill.expected_seqno, fill.side, fill.qty, fill.limit_px)
+        }
+        2 => {
+            // add_range: lower_px(8) + upper_px(8) + liquidity(16)
+            if data.len() < 32 {
+                return Err(PercolatorError::InvalidInstruction.into());
+            }
+
+            let lower_px = i64::from_le_bytes(data[0..8].try_into().unwrap());
+            let upper_px = i64::from_le_bytes(data[8..16].try_into().unwrap());
+            let liquidity = u128::from_le_bytes(data[16..32].try_into().unwrap());
+
+            instructions::process_add_range(accounts, lower_px, upper_px, liquidity)
+        }
+        3 => {
+            // remove_range: range_idx(2)
+            if data.len() < 2 {
                 return Err(PercolatorError::InvalidInstruction.into());
             }
 
-            let side = if data[0] == 0 { Side::Buy } else { Side::Sell };
-            let qty = i64::from_le_bytes(data[1..9].try_into().unwrap());
-            let limit_px = i64::from_le_bytes(data[9..17].try_into().unwrap());
+            let range_idx = u16::from_le_bytes(data[0..2].try_into().unwrap());
+
+            instructions::process_remove_range(accounts, range_idx)
+        }
+        4 => {
+            // refresh_quotes: no data
+            instructions::process_refresh_quotes(accounts)
+        }
+        5 => {
+            // set_sample_ladder: mode(1) + sizes(8 * 4)
+            if data.len() < 1 + 8 * SAMPLE_LEVELS {
+                return Err(PercolatorError::InvalidInstruction.into());
+            }
+
+            let Some(mode) = SampleMode::from_u8(data[0]) else {
+                msg!("Error: Unknown sample mode");
+                return Err(PercolatorError::InvalidInstruction.into());
+            };
+            let mut sizes = [0i64; SAMPLE_LEVELS];
+            for (i, size) in sizes.iter_mut().enumerate() {
+                let start = 1 + i * 8;
+                *size = i64::from_le_bytes(data[start..start + 8].try_into().unwrap());
+            }
 
-            instructions::process_commit_fill(accounts, side, qty, limit_px)
+            instructions::process_set_sample_ladder(accounts, mode, sizes)
         }
         _ => {
             msg!("Error: Unknown instruction discriminator");
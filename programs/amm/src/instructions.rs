@@ -1,10 +1,41 @@
-//! AMM instructions - initialize and commit_fill
+//! AMM instructions - initialize, commit_fill, range management and quote refresh
 
-use crate::{AmmState, math::{quote_buy, quote_sell}};
+use crate::{AmmState, CurveMode, SampleMode, SAMPLE_LEVELS, oracle::read_oracle_price};
 use percolator_common::{PercolatorError, Side, SlabHeader, FillReceipt, borrow_account_data_mut};
-use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, ProgramResult};
+use pinocchio::{
+    account_info::AccountInfo,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+/// Curve and fee parameters of a new pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InitParams {
+    /// Initial base reserve
+    pub x_reserve: i64,
+    /// Initial quote reserve
+    pub y_reserve: i64,
+    /// ConstantProduct, OraclePegged or Concentrated
+    pub curve_mode: CurveMode,
+    /// Virtual base depth around the peg (OraclePegged only)
+    pub liquidity_depth: i64,
+    /// Dynamic fee floor
+    pub min_fee_bps: u16,
+    /// Dynamic fee cap (fee band disabled when max <= min)
+    pub max_fee_bps: u16,
+}
 
 /// Initialize a new AMM pool
+///
+/// # Arguments
+/// * `accounts` - [amm_account, payer, oracle (required for OraclePegged, optional otherwise)]
+/// * `params` - Reserves, curve mode and dynamic fee band
+///
+/// Concentrated pools start empty at `mark_px`; reserves must be zero and
+/// liquidity is added afterwards with AddRange.
 pub fn process_initialize(
     accounts: &[AccountInfo],
     lp_owner: Pubkey,
@@ -14,10 +45,18 @@ pub fn process_initialize(
     taker_fee_bps: i64,
     contract_size: i64,
     bump: u8,
-    x_reserve: i64,
-    y_reserve: i64,
+    params: InitParams,
 ) -> ProgramResult {
-    let [amm_account, payer] = accounts else {
+    let InitParams {
+        x_reserve,
+        y_reserve,
+        curve_mode,
+        liquidity_depth,
+        min_fee_bps,
+        max_fee_bps,
+    } = params;
+
+    let [amm_account, payer, remaining @ ..] = accounts else {
         return Err(PercolatorError::InvalidAccount.into());
     };
 
@@ -46,7 +85,57 @@ pub fn process_initialize(
     );
 
     // Create AMM state
-    let mut amm = AmmState::new(header, x_reserve, y_reserve, taker_fee_bps);
+    let mut amm = match curve_mode {
+        CurveMode::ConstantProduct => AmmState::new(header, x_reserve, y_reserve, taker_fee_bps),
+        CurveMode::OraclePegged => {
+            let [oracle_account, ..] = remaining else {
+                msg!("Error: Oracle-pegged pool requires an oracle account");
+                return Err(PercolatorError::InvalidAccount.into());
+            };
+            if liquidity_depth <= 0 {
+                msg!("Error: Liquidity depth must be positive");
+                return Err(PercolatorError::InvalidQuantity.into());
+            }
+
+            let peg_px = read_oracle_price(oracle_account, Clock::get()?.unix_timestamp)?;
+            AmmState::new_pegged(
+                header,
+                x_reserve,
+                y_reserve,
+                taker_fee_bps,
+                *oracle_account.key(),
+                peg_px,
+                liquidity_depth,
+            )
+        }
+        CurveMode::Concentrated => {
+            if x_reserve != 0 || y_reserve != 0 {
+                msg!("Error: Concentrated pool must start without reserves");
+                return Err(PercolatorError::InvalidQuantity.into());
+            }
+            if mark_px <= 0 {
+                msg!("Error: Start price must be positive");
+                return Err(PercolatorError::InvalidPrice.into());
+            }
+            AmmState::new_concentrated(header, taker_fee_bps, mark_px)
+        }
+    };
+
+    // Other modes may still reference an oracle for dynamic fees
+    if curve_mode != CurveMode::OraclePegged {
+        if let [oracle_account, ..] = remaining {
+            read_oracle_price(oracle_account, Clock::get()?.unix_timestamp)?;
+            amm.pool.oracle = *oracle_account.key();
+        }
+    }
+
+    if max_fee_bps > min_fee_bps {
+        if max_fee_bps as i64 >= 10_000 {
+            msg!("Error: Max fee must be below 100%");
+            return Err(PercolatorError::InvalidInstruction.into());
+        }
+        amm.set_dynamic_fees(min_fee_bps, max_fee_bps);
+    }
 
     // Synthesize initial quote cache
     amm.synthesize_quote_cache();
@@ -61,27 +150,58 @@ pub fn process_initialize(
     Ok(())
 }
 
+/// Read the pool's oracle price from the trailing accounts
+///
+/// OraclePegged pools must be passed their oracle, so a fill or refresh is
+/// never quoted off a stale peg; other pools read it only when supplied.
+/// Prices older than `MAX_ORACLE_AGE_SECS` are rejected.
+fn read_pool_oracle(amm: &AmmState, remaining: &[AccountInfo]) -> Result<Option<i64>, ProgramError> {
+    match remaining {
+        [oracle_account, ..] if amm.pool.oracle != Pubkey::default() => {
+            if oracle_account.key() != &amm.pool.oracle {
+                msg!("Error: Oracle does not match pool");
+                return Err(PercolatorError::InvalidAccount.into());
+            }
+            Ok(Some(read_oracle_price(oracle_account, Clock::get()?.unix_timestamp)?))
+        }
+        _ if amm.curve_mode() == CurveMode::OraclePegged => {
+            msg!("Error: Oracle-pegged pool requires its oracle account");
+            Err(PercolatorError::InvalidAccount.into())
+        }
+        _ => Ok(None),
+    }
+}
+
 /// Commit a fill against the AMM curve
 ///
 /// This is the CPI endpoint for the router to execute trades against the AMM.
 ///
 /// # Arguments
-/// * `accounts` - [amm_account, receipt_account, router_signer]
+/// * `accounts` - [amm_account, receipt_account, router_signer, oracle (required for OraclePegged, optional otherwise)]
+/// * `expected_seqno` - Pool seqno the router read (TOCTOU protection)
 /// * `side` - Buy or Sell
 /// * `qty` - Desired quantity (1e6 scale, positive)
 /// * `limit_px` - Worst acceptable VWAP (1e6 scale)
 ///
+/// OraclePegged pools re-centre the curve on the current oracle price
+/// before quoting. With dynamic fees the oracle also feeds the deviation sample.
+///
+/// The fee (at the effective rate) is already in the curve's VWAP and stays
+/// in the reserves, so the receipt reports a fee of 0: the router charges
+/// receipt fees on top of the fill price and would otherwise take it twice.
+///
 /// # Returns
 /// * Writes FillReceipt to receipt_account
 /// * Updates AMM reserves and QuoteCache
 /// * Increments seqno
 pub fn process_commit_fill(
     accounts: &[AccountInfo],
+    expected_seqno: u32,
     side: Side,
     qty: i64,
     limit_px: i64,
 ) -> ProgramResult {
-    let [amm_account, receipt_account, router_signer] = accounts else {
+    let [amm_account, receipt_account, router_signer, remaining @ ..] = accounts else {
         return Err(PercolatorError::InvalidAccount.into());
     };
 
@@ -91,14 +211,8 @@ pub fn process_commit_fill(
         return Err(PercolatorError::Unauthorized.into());
     }
 
-    // Get mutable AMM state
-    let data = amm_account.try_borrow_mut_data()?;
-    if data.len() != AmmState::LEN {
-        msg!("Error: AMM account has incorrect size");
-        return Err(PercolatorError::InvalidAccount.into());
-    }
-
-    let amm = unsafe { &mut *(data.as_ptr() as *mut AmmState) };
+    // Get mutable AMM state (current layout version only)
+    let amm = load_pool(amm_account)?;
 
     // Verify router authority
     if &amm.header.router_id != router_signer.key() {
@@ -106,6 +220,12 @@ pub fn process_commit_fill(
         return Err(PercolatorError::Unauthorized.into());
     }
 
+    // TOCTOU Protection: the curve must not have moved since the router read it
+    if amm.header.seqno != expected_seqno {
+        msg!("Error: Seqno mismatch - pool changed since read");
+        return Err(PercolatorError::SeqnoMismatch.into());
+    }
+
     // Validate order parameters
     if qty <= 0 {
         msg!("Error: Quantity must be positive");
@@ -119,60 +239,30 @@ pub fn process_commit_fill(
     // Capture seqno before execution
     let seqno_committed = amm.header.seqno;
 
-    // Execute trade against AMM curve
-    let result = match side {
-        Side::Buy => {
-            // User buys qty contracts from AMM (AMM sells)
-            quote_buy(
-                amm.pool.x_reserve,
-                amm.pool.y_reserve,
-                amm.pool.fee_bps,
-                qty,
-                amm.pool.min_liquidity,
-            )
-        }
-        Side::Sell => {
-            // User sells qty contracts to AMM (AMM buys)
-            quote_sell(
-                amm.pool.x_reserve,
-                amm.pool.y_reserve,
-                amm.pool.fee_bps,
-                qty,
-                amm.pool.min_liquidity,
-            )
-        }
-    }?;
+    let oracle_px = read_pool_oracle(amm, remaining)?;
 
-    // Check limit price
-    match side {
-        Side::Buy => {
-            if result.vwap_px > limit_px {
-                msg!("Error: VWAP exceeds buy limit");
-                return Err(PercolatorError::InvalidPrice.into());
-            }
-        }
-        Side::Sell => {
-            if result.vwap_px < limit_px {
-                msg!("Error: VWAP below sell limit");
-                return Err(PercolatorError::InvalidPrice.into());
-            }
+    // Reprice the fee and re-centre a pegged curve before quoting
+    amm.reprice(oracle_px);
+
+    // Execute trade against AMM curve
+    // Buy: user buys qty contracts from AMM (AMM sells)
+    // Sell: user sells qty contracts to AMM (AMM buys)
+    // Reserves (and ranges) are only updated if the VWAP is within limit
+    let result = amm.fill(side, qty, limit_px).inspect_err(|e| {
+        if *e == PercolatorError::InvalidPrice {
+            msg!("Error: VWAP outside limit price");
         }
-    }
+    })?;
 
-    // Calculate notional and fee
+    // Fee is priced into the VWAP and paid into the reserves
     let notional = (qty as i128 * result.vwap_px as i128 / 1_000_000) as i64;
-    let fee = (notional as i128 * amm.pool.fee_bps as i128 / 10_000) as i64;
-
-    // Update AMM reserves
-    amm.pool.x_reserve = result.new_x;
-    amm.pool.y_reserve = result.new_y;
 
     // Synthesize new QuoteCache reflecting the updated curve
     amm.synthesize_quote_cache();
 
     // Write fill receipt
     let receipt = unsafe { borrow_account_data_mut::<FillReceipt>(receipt_account)? };
-    receipt.write(seqno_committed, qty, result.vwap_px, notional, fee);
+    receipt.write(seqno_committed, qty, result.vwap_px, notional, 0);
 
     // Increment seqno (AMM state changed)
     amm.header.increment_seqno();
@@ -181,3 +271,319 @@ pub fn process_commit_fill(
 
     Ok(())
 }
+
+/// Add a concentrated-liquidity range (pool LP owner only)
+///
+/// Ranges are quoted without deposited reserves, so only the pool's
+/// `lp_owner` may place them.
+///
+/// # Arguments
+/// * `accounts` - [amm_account, lp_owner]
+/// * `lower_px` / `upper_px` - Range bounds (1e6 scale, multiples of header.tick)
+/// * `liquidity` - Liquidity L to place in the range
+///
+/// # Returns
+/// * Range occupies the first free slot; reserves and QuoteCache are refreshed
+/// * Increments seqno
+pub fn process_add_range(
+    accounts: &[AccountInfo],
+    lower_px: i64,
+    upper_px: i64,
+    liquidity: u128,
+) -> ProgramResult {
+    let [amm_account, owner] = accounts else {
+        return Err(PercolatorError::InvalidAccount.into());
+    };
+
+    if !owner.is_signer() {
+        msg!("Error: LP owner must be signer");
+        return Err(PercolatorError::Unauthorized.into());
+    }
+
+    let amm = load_concentrated(amm_account)?;
+    if &amm.header.lp_owner != owner.key() {
+        msg!("Error: Signer is not the pool LP owner");
+        return Err(PercolatorError::Unauthorized.into());
+    }
+
+    // Bounds must sit on the tick grid
+    let tick = amm.header.tick;
+    if tick <= 0 || lower_px % tick != 0 || upper_px % tick != 0 {
+        msg!("Error: Range bounds must be tick-aligned");
+        return Err(PercolatorError::InvalidPrice.into());
+    }
+
+    amm.ranges.add_range(*owner.key(), lower_px, upper_px, liquidity)?;
+    amm.sync_range_reserves();
+
+    // Increment seqno before resynthesizing so the cache snapshot matches
+    amm.header.increment_seqno();
+    amm.synthesize_quote_cache();
+
+    msg!("Range added");
+    Ok(())
+}
+
+/// Remove a concentrated-liquidity range
+///
+/// # Arguments
+/// * `accounts` - [amm_account, owner]
+/// * `range_idx` - Range slot index returned by AddRange
+///
+/// # Returns
+/// * Range is deactivated; reserves and QuoteCache are refreshed
+/// * Increments seqno
+pub fn process_remove_range(accounts: &[AccountInfo], range_idx: u16) -> ProgramResult {
+    let [amm_account, owner] = accounts else {
+        return Err(PercolatorError::InvalidAccount.into());
+    };
+
+    if !owner.is_signer() {
+        msg!("Error: Range owner must be signer");
+        return Err(PercolatorError::Unauthorized.into());
+    }
+
+    let amm = load_concentrated(amm_account)?;
+
+    amm.ranges.remove_range(range_idx, owner.key())?;
+    amm.sync_range_reserves();
+
+    amm.header.increment_seqno();
+    amm.synthesize_quote_cache();
+
+    msg!("Range removed");
+    Ok(())
+}
+
+/// Refresh the QuoteCache (permissionless)
+///
+/// Re-synthesizes quotes from current pool state and bumps seqno so the
+/// router's chooser picks up the new depth.
+///
+/// # Arguments
+/// * `accounts` - [amm_account, oracle (required for OraclePegged, optional otherwise)]
+///
+/// OraclePegged curves are re-centred on the oracle price first.
+pub fn process_refresh_quotes(accounts: &[AccountInfo]) -> ProgramResult {
+    let [amm_account, remaining @ ..] = accounts else {
+        return Err(PercolatorError::InvalidAccount.into());
+    };
+
+    let amm = load_pool(amm_account)?;
+
+    if let (CurveMode::OraclePegged, Some(px)) = (amm.curve_mode(), read_pool_oracle(amm, remaining)?) {
+        amm.repeg(px);
+    }
+
+    amm.header.increment_seqno();
+    amm.synthesize_quote_cache();
+
+    msg!("Quotes refreshed");
+    Ok(())
+}
+
+/// Configure the quote-cache sample ladder
+///
+/// # Arguments
+/// * `accounts` - [amm_account, lp_owner]
+/// * `mode` - Sizes as bps of curve base or absolute contract quantities
+/// * `sizes` - Ascending sample sizes, one per level
+///
+/// # Returns
+/// * Stores the ladder, refreshes the QuoteCache and increments seqno
+pub fn process_set_sample_ladder(
+    accounts: &[AccountInfo],
+    mode: SampleMode,
+    sizes: [i64; SAMPLE_LEVELS],
+) -> ProgramResult {
+    let [amm_account, lp_owner] = accounts else {
+        return Err(PercolatorError::InvalidAccount.into());
+    };
+
+    if !lp_owner.is_signer() {
+        msg!("Error: LP owner must be signer");
+        return Err(PercolatorError::Unauthorized.into());
+    }
+
+    let amm = load_pool(amm_account)?;
+    if &amm.header.lp_owner != lp_owner.key() {
+        msg!("Error: Signer is not the pool LP owner");
+        return Err(PercolatorError::Unauthorized.into());
+    }
+
+    amm.set_sample_ladder(mode, sizes).inspect_err(|_| {
+        msg!("Error: Sample sizes must be positive and ascending");
+    })?;
+
+    amm.header.increment_seqno();
+    amm.synthesize_quote_cache();
+
+    msg!("Sample ladder updated");
+    Ok(())
+}
+
+/// Borrow AMM state after checking the account size, header magic and
+/// layout version
+fn load_pool(amm_account: &AccountInfo) -> Result<&mut AmmState, PercolatorError> {
+    {
+        let data = amm_account.try_borrow_data().map_err(|_| PercolatorError::InvalidAccount)?;
+        if data.len() != AmmState::LEN || !data.starts_with(SlabHeader::MAGIC) {
+            msg!("Error: Invalid AMM account");
+            return Err(PercolatorError::InvalidAccount);
+        }
+    }
+
+    let amm = unsafe { borrow_account_data_mut::<AmmState>(amm_account)? };
+    if amm.header.version != AmmState::VERSION {
+        msg!("Error: Unsupported AMM account version");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    Ok(amm)
+}
+
+/// Borrow AMM state, requiring a Concentrated-mode pool
+fn load_concentrated(amm_account: &AccountInfo) -> Result<&mut AmmState, PercolatorError> {
+    let amm = load_pool(amm_account)?;
+    if amm.curve_mode() != CurveMode::Concentrated {
+        msg!("Error: Pool is not in concentrated mode");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+    Ok(amm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use percolator_common::test_accounts::TestAccount;
+    use percolator_common::CommitFillData;
+
+    const LP_OWNER: Pubkey = [7; 32];
+
+    fn concentrated_pool() -> TestAccount {
+        let header = SlabHeader::new([1; 32], LP_OWNER, [2; 32], [3; 32], 50_000_000_000, 5, 1_000_000, 0);
+        let amm = AmmState::new_concentrated(header, 5, 50_000_000_000);
+        let data = unsafe { core::slice::from_raw_parts(&amm as *const AmmState as *const u8, AmmState::LEN) };
+        TestAccount::new([1; 32], [9; 32], data)
+    }
+
+    #[test]
+    fn test_add_range_requires_pool_lp_owner() {
+        let mut pool = concentrated_pool();
+        let (lower, upper) = (49_000_000_000, 51_000_000_000);
+
+        // Any other signer is rejected
+        let mut other = TestAccount::new([8; 32], [0; 32], &[]).signer();
+        assert_eq!(
+            process_add_range(&[pool.info(), other.info()], lower, upper, 1_000_000),
+            Err(PercolatorError::Unauthorized.into())
+        );
+
+        // The LP owner must sign
+        let mut unsigned_owner = TestAccount::new(LP_OWNER, [0; 32], &[]);
+        assert_eq!(
+            process_add_range(&[pool.info(), unsigned_owner.info()], lower, upper, 1_000_000),
+            Err(PercolatorError::Unauthorized.into())
+        );
+
+        let mut owner = TestAccount::new(LP_OWNER, [0; 32], &[]).signer();
+        assert_eq!(process_add_range(&[pool.info(), owner.info()], lower, upper, 1_000_000), Ok(()));
+    }
+
+    const ROUTER: Pubkey = [2; 32];
+    const CP_X: i64 = 1_000_000_000;
+    const CP_Y: i64 = 50_000_000_000_000;
+
+    /// Constant-product pool at 50k routed by ROUTER
+    fn routed_pool(fee_bps: i64) -> TestAccount {
+        let header = SlabHeader::new([1; 32], LP_OWNER, ROUTER, [3; 32], 50_000_000_000, fee_bps, 1_000_000, 0);
+        let amm = AmmState::new(header, CP_X, CP_Y, fee_bps);
+        let data = unsafe { core::slice::from_raw_parts(&amm as *const AmmState as *const u8, AmmState::LEN) };
+        TestAccount::new([1; 32], [9; 32], data)
+    }
+
+    #[test]
+    fn test_routed_fill_charges_fee_once() {
+        let (x, y, fee_bps) = (CP_X, CP_Y, 30);
+        let mut pool = routed_pool(fee_bps);
+        let mut receipt = TestAccount::new([4; 32], [9; 32], &[0; FillReceipt::LEN]);
+        let mut router = TestAccount::new(ROUTER, [0; 32], &[]).signer();
+
+        let qty = 1_000_000;
+        assert_eq!(
+            process_commit_fill(&[pool.info(), receipt.info(), router.info()], 0, Side::Buy, qty, i64::MAX),
+            Ok(())
+        );
+
+        // The fee is in the VWAP (paid into the reserves), not charged again on the receipt
+        let with_fee = crate::math::quote_buy(x, y, fee_bps, qty, 1000).unwrap().vwap_px;
+        let fee_free = crate::math::quote_buy(x, y, 0, qty, 1000).unwrap().vwap_px;
+        let receipt_info = receipt.info();
+        let written = unsafe { borrow_account_data_mut::<FillReceipt>(&receipt_info).unwrap() };
+        assert_eq!(written.vwap_px, with_fee);
+        assert!(written.vwap_px > fee_free);
+        assert_eq!(written.fee, 0);
+    }
+
+    #[test]
+    fn test_router_commit_fill_data_decodes() {
+        let mut pool = routed_pool(0);
+        let mut receipt = TestAccount::new([4; 32], [9; 32], &[0; FillReceipt::LEN]);
+        let mut router = TestAccount::new(ROUTER, [0; 32], &[]).signer();
+
+        // Encoded exactly as the router's cross-slab CPI builds it
+        let fill = CommitFillData {
+            expected_seqno: 0,
+            side: Side::Sell,
+            qty: 2_000_000,
+            limit_px: 40_000_000_000,
+        };
+        let data = fill.encode();
+        let accounts = [pool.info(), receipt.info(), router.info()];
+        assert_eq!(crate::entrypoint::process_instruction(&[9; 32], &accounts, &data), Ok(()));
+
+        let receipt_info = receipt.info();
+        let written = unsafe { borrow_account_data_mut::<FillReceipt>(&receipt_info).unwrap() };
+        assert_eq!(written.filled_qty, fill.qty);
+        assert_eq!(written.seqno_committed, fill.expected_seqno);
+        assert!(written.vwap_px < 50_000_000_000 && written.vwap_px >= fill.limit_px);
+
+        // Replaying the same seqno after the pool moved is rejected
+        let mut receipt = TestAccount::new([4; 32], [9; 32], &[0; FillReceipt::LEN]);
+        let accounts = [pool.info(), receipt.info(), router.info()];
+        assert_eq!(
+            crate::entrypoint::process_instruction(&[9; 32], &accounts, &data),
+            Err(PercolatorError::SeqnoMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_refresh_rejects_malformed_pool_account() {
+        // Too short for the pool layout
+        let mut short = TestAccount::new([1; 32], [9; 32], &[0; 64]);
+        assert_eq!(process_refresh_quotes(&[short.info()]), Err(PercolatorError::InvalidAccount.into()));
+
+        // Right size, wrong magic
+        let mut header = SlabHeader::new([1; 32], LP_OWNER, [2; 32], [3; 32], 50_000_000_000, 5, 1_000_000, 0);
+        header.magic = *b"NOTAPOOL";
+        let amm = AmmState::new_concentrated(header, 5, 50_000_000_000);
+        let data = unsafe { core::slice::from_raw_parts(&amm as *const AmmState as *const u8, AmmState::LEN) };
+        let mut bad_magic = TestAccount::new([1; 32], [9; 32], data);
+        assert_eq!(process_refresh_quotes(&[bad_magic.info()]), Err(PercolatorError::InvalidAccount.into()));
+        let mut owner = TestAccount::new(LP_OWNER, [0; 32], &[]).signer();
+        assert_eq!(
+            process_set_sample_ladder(&[bad_magic.info(), owner.info()], SampleMode::ReserveBps, [100, 200, 500, 1000]),
+            Err(PercolatorError::InvalidAccount.into())
+        );
+
+        // v1 pools predate the current pool layout
+        let header = SlabHeader::new([1; 32], LP_OWNER, [2; 32], [3; 32], 50_000_000_000, 5, 1_000_000, 0);
+        let mut amm = AmmState::new_concentrated(header, 5, 50_000_000_000);
+        amm.header.version = SlabHeader::VERSION;
+        let data = unsafe { core::slice::from_raw_parts(&amm as *const AmmState as *const u8, AmmState::LEN) };
+        let mut v1 = TestAccount::new([1; 32], [9; 32], data);
+        assert_eq!(process_refresh_quotes(&[v1.info()]), Err(PercolatorError::InvalidAccount.into()));
+
+        let mut pool = concentrated_pool();
+        assert_eq!(process_refresh_quotes(&[pool.info()]), Ok(()));
+    }
+}
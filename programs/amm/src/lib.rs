@@ -5,12 +5,18 @@
 //! - Same SlabHeader and QuoteCache layout
 //! - Same commit_fill CPI interface
 //! - Router-readable quote synthesis
+//!
+//! Pools run either as a plain constant-product curve or, for perps, as an
+//! oracle-pegged curve re-centred on the PriceOracle price, or with LP
+//! liquidity concentrated in tick ranges.
 
 #![allow(clippy::arithmetic_side_effects)]
 
 pub mod entrypoint;
 pub mod instructions;
 pub mod math;
+pub mod oracle;
+pub mod ranges;
 pub mod state;
 
 pub use state::*;
@@ -1,4 +1,7 @@
 //! Constant product AMM math (x·y=k)
+//!
+//! Also provides the oracle-pegged variant, which prices on a virtual
+//! x·y=k curve re-centred on the oracle price.
 
 use percolator_common::PercolatorError;
 
@@ -155,6 +158,193 @@ pub fn quote_sell(
     })
 }
 
+/// Virtual reserves for an oracle-pegged curve
+///
+/// The curve is centred on the peg price with `depth` contracts of virtual
+/// base liquidity, so spot = y_v / x_v = peg_px and slippage is set by depth:
+/// - x_v = depth
+/// - y_v = depth · peg_px / SCALE
+fn pegged_virtual_reserves(peg_px: i64, depth: i64) -> Result<(i64, i64), PercolatorError> {
+    if peg_px <= 0 || depth <= 0 {
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let y_virtual = (depth as i128 * peg_px as i128) / SCALE as i128;
+    if y_virtual <= 0 || y_virtual > i64::MAX as i128 {
+        return Err(PercolatorError::Overflow);
+    }
+
+    Ok((depth, y_virtual as i64))
+}
+
+/// Calculate quote for buying base on an oracle-pegged curve
+///
+/// Pricing comes from a constant-product curve re-centred on `peg_px`
+/// with `depth` virtual base reserves. The real inventory only settles
+/// the trade:
+/// - x_inv must cover Δx_out above min_liquidity
+/// - new_x = x_inv - Δx_out, new_y = y_inv + Δy_in
+pub fn quote_buy_pegged(
+    peg_px: i64,
+    depth: i64,
+    x_inventory: i64,
+    y_inventory: i64,
+    fee_bps: i64,
+    dx_out: i64,
+    min_liquidity: i64,
+) -> Result<QuoteResult, PercolatorError> {
+    let (x_virtual, y_virtual) = pegged_virtual_reserves(peg_px, depth)?;
+    let curve = quote_buy(x_virtual, y_virtual, fee_bps, dx_out, 0)?;
+
+    if dx_out >= x_inventory - min_liquidity {
+        return Err(PercolatorError::InsufficientLiquidity);
+    }
+
+    Ok(QuoteResult {
+        quote_amount: curve.quote_amount,
+        vwap_px: curve.vwap_px,
+        new_x: x_inventory - dx_out,
+        new_y: y_inventory + curve.quote_amount,
+    })
+}
+
+/// Calculate quote for selling base on an oracle-pegged curve
+///
+/// Mirror of `quote_buy_pegged`:
+/// - y_inv must cover Δy_out above min_liquidity
+/// - new_x = x_inv + Δx_in, new_y = y_inv - Δy_out
+pub fn quote_sell_pegged(
+    peg_px: i64,
+    depth: i64,
+    x_inventory: i64,
+    y_inventory: i64,
+    fee_bps: i64,
+    dx_in: i64,
+    min_liquidity: i64,
+) -> Result<QuoteResult, PercolatorError> {
+    let (x_virtual, y_virtual) = pegged_virtual_reserves(peg_px, depth)?;
+    let curve = quote_sell(x_virtual, y_virtual, fee_bps, dx_in, 0)?;
+
+    if curve.quote_amount >= y_inventory - min_liquidity {
+        return Err(PercolatorError::InsufficientLiquidity);
+    }
+
+    Ok(QuoteResult {
+        quote_amount: curve.quote_amount,
+        vwap_px: curve.vwap_px,
+        new_x: x_inventory + dx_in,
+        new_y: y_inventory - curve.quote_amount,
+    })
+}
+
+/// Integer square root (floor) for u128
+pub fn isqrt_u128(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // Newton's method from an initial guess >= √n
+    let mut x = 1u128 << ((128 - n.leading_zeros()).div_ceil(2));
+    loop {
+        let y = (x + n / x) >> 1;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// Convert a price (1e6 scale) to sqrt-price: √(px) scaled by SCALE
+pub fn sqrt_px_from_px(px: i64) -> u128 {
+    if px <= 0 {
+        return 0;
+    }
+    isqrt_u128(px as u128 * SCALE as u128)
+}
+
+/// Convert a sqrt-price back to a price (1e6 scale)
+pub fn px_from_sqrt_px(sqrt_px: u128) -> i64 {
+    (sqrt_px * sqrt_px / SCALE as u128) as i64
+}
+
+/// Base held by liquidity L between two sqrt-prices (sqrt_a < sqrt_b)
+///
+/// Δx = L·SCALE/√a - L·SCALE/√b
+pub fn base_delta(liquidity: u128, sqrt_a: u128, sqrt_b: u128) -> Result<u128, PercolatorError> {
+    if sqrt_a == 0 || sqrt_b <= sqrt_a {
+        return Ok(0);
+    }
+    let l_scaled = liquidity
+        .checked_mul(SCALE as u128)
+        .ok_or(PercolatorError::Overflow)?;
+    Ok(l_scaled / sqrt_a - l_scaled / sqrt_b)
+}
+
+/// Quote held by liquidity L between two sqrt-prices (sqrt_a < sqrt_b)
+///
+/// Δy = L·(√b - √a)/SCALE
+pub fn quote_delta(liquidity: u128, sqrt_a: u128, sqrt_b: u128) -> Result<u128, PercolatorError> {
+    if sqrt_b <= sqrt_a {
+        return Ok(0);
+    }
+    liquidity
+        .checked_mul(sqrt_b - sqrt_a)
+        .map(|v| v / SCALE as u128)
+        .ok_or(PercolatorError::Overflow)
+}
+
+/// Sqrt-price after `dx` base leaves liquidity L (price moves up)
+///
+/// √p' = L·SCALE / (L·SCALE/√p - Δx)
+pub fn sqrt_px_after_base_out(liquidity: u128, sqrt_px: u128, dx: u128) -> Result<u128, PercolatorError> {
+    let l_scaled = liquidity
+        .checked_mul(SCALE as u128)
+        .ok_or(PercolatorError::Overflow)?;
+    let virtual_base = l_scaled / sqrt_px;
+    if dx >= virtual_base {
+        return Err(PercolatorError::InsufficientLiquidity);
+    }
+    Ok(l_scaled / (virtual_base - dx))
+}
+
+/// Sqrt-price after `dx` base enters liquidity L (price moves down)
+///
+/// √p' = L·SCALE / (L·SCALE/√p + Δx)
+pub fn sqrt_px_after_base_in(liquidity: u128, sqrt_px: u128, dx: u128) -> Result<u128, PercolatorError> {
+    let l_scaled = liquidity
+        .checked_mul(SCALE as u128)
+        .ok_or(PercolatorError::Overflow)?;
+    let virtual_base = l_scaled / sqrt_px;
+    Ok(l_scaled / (virtual_base + dx))
+}
+
+/// Absolute move from `reference_px` to `px` in basis points
+///
+/// Returns 0 when there is no reference yet.
+pub fn move_bps(reference_px: i64, px: i64) -> i64 {
+    if reference_px <= 0 || px <= 0 {
+        return 0;
+    }
+    let diff = (px as i128 - reference_px as i128).abs();
+    (diff * BPS_SCALE as i128 / reference_px as i128).min(i64::MAX as i128) as i64
+}
+
+/// Exponential moving average step: ema·(1 - alpha) + sample·alpha
+pub fn ema_update(ema: i64, sample: i64, alpha_bps: i64) -> i64 {
+    let alpha = alpha_bps.clamp(0, BPS_SCALE) as i128;
+    ((ema as i128 * (BPS_SCALE as i128 - alpha) + sample as i128 * alpha) / BPS_SCALE as i128) as i64
+}
+
+/// Scale the fee linearly from `min_fee_bps` to `max_fee_bps` as the
+/// volatility EMA rises from 0 to `saturation_bps`
+pub fn dynamic_fee_bps(min_fee_bps: i64, max_fee_bps: i64, vol_ema_bps: i64, saturation_bps: i64) -> i64 {
+    if max_fee_bps <= min_fee_bps || saturation_bps <= 0 {
+        return min_fee_bps;
+    }
+    let vol = vol_ema_bps.clamp(0, saturation_bps);
+    min_fee_bps + (max_fee_bps - min_fee_bps) * vol / saturation_bps
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -421,4 +611,151 @@ mod tests {
         // Larger trade should have much higher price impact
         assert!(large_impact > small_impact * 5);
     }
+
+    #[test]
+    fn test_pegged_buy_centred_on_peg() {
+        // Inventory is skewed (spot would be 120k), but the peg is 60k
+        let x_inv = 500 * TEST_SCALE;
+        let y_inv = 60_000_000 * TEST_SCALE;
+        let peg = 60_000 * TEST_SCALE;
+        let depth = 1000 * TEST_SCALE;
+
+        let result = quote_buy_pegged(peg, depth, x_inv, y_inv, 5, TEST_SCALE, 1000).unwrap();
+
+        // Priced around the peg, not the inventory ratio
+        assert!(result.vwap_px > peg);
+        assert!(result.vwap_px < 61_000 * TEST_SCALE);
+
+        // Inventory settles the trade
+        assert_eq!(result.new_x, x_inv - TEST_SCALE);
+        assert_eq!(result.new_y, y_inv + result.quote_amount);
+    }
+
+    #[test]
+    fn test_pegged_sell_centred_on_peg() {
+        let x_inv = 2000 * TEST_SCALE;
+        let y_inv = 60_000_000 * TEST_SCALE;
+        let peg = 60_000 * TEST_SCALE;
+        let depth = 1000 * TEST_SCALE;
+
+        let result = quote_sell_pegged(peg, depth, x_inv, y_inv, 5, TEST_SCALE, 1000).unwrap();
+
+        assert!(result.vwap_px < peg);
+        assert!(result.vwap_px > 59_000 * TEST_SCALE);
+        assert_eq!(result.new_x, x_inv + TEST_SCALE);
+        assert_eq!(result.new_y, y_inv - result.quote_amount);
+    }
+
+    #[test]
+    fn test_pegged_depth_sets_slippage() {
+        let x_inv = 10_000 * TEST_SCALE;
+        let y_inv = 600_000_000 * TEST_SCALE;
+        let peg = 60_000 * TEST_SCALE;
+
+        let shallow = quote_buy_pegged(peg, 100 * TEST_SCALE, x_inv, y_inv, 5, 10 * TEST_SCALE, 1000).unwrap();
+        let deep = quote_buy_pegged(peg, 10_000 * TEST_SCALE, x_inv, y_inv, 5, 10 * TEST_SCALE, 1000).unwrap();
+
+        // Deeper virtual liquidity means less slippage around the peg
+        assert!(deep.vwap_px < shallow.vwap_px);
+    }
+
+    #[test]
+    fn test_pegged_limited_by_inventory() {
+        let peg = 60_000 * TEST_SCALE;
+        let depth = 1000 * TEST_SCALE;
+
+        // Curve could quote 10 contracts, but inventory only holds 5
+        let result = quote_buy_pegged(peg, depth, 5 * TEST_SCALE, 60_000_000 * TEST_SCALE, 5, 10 * TEST_SCALE, 1000);
+        assert_eq!(result, Err(PercolatorError::InsufficientLiquidity));
+
+        // Same on the sell side when quote inventory is short
+        let result = quote_sell_pegged(peg, depth, 1000 * TEST_SCALE, 1000 * TEST_SCALE, 5, 10 * TEST_SCALE, 1000);
+        assert_eq!(result, Err(PercolatorError::InsufficientLiquidity));
+    }
+
+    #[test]
+    fn test_pegged_invalid_peg() {
+        let x_inv = 1000 * TEST_SCALE;
+        let y_inv = 60_000_000 * TEST_SCALE;
+
+        assert!(quote_buy_pegged(0, 1000 * TEST_SCALE, x_inv, y_inv, 5, TEST_SCALE, 1000).is_err());
+        assert!(quote_sell_pegged(60_000 * TEST_SCALE, 0, x_inv, y_inv, 5, TEST_SCALE, 1000).is_err());
+    }
+
+    #[test]
+    fn test_isqrt() {
+        assert_eq!(isqrt_u128(0), 0);
+        assert_eq!(isqrt_u128(1), 1);
+        assert_eq!(isqrt_u128(15), 3);
+        assert_eq!(isqrt_u128(16), 4);
+        assert_eq!(isqrt_u128(1_000_000_000_000), 1_000_000);
+        assert_eq!(isqrt_u128(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn test_sqrt_px_round_trip() {
+        let px = 60_000 * TEST_SCALE;
+        let sqrt_px = sqrt_px_from_px(px);
+
+        // √60000 ≈ 244.948974
+        assert_eq!(sqrt_px, 244_948_974);
+
+        // Round trip loses at most a few units of the last digit
+        let back = px_from_sqrt_px(sqrt_px);
+        assert!((px - back).abs() < 1_000);
+    }
+
+    #[test]
+    fn test_range_deltas_consistent() {
+        let liquidity = 1_000_000_000_000u128;
+        let sqrt_a = sqrt_px_from_px(55_000 * TEST_SCALE);
+        let sqrt_p = sqrt_px_from_px(60_000 * TEST_SCALE);
+        let sqrt_b = sqrt_px_from_px(65_000 * TEST_SCALE);
+
+        // Removing all base above the price lands on the upper bound
+        let dx = base_delta(liquidity, sqrt_p, sqrt_b).unwrap();
+        let end = sqrt_px_after_base_out(liquidity, sqrt_p, dx).unwrap();
+        assert!(end.abs_diff(sqrt_b) <= 1);
+
+        // Adding all base below the price lands on the lower bound
+        let dx = base_delta(liquidity, sqrt_a, sqrt_p).unwrap();
+        let end = sqrt_px_after_base_in(liquidity, sqrt_p, dx).unwrap();
+        assert!(end.abs_diff(sqrt_a) <= 1);
+
+        // Average price of the quote/base swapped stays inside the range
+        let dy = quote_delta(liquidity, sqrt_p, sqrt_b).unwrap();
+        let dx = base_delta(liquidity, sqrt_p, sqrt_b).unwrap();
+        let avg_px = (dy * SCALE as u128 / dx) as i64;
+        assert!(avg_px > 60_000 * TEST_SCALE && avg_px < 65_000 * TEST_SCALE);
+    }
+
+    #[test]
+    fn test_move_bps() {
+        assert_eq!(move_bps(0, 60_000 * TEST_SCALE), 0);
+        assert_eq!(move_bps(60_000 * TEST_SCALE, 60_600 * TEST_SCALE), 100);
+        assert_eq!(move_bps(60_000 * TEST_SCALE, 59_400 * TEST_SCALE), 100);
+    }
+
+    #[test]
+    fn test_ema_update() {
+        // 20% weight on the newest sample
+        assert_eq!(ema_update(0, 100, 2_000), 20);
+        assert_eq!(ema_update(20, 100, 2_000), 36);
+
+        // Decays back towards calm samples
+        assert_eq!(ema_update(100, 0, 2_000), 80);
+    }
+
+    #[test]
+    fn test_dynamic_fee_bps() {
+        assert_eq!(dynamic_fee_bps(5, 50, 0, 100), 5);
+        assert_eq!(dynamic_fee_bps(5, 50, 50, 100), 27);
+        assert_eq!(dynamic_fee_bps(5, 50, 100, 100), 50);
+
+        // Clamped at max beyond saturation
+        assert_eq!(dynamic_fee_bps(5, 50, 1_000, 100), 50);
+
+        // Disabled when min == max
+        assert_eq!(dynamic_fee_bps(5, 5, 1_000, 100), 5);
+    }
 }
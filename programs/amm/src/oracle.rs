@@ -0,0 +1,77 @@
+//! PriceOracle reader for oracle-pegged pools
+//!
+//! Parses the oracle program's account layout directly (like the router's
+//! custom adapter) so the AMM does not link the oracle program.
+
+use percolator_common::PercolatorError;
+use pinocchio::account_info::AccountInfo;
+
+/// PriceOracle layout (from programs/oracle/src/state.rs):
+/// magic [0..8], version [8], bump [9], authority [16..48],
+/// instrument [48..80], price [80..88], timestamp [88..96], confidence [96..104]
+const ORACLE_SIZE: usize = 128;
+const MAGIC: &[u8; 8] = b"PRCLORCL";
+const PRICE_OFFSET: usize = 80;
+const TIMESTAMP_OFFSET: usize = 88;
+
+/// Oldest oracle update a pool will peg or price fees against (seconds)
+pub const MAX_ORACLE_AGE_SECS: i64 = 60;
+
+/// Read the current price (1e6 scale) from a PriceOracle account
+///
+/// Rejects prices published more than `MAX_ORACLE_AGE_SECS` before `now`
+/// (unix seconds, from the Clock sysvar).
+pub fn read_oracle_price(oracle_account: &AccountInfo, now: i64) -> Result<i64, PercolatorError> {
+    let data = oracle_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+
+    if data.len() != ORACLE_SIZE || &data[0..8] != MAGIC {
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let price = i64::from_le_bytes(
+        data[PRICE_OFFSET..PRICE_OFFSET + 8]
+            .try_into()
+            .map_err(|_| PercolatorError::InvalidAccount)?,
+    );
+
+    if price <= 0 {
+        return Err(PercolatorError::InvalidPrice);
+    }
+
+    let timestamp = i64::from_le_bytes(
+        data[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8]
+            .try_into()
+            .map_err(|_| PercolatorError::InvalidAccount)?,
+    );
+    if now.saturating_sub(timestamp) > MAX_ORACLE_AGE_SECS {
+        return Err(PercolatorError::StalePrice);
+    }
+
+    Ok(price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use percolator_common::test_accounts::TestAccount;
+
+    fn oracle(price: i64, timestamp: i64) -> TestAccount {
+        let mut data = [0u8; ORACLE_SIZE];
+        data[0..8].copy_from_slice(MAGIC);
+        data[PRICE_OFFSET..PRICE_OFFSET + 8].copy_from_slice(&price.to_le_bytes());
+        data[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8].copy_from_slice(&timestamp.to_le_bytes());
+        TestAccount::new([5; 32], [6; 32], &data)
+    }
+
+    #[test]
+    fn test_stale_oracle_rejected() {
+        let now = 1_700_000_000;
+        let mut fresh = oracle(50_000_000_000, now - MAX_ORACLE_AGE_SECS);
+        assert_eq!(read_oracle_price(&fresh.info(), now), Ok(50_000_000_000));
+
+        let mut stale = oracle(50_000_000_000, now - MAX_ORACLE_AGE_SECS - 1);
+        assert_eq!(read_oracle_price(&stale.info(), now), Err(PercolatorError::StalePrice));
+    }
+}
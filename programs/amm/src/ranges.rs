@@ -0,0 +1,625 @@
+//! Concentrated-liquidity ranges
+//!
+//! Each range holds liquidity L between a lower and upper price. Inside a
+//! range the curve behaves like x·y=L² on virtual reserves, so LPs can place
+//! depth only near the index instead of across the full price axis.
+//!
+//! Prices are tracked as sqrt-prices (√px scaled by SCALE). Per-range
+//! reserves are a function of L and the current sqrt-price and are
+//! recomputed whenever the price moves.
+
+use crate::math::{
+    base_delta, px_from_sqrt_px, quote_delta, sqrt_px_after_base_in, sqrt_px_after_base_out,
+    sqrt_px_from_px, QuoteResult, SCALE,
+};
+use percolator_common::{PercolatorError, QuoteLevel, Side};
+use pinocchio::pubkey::Pubkey;
+
+/// Maximum number of liquidity ranges per pool
+pub const MAX_RANGES: usize = 16;
+
+/// Upper bound on range boundaries crossed in a single fill
+const MAX_CROSSINGS: usize = 2 * MAX_RANGES + 1;
+
+/// Basis points scale (10,000 bps = 100%)
+const BPS_SCALE: u128 = 10_000;
+
+/// A tick-ranged liquidity position
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LiquidityRange {
+    /// LP that owns this range
+    pub owner: Pubkey,
+    /// Lower price bound (1e6 scale, tick-aligned)
+    pub lower_px: i64,
+    /// Upper price bound (1e6 scale, tick-aligned)
+    pub upper_px: i64,
+    /// √lower_px (scaled by SCALE)
+    pub sqrt_lower: u128,
+    /// √upper_px (scaled by SCALE)
+    pub sqrt_upper: u128,
+    /// Liquidity L
+    pub liquidity: u128,
+    /// Base reserve held by this range at the current price (1e6 scale)
+    pub x_reserve: i64,
+    /// Quote reserve held by this range at the current price (1e6 scale)
+    pub y_reserve: i64,
+    /// Fees earned in base (1e6 scale)
+    pub fees_base: i64,
+    /// Fees earned in quote (1e6 scale)
+    pub fees_quote: i64,
+    /// Active flag
+    pub active: bool,
+    /// Padding
+    pub _padding: [u8; 15],
+}
+
+impl LiquidityRange {
+    /// Whether the segment just above `sqrt_px` lies inside this range
+    fn covers_up(&self, sqrt_px: u128) -> bool {
+        self.active && self.sqrt_lower <= sqrt_px && sqrt_px < self.sqrt_upper
+    }
+
+    /// Whether the segment just below `sqrt_px` lies inside this range
+    fn covers_down(&self, sqrt_px: u128) -> bool {
+        self.active && self.sqrt_lower < sqrt_px && sqrt_px <= self.sqrt_upper
+    }
+
+    /// Base and quote held by this range at `sqrt_px`
+    pub fn amounts_at(&self, sqrt_px: u128) -> Result<(i64, i64), PercolatorError> {
+        let (x, y) = if sqrt_px <= self.sqrt_lower {
+            // Below range: all base
+            (base_delta(self.liquidity, self.sqrt_lower, self.sqrt_upper)?, 0)
+        } else if sqrt_px >= self.sqrt_upper {
+            // Above range: all quote
+            (0, quote_delta(self.liquidity, self.sqrt_lower, self.sqrt_upper)?)
+        } else {
+            (
+                base_delta(self.liquidity, sqrt_px, self.sqrt_upper)?,
+                quote_delta(self.liquidity, self.sqrt_lower, sqrt_px)?,
+            )
+        };
+
+        if x > i64::MAX as u128 || y > i64::MAX as u128 {
+            return Err(PercolatorError::Overflow);
+        }
+        Ok((x as i64, y as i64))
+    }
+}
+
+/// Outcome of walking a fill across the active ranges
+#[derive(Debug, Clone, Copy)]
+pub struct RangeSwap {
+    /// Quote paid (buy, incl. fee) or received (sell)
+    pub quote_amount: i64,
+    /// Volume-weighted average price (1e6 scale)
+    pub vwap_px: i64,
+    /// Sqrt-price after the fill
+    pub end_sqrt_px: u128,
+    /// Sqrt-price before the fill (fee attribution)
+    pub start_sqrt_px: u128,
+    /// Fee retained in base (sell side)
+    pub fee_base: i64,
+    /// Fee retained in quote (buy side)
+    pub fee_quote: i64,
+}
+
+/// Concentrated-liquidity book: current price plus the LP ranges
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RangeBook {
+    /// Current sqrt-price (√px scaled by SCALE)
+    pub sqrt_px: u128,
+    /// Number of range slots in use (active or not)
+    pub range_count: u16,
+    /// Padding
+    pub _padding: [u8; 14],
+    /// Liquidity ranges
+    pub ranges: [LiquidityRange; MAX_RANGES],
+}
+
+impl RangeBook {
+    /// Create an empty range book at `px`
+    pub fn new(px: i64) -> Self {
+        let mut book: Self = unsafe { core::mem::zeroed() };
+        book.sqrt_px = sqrt_px_from_px(px);
+        book
+    }
+
+    /// Current price (1e6 scale)
+    pub fn price(&self) -> i64 {
+        px_from_sqrt_px(self.sqrt_px)
+    }
+
+    /// Add a liquidity range
+    ///
+    /// Returns the range slot index. Reserves are set from L at the
+    /// current price.
+    pub fn add_range(
+        &mut self,
+        owner: Pubkey,
+        lower_px: i64,
+        upper_px: i64,
+        liquidity: u128,
+    ) -> Result<u16, PercolatorError> {
+        if lower_px <= 0 || upper_px <= lower_px {
+            return Err(PercolatorError::InvalidPrice);
+        }
+        if liquidity == 0 {
+            return Err(PercolatorError::InvalidQuantity);
+        }
+
+        // Reuse an inactive slot before growing
+        let idx = match (0..self.range_count as usize).find(|&i| !self.ranges[i].active) {
+            Some(i) => i,
+            None => {
+                if self.range_count as usize >= MAX_RANGES {
+                    return Err(PercolatorError::PoolFull);
+                }
+                self.range_count += 1;
+                self.range_count as usize - 1
+            }
+        };
+
+        let mut range = LiquidityRange {
+            owner,
+            lower_px,
+            upper_px,
+            sqrt_lower: sqrt_px_from_px(lower_px),
+            sqrt_upper: sqrt_px_from_px(upper_px),
+            liquidity,
+            x_reserve: 0,
+            y_reserve: 0,
+            fees_base: 0,
+            fees_quote: 0,
+            active: true,
+            _padding: [0; 15],
+        };
+        let (x, y) = range.amounts_at(self.sqrt_px)?;
+        range.x_reserve = x;
+        range.y_reserve = y;
+
+        self.ranges[idx] = range;
+        Ok(idx as u16)
+    }
+
+    /// Remove a liquidity range owned by `owner`
+    ///
+    /// Returns (base, quote) released, including earned fees.
+    pub fn remove_range(&mut self, idx: u16, owner: &Pubkey) -> Result<(i64, i64), PercolatorError> {
+        let idx = idx as usize;
+        if idx >= self.range_count as usize || !self.ranges[idx].active {
+            return Err(PercolatorError::PositionNotFound);
+        }
+
+        let range = &mut self.ranges[idx];
+        if &range.owner != owner {
+            return Err(PercolatorError::Unauthorized);
+        }
+
+        let released = (
+            range.x_reserve + range.fees_base,
+            range.y_reserve + range.fees_quote,
+        );
+        range.active = false;
+        range.liquidity = 0;
+        range.x_reserve = 0;
+        range.y_reserve = 0;
+        range.fees_base = 0;
+        range.fees_quote = 0;
+
+        Ok(released)
+    }
+
+    /// Total base and quote held across active ranges
+    pub fn total_reserves(&self) -> (i64, i64) {
+        let mut x = 0i64;
+        let mut y = 0i64;
+        for range in self.ranges[..self.range_count as usize].iter().filter(|r| r.active) {
+            x = x.saturating_add(range.x_reserve);
+            y = y.saturating_add(range.y_reserve);
+        }
+        (x, y)
+    }
+
+    /// Active liquidity and next boundary above `sqrt_px`
+    fn step_up(&self, sqrt_px: u128) -> (u128, Option<u128>) {
+        let mut liquidity = 0u128;
+        let mut next: Option<u128> = None;
+        for range in self.ranges[..self.range_count as usize].iter().filter(|r| r.active) {
+            if range.covers_up(sqrt_px) {
+                liquidity += range.liquidity;
+            }
+            for bound in [range.sqrt_lower, range.sqrt_upper] {
+                if bound > sqrt_px && next.is_none_or(|n| bound < n) {
+                    next = Some(bound);
+                }
+            }
+        }
+        (liquidity, next)
+    }
+
+    /// Active liquidity and next boundary below `sqrt_px`
+    fn step_down(&self, sqrt_px: u128) -> (u128, Option<u128>) {
+        let mut liquidity = 0u128;
+        let mut next: Option<u128> = None;
+        for range in self.ranges[..self.range_count as usize].iter().filter(|r| r.active) {
+            if range.covers_down(sqrt_px) {
+                liquidity += range.liquidity;
+            }
+            for bound in [range.sqrt_lower, range.sqrt_upper] {
+                if bound < sqrt_px && next.is_none_or(|n| bound > n) {
+                    next = Some(bound);
+                }
+            }
+        }
+        (liquidity, next)
+    }
+
+    /// Walk a fill of `qty` contracts across ranges without mutating state
+    ///
+    /// `side` is the taker side. Buy moves price up through range
+    /// boundaries, Sell moves it down. Empty gaps between ranges are
+    /// skipped; running out of boundaries means insufficient liquidity.
+    pub fn simulate(&self, side: Side, qty: i64, fee_bps: i64) -> Result<RangeSwap, PercolatorError> {
+        if qty <= 0 {
+            return Err(PercolatorError::InvalidQuantity);
+        }
+        if self.sqrt_px == 0 {
+            return Err(PercolatorError::InsufficientLiquidity);
+        }
+
+        let fee_bps = fee_bps.max(0) as u128;
+        let qty_u = qty as u128;
+        let mut sqrt_px = self.sqrt_px;
+        let mut quote_total = 0u128;
+
+        // Sell: fee is taken from the base coming in
+        let mut remaining = match side {
+            Side::Buy => qty_u,
+            Side::Sell => qty_u * (BPS_SCALE - fee_bps) / BPS_SCALE,
+        };
+
+        for _ in 0..MAX_CROSSINGS {
+            if remaining == 0 {
+                break;
+            }
+
+            let (liquidity, next) = match side {
+                Side::Buy => self.step_up(sqrt_px),
+                Side::Sell => self.step_down(sqrt_px),
+            };
+            let Some(next) = next else {
+                return Err(PercolatorError::InsufficientLiquidity);
+            };
+
+            if liquidity == 0 {
+                // Gap between ranges: jump to the next boundary
+                sqrt_px = next;
+                continue;
+            }
+
+            match side {
+                Side::Buy => {
+                    let avail = base_delta(liquidity, sqrt_px, next)?;
+                    if remaining < avail {
+                        let end = sqrt_px_after_base_out(liquidity, sqrt_px, remaining)?.min(next);
+                        quote_total += quote_delta(liquidity, sqrt_px, end)?;
+                        sqrt_px = end;
+                        remaining = 0;
+                    } else {
+                        quote_total += quote_delta(liquidity, sqrt_px, next)?;
+                        sqrt_px = next;
+                        remaining -= avail;
+                    }
+                }
+                Side::Sell => {
+                    let room = base_delta(liquidity, next, sqrt_px)?;
+                    if remaining < room {
+                        let end = sqrt_px_after_base_in(liquidity, sqrt_px, remaining)?.max(next);
+                        quote_total += quote_delta(liquidity, end, sqrt_px)?;
+                        sqrt_px = end;
+                        remaining = 0;
+                    } else {
+                        quote_total += quote_delta(liquidity, next, sqrt_px)?;
+                        sqrt_px = next;
+                        remaining -= room;
+                    }
+                }
+            }
+        }
+
+        if remaining > 0 || quote_total == 0 {
+            return Err(PercolatorError::InsufficientLiquidity);
+        }
+
+        // Buy: fee is added on top of the quote paid in
+        let (quote_amount, fee_base, fee_quote) = match side {
+            Side::Buy => {
+                let gross = quote_total * BPS_SCALE / (BPS_SCALE - fee_bps);
+                (gross, 0, gross - quote_total)
+            }
+            Side::Sell => (quote_total, qty_u - qty_u * (BPS_SCALE - fee_bps) / BPS_SCALE, 0),
+        };
+
+        if quote_amount > i64::MAX as u128 {
+            return Err(PercolatorError::Overflow);
+        }
+
+        Ok(RangeSwap {
+            quote_amount: quote_amount as i64,
+            vwap_px: (quote_amount * SCALE as u128 / qty_u) as i64,
+            end_sqrt_px: sqrt_px,
+            start_sqrt_px: self.sqrt_px,
+            fee_base: fee_base as i64,
+            fee_quote: fee_quote as i64,
+        })
+    }
+
+    /// Quote a fill as a QuoteResult (new_x/new_y are pool totals after the fill)
+    pub fn quote(&self, side: Side, qty: i64, fee_bps: i64) -> Result<QuoteResult, PercolatorError> {
+        let swap = self.simulate(side, qty, fee_bps)?;
+
+        let mut new_x = 0i64;
+        let mut new_y = 0i64;
+        for range in self.ranges[..self.range_count as usize].iter().filter(|r| r.active) {
+            let (x, y) = range.amounts_at(swap.end_sqrt_px)?;
+            new_x = new_x.saturating_add(x);
+            new_y = new_y.saturating_add(y);
+        }
+
+        Ok(QuoteResult {
+            quote_amount: swap.quote_amount,
+            vwap_px: swap.vwap_px,
+            new_x,
+            new_y,
+        })
+    }
+
+    /// Apply a simulated fill: move the price, crediting fees to the ranges
+    /// active at the start price pro rata to liquidity, and recompute reserves
+    pub fn commit(&mut self, side: Side, swap: &RangeSwap) -> Result<(), PercolatorError> {
+        let (start_liquidity, _) = match side {
+            Side::Buy => self.step_up(swap.start_sqrt_px),
+            Side::Sell => self.step_down(swap.start_sqrt_px),
+        };
+
+        let count = self.range_count as usize;
+        for range in self.ranges[..count].iter_mut().filter(|r| r.active) {
+            let covers = match side {
+                Side::Buy => range.covers_up(swap.start_sqrt_px),
+                Side::Sell => range.covers_down(swap.start_sqrt_px),
+            };
+            if covers && start_liquidity > 0 {
+                range.fees_base += (swap.fee_base as u128 * range.liquidity / start_liquidity) as i64;
+                range.fees_quote += (swap.fee_quote as u128 * range.liquidity / start_liquidity) as i64;
+            }
+
+            let (x, y) = range.amounts_at(swap.end_sqrt_px)?;
+            range.x_reserve = x;
+            range.y_reserve = y;
+        }
+
+        self.sqrt_px = swap.end_sqrt_px;
+        Ok(())
+    }
+
+    /// Build quote levels from the active ranges
+    ///
+    /// Each level is one segment between consecutive range boundaries
+    /// (gaps skipped), priced at that segment's VWAP including fee.
+    pub fn quote_levels(&self, fee_bps: i64) -> ([QuoteLevel; 4], [QuoteLevel; 4]) {
+        let fee_bps = fee_bps.max(0) as u128;
+        let mut bids = [QuoteLevel::default(); 4];
+        let mut asks = [QuoteLevel::default(); 4];
+
+        // Asks: walk up from the current price
+        let mut sqrt_px = self.sqrt_px;
+        let mut level = 0;
+        for _ in 0..MAX_CROSSINGS {
+            if level == asks.len() {
+                break;
+            }
+            let (liquidity, Some(next)) = self.step_up(sqrt_px) else {
+                break;
+            };
+            if liquidity > 0 {
+                if let (Ok(base), Ok(quote)) = (
+                    base_delta(liquidity, sqrt_px, next),
+                    quote_delta(liquidity, sqrt_px, next),
+                ) {
+                    let gross = quote * BPS_SCALE / (BPS_SCALE - fee_bps);
+                    if let Some(px) = (gross * SCALE as u128).checked_div(base) {
+                        asks[level] = QuoteLevel {
+                            px: px as i64,
+                            avail_qty: base as i64,
+                        };
+                        level += 1;
+                    }
+                }
+            }
+            sqrt_px = next;
+        }
+
+        // Bids: walk down from the current price
+        let mut sqrt_px = self.sqrt_px;
+        let mut level = 0;
+        for _ in 0..MAX_CROSSINGS {
+            if level == bids.len() {
+                break;
+            }
+            let (liquidity, Some(next)) = self.step_down(sqrt_px) else {
+                break;
+            };
+            if liquidity > 0 {
+                if let (Ok(base), Ok(quote)) = (
+                    base_delta(liquidity, next, sqrt_px),
+                    quote_delta(liquidity, next, sqrt_px),
+                ) {
+                    // Taker must send base grossed up for the fee
+                    let base_in = base * BPS_SCALE / (BPS_SCALE - fee_bps);
+                    if let Some(px) = (quote * SCALE as u128).checked_div(base_in) {
+                        bids[level] = QuoteLevel {
+                            px: px as i64,
+                            avail_qty: base_in as i64,
+                        };
+                        level += 1;
+                    }
+                }
+            }
+            sqrt_px = next;
+        }
+
+        (bids, asks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: i64 = SCALE;
+    const L: u128 = 1_000_000_000_000;
+
+    fn owner() -> Pubkey {
+        Pubkey::from([9; 32])
+    }
+
+    #[test]
+    fn test_add_range_reserves() {
+        let mut book = RangeBook::new(60_000 * S);
+
+        // Straddling the price: holds both base and quote
+        let idx = book.add_range(owner(), 55_000 * S, 65_000 * S, L).unwrap();
+        let range = book.ranges[idx as usize];
+        assert!(range.x_reserve > 0);
+        assert!(range.y_reserve > 0);
+
+        // Entirely above the price: base only
+        let idx = book.add_range(owner(), 70_000 * S, 80_000 * S, L).unwrap();
+        let range = book.ranges[idx as usize];
+        assert!(range.x_reserve > 0);
+        assert_eq!(range.y_reserve, 0);
+
+        // Entirely below the price: quote only
+        let idx = book.add_range(owner(), 40_000 * S, 50_000 * S, L).unwrap();
+        let range = book.ranges[idx as usize];
+        assert_eq!(range.x_reserve, 0);
+        assert!(range.y_reserve > 0);
+    }
+
+    #[test]
+    fn test_add_range_validation() {
+        let mut book = RangeBook::new(60_000 * S);
+
+        assert!(book.add_range(owner(), 65_000 * S, 55_000 * S, L).is_err());
+        assert!(book.add_range(owner(), 0, 55_000 * S, L).is_err());
+        assert!(book.add_range(owner(), 55_000 * S, 65_000 * S, 0).is_err());
+
+        for _ in 0..MAX_RANGES {
+            book.add_range(owner(), 55_000 * S, 65_000 * S, L).unwrap();
+        }
+        assert_eq!(
+            book.add_range(owner(), 55_000 * S, 65_000 * S, L),
+            Err(PercolatorError::PoolFull)
+        );
+    }
+
+    #[test]
+    fn test_buy_within_range() {
+        let mut book = RangeBook::new(60_000 * S);
+        book.add_range(owner(), 55_000 * S, 65_000 * S, L).unwrap();
+
+        let swap = book.simulate(Side::Buy, S, 5).unwrap();
+        assert!(swap.vwap_px > 60_000 * S);
+        assert!(swap.end_sqrt_px > book.sqrt_px);
+
+        book.commit(Side::Buy, &swap).unwrap();
+        assert!(book.price() > 60_000 * S);
+        assert!(book.price() < 65_000 * S);
+        assert!(book.ranges[0].fees_quote > 0);
+    }
+
+    #[test]
+    fn test_sell_within_range() {
+        let mut book = RangeBook::new(60_000 * S);
+        book.add_range(owner(), 55_000 * S, 65_000 * S, L).unwrap();
+
+        let swap = book.simulate(Side::Sell, S, 5).unwrap();
+        assert!(swap.vwap_px < 60_000 * S);
+
+        book.commit(Side::Sell, &swap).unwrap();
+        assert!(book.price() < 60_000 * S);
+        assert!(book.price() > 55_000 * S);
+        assert!(book.ranges[0].fees_base > 0);
+    }
+
+    #[test]
+    fn test_buy_crosses_into_next_range() {
+        let mut book = RangeBook::new(60_000 * S);
+        book.add_range(owner(), 59_000 * S, 61_000 * S, L).unwrap();
+        book.add_range(owner(), 62_000 * S, 70_000 * S, L).unwrap();
+
+        // More than the first range holds above the price
+        let first_avail = book.ranges[0].x_reserve;
+        let swap = book.simulate(Side::Buy, first_avail + S, 0).unwrap();
+
+        // Ended inside the second range, past the gap
+        book.commit(Side::Buy, &swap).unwrap();
+        assert!(book.price() > 62_000 * S);
+
+        // First range is now entirely quote
+        assert_eq!(book.ranges[0].x_reserve, 0);
+        assert!(book.ranges[0].y_reserve > 0);
+    }
+
+    #[test]
+    fn test_insufficient_liquidity_past_last_range() {
+        let mut book = RangeBook::new(60_000 * S);
+        book.add_range(owner(), 59_000 * S, 61_000 * S, L).unwrap();
+
+        let total_base = book.ranges[0].x_reserve;
+        assert_eq!(
+            book.simulate(Side::Buy, total_base + S, 0).unwrap_err(),
+            PercolatorError::InsufficientLiquidity
+        );
+    }
+
+    #[test]
+    fn test_quote_levels_from_ranges() {
+        let mut book = RangeBook::new(60_000 * S);
+        book.add_range(owner(), 59_000 * S, 61_000 * S, L).unwrap();
+        book.add_range(owner(), 60_000 * S, 62_000 * S, L).unwrap();
+
+        let (bids, asks) = book.quote_levels(5);
+
+        // Asks: [60k, 61k) with both ranges, then [61k, 62k) with one
+        assert!(asks[0].avail_qty > 0 && asks[1].avail_qty > 0);
+        assert!(asks[0].avail_qty > asks[1].avail_qty);
+        assert!(asks[0].px > 60_000 * S && asks[0].px < asks[1].px);
+        assert_eq!(asks[2].avail_qty, 0);
+
+        // Bids: only [59k, 60k) from the first range
+        assert!(bids[0].avail_qty > 0);
+        assert!(bids[0].px < 60_000 * S);
+        assert_eq!(bids[1].avail_qty, 0);
+    }
+
+    #[test]
+    fn test_remove_range() {
+        let mut book = RangeBook::new(60_000 * S);
+        let idx = book.add_range(owner(), 55_000 * S, 65_000 * S, L).unwrap();
+        let (x, y) = book.total_reserves();
+
+        assert_eq!(
+            book.remove_range(idx, &Pubkey::from([1; 32])),
+            Err(PercolatorError::Unauthorized)
+        );
+        assert_eq!(book.remove_range(idx, &owner()), Ok((x, y)));
+        assert_eq!(book.total_reserves(), (0, 0));
+
+        // Slot is reused
+        assert_eq!(book.add_range(owner(), 55_000 * S, 65_000 * S, L).unwrap(), idx);
+        assert_eq!(book.range_count, 1);
+    }
+}
@@ -1,6 +1,18 @@
 //! AMM state - constant product automated market maker
 
-use percolator_common::{SlabHeader, QuoteCache};
+use percolator_common::{PercolatorError, QuoteCache, Side, SlabHeader};
+use pinocchio::pubkey::Pubkey;
+use crate::math::QuoteResult;
+use crate::ranges::RangeBook;
+
+/// Weight of the newest observation in the volatility EMA (bps)
+pub const VOL_EMA_ALPHA_BPS: i64 = 2_000;
+
+/// Volatility EMA level (bps) at which the fee reaches max_fee_bps
+pub const VOL_SATURATION_BPS: i64 = 100;
+
+/// LP shares minted to the pool's LP at Initialize (1.0 share, 1e6 scale)
+pub const INITIAL_LP_SHARES: u64 = 1_000_000;
 
 /// AMM pool state - uses same header/cache layout as orderbook slab
 /// Layout: SlabHeader (200B) + QuoteCache (136B) + AmmData (variable)
@@ -14,48 +26,405 @@ pub struct AmmState {
 
     /// AMM-specific pool data
     pub pool: AmmPool,
+
+    /// Concentrated-liquidity ranges (Concentrated mode only)
+    pub ranges: RangeBook,
+}
+
+/// AMM curve mode, selected at Initialize
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveMode {
+    /// Plain x·y=k on the pool reserves (spot floats freely)
+    ConstantProduct = 0,
+    /// Virtual x·y=k curve re-centred on the oracle price.
+    /// Reserves track LP inventory rather than price discovery.
+    OraclePegged = 1,
+    /// LP liquidity placed in tick ranges around the price.
+    /// Reserves are the sum of the active ranges.
+    Concentrated = 2,
+}
+
+impl CurveMode {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::ConstantProduct),
+            1 => Some(Self::OraclePegged),
+            2 => Some(Self::Concentrated),
+            _ => None,
+        }
+    }
+}
+
+/// How the quote-cache sample ladder is interpreted
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleMode {
+    /// Sizes are basis points of the curve base (reserves or pegged depth)
+    ReserveBps = 0,
+    /// Sizes are absolute contract quantities (1e6 scale)
+    Absolute = 1,
+}
+
+impl SampleMode {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::ReserveBps),
+            1 => Some(Self::Absolute),
+            _ => None,
+        }
+    }
 }
 
+/// Number of quote-cache sample sizes per side
+pub const SAMPLE_LEVELS: usize = 4;
+
+/// Default sample ladder: 1%, 2%, 5%, 10% of curve base
+pub const DEFAULT_SAMPLE_BPS: [i64; SAMPLE_LEVELS] = [100, 200, 500, 1_000];
+
 /// AMM pool reserves and parameters
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct AmmPool {
     /// Base reserve (x in x·y=k) - instrument contracts, scaled by SCALE
+    /// In OraclePegged mode this is base inventory held by the LP.
+    /// In Concentrated mode this is the sum over active ranges.
     pub x_reserve: i64,
 
     /// Quote reserve (y in x·y=k) - collateral/USDC, scaled by SCALE
+    /// In OraclePegged mode this is quote inventory held by the LP.
     pub y_reserve: i64,
 
     /// Fee in basis points (e.g., 5 = 0.05%)
+    /// With dynamic fees this is the current effective fee.
     pub fee_bps: i64,
 
     /// Minimum liquidity floor (prevents draining pool completely)
     pub min_liquidity: i64,
 
-    /// Padding for future use
-    pub _padding: [u64; 4],
+    /// Curve mode (CurveMode as u8)
+    pub curve_mode: u8,
+
+    /// Padding for alignment
+    pub _padding_mode: [u8; 7],
+
+    /// Virtual base depth of the pegged curve (contracts, scaled by SCALE)
+    /// Sets slippage around the peg; unused in ConstantProduct mode
+    pub liquidity_depth: i64,
+
+    /// Oracle price the pegged curve is currently centred on (1e6 scale)
+    pub peg_px: i64,
+
+    /// PriceOracle account the pool pegs to (default in ConstantProduct mode)
+    pub oracle: Pubkey,
+
+    /// Dynamic fee floor in bps (dynamic fees disabled when max <= min)
+    pub min_fee_bps: u16,
+
+    /// Dynamic fee ceiling in bps
+    pub max_fee_bps: u16,
+
+    /// Padding for alignment
+    pub _padding_fee: [u8; 4],
+
+    /// Short EMA of realised price moves and oracle deviation (bps)
+    pub vol_ema_bps: i64,
+
+    /// Spot price at the previous fill (volatility reference)
+    pub last_fill_px: i64,
+
+    /// Total LP shares outstanding, 1e6 scale (router values LP buckets pro rata)
+    pub total_lp_shares: u64,
+
+    /// Quote-cache sample mode (SampleMode as u8)
+    pub sample_mode: u8,
+
+    /// Padding for alignment
+    pub _padding_sample: [u8; 7],
+
+    /// Quote-cache sample sizes, ascending (bps of curve base or absolute qty)
+    pub sample_sizes: [i64; SAMPLE_LEVELS],
 }
 
 impl AmmState {
     pub const LEN: usize = core::mem::size_of::<Self>();
 
+    /// Pool account layout version, stored in header.version
+    ///
+    /// 2: dynamic fee, LP share and sample ladder fields outgrew the v1
+    /// reserved padding; v1 pools are rejected rather than misread.
+    pub const VERSION: u32 = 2;
+
     /// Create new AMM state
     pub fn new(header: SlabHeader, x_reserve: i64, y_reserve: i64, fee_bps: i64) -> Self {
         Self {
-            header,
+            header: SlabHeader { version: Self::VERSION, ..header },
             quote_cache: QuoteCache::new(),
             pool: AmmPool {
                 x_reserve,
                 y_reserve,
                 fee_bps,
                 min_liquidity: 1000, // 0.001 contracts minimum
-                _padding: [0; 4],
+                curve_mode: CurveMode::ConstantProduct as u8,
+                _padding_mode: [0; 7],
+                liquidity_depth: 0,
+                peg_px: 0,
+                oracle: Pubkey::default(),
+                min_fee_bps: 0,
+                max_fee_bps: 0,
+                _padding_fee: [0; 4],
+                vol_ema_bps: 0,
+                last_fill_px: 0,
+                total_lp_shares: INITIAL_LP_SHARES,
+                sample_mode: SampleMode::ReserveBps as u8,
+                _padding_sample: [0; 7],
+                sample_sizes: DEFAULT_SAMPLE_BPS,
             },
+            ranges: RangeBook::new(0),
         }
     }
 
-    /// Get spot price: p = y/x (scaled)
+    /// Create new oracle-pegged AMM state
+    ///
+    /// The curve is centred on `peg_px` with `liquidity_depth` virtual base
+    /// reserves; `x_reserve` / `y_reserve` are the LP's starting inventory.
+    pub fn new_pegged(
+        header: SlabHeader,
+        x_reserve: i64,
+        y_reserve: i64,
+        fee_bps: i64,
+        oracle: Pubkey,
+        peg_px: i64,
+        liquidity_depth: i64,
+    ) -> Self {
+        let mut amm = Self::new(header, x_reserve, y_reserve, fee_bps);
+        amm.pool.curve_mode = CurveMode::OraclePegged as u8;
+        amm.pool.oracle = oracle;
+        amm.pool.liquidity_depth = liquidity_depth;
+        amm.repeg(peg_px);
+        amm
+    }
+
+    /// Create new concentrated-liquidity AMM state
+    ///
+    /// The pool starts empty at `start_px`; liquidity arrives via AddRange.
+    pub fn new_concentrated(header: SlabHeader, fee_bps: i64, start_px: i64) -> Self {
+        let mut amm = Self::new(header, 0, 0, fee_bps);
+        amm.pool.curve_mode = CurveMode::Concentrated as u8;
+        amm.ranges = RangeBook::new(start_px);
+        amm
+    }
+
+    /// Enable volatility-responsive fees between `min_fee_bps` and `max_fee_bps`
+    ///
+    /// The pool starts at the floor; `fee_bps` then tracks the volatility EMA.
+    pub fn set_dynamic_fees(&mut self, min_fee_bps: u16, max_fee_bps: u16) {
+        self.pool.min_fee_bps = min_fee_bps;
+        self.pool.max_fee_bps = max_fee_bps;
+        if self.dynamic_fees() {
+            self.pool.fee_bps = min_fee_bps as i64;
+        }
+    }
+
+    /// Whether the fee scales with volatility
+    pub fn dynamic_fees(&self) -> bool {
+        self.pool.max_fee_bps > self.pool.min_fee_bps
+    }
+
+    /// Fold the latest price observations into the volatility EMA and
+    /// reprice the fee
+    ///
+    /// Called once per fill, before quoting and before a pegged curve is
+    /// repegged, so the deviation term measures how far the old peg drifted.
+    /// The sample is the larger of the spot move since the previous fill
+    /// (which includes that fill's impact and any repeg since) and the
+    /// spot's deviation from `oracle_px`.
+    pub fn update_dynamic_fee(&mut self, oracle_px: Option<i64>) {
+        use crate::math::{dynamic_fee_bps, ema_update, move_bps};
+
+        if !self.dynamic_fees() {
+            return;
+        }
+
+        let spot = self.spot_price();
+        let realised = move_bps(self.pool.last_fill_px, spot);
+        let deviation = oracle_px.map_or(0, |px| move_bps(px, spot));
+
+        self.pool.vol_ema_bps = ema_update(
+            self.pool.vol_ema_bps,
+            realised.max(deviation),
+            VOL_EMA_ALPHA_BPS,
+        );
+        self.pool.fee_bps = dynamic_fee_bps(
+            self.pool.min_fee_bps as i64,
+            self.pool.max_fee_bps as i64,
+            self.pool.vol_ema_bps,
+            VOL_SATURATION_BPS,
+        );
+        self.pool.last_fill_px = spot;
+    }
+
+    /// Configure the quote-cache sample ladder
+    ///
+    /// Sizes must be positive and strictly ascending; bps sizes are capped
+    /// at 100% of the curve base.
+    pub fn set_sample_ladder(
+        &mut self,
+        mode: SampleMode,
+        sizes: [i64; SAMPLE_LEVELS],
+    ) -> Result<(), PercolatorError> {
+        if sizes[0] <= 0 || sizes.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(PercolatorError::InvalidQuantity);
+        }
+        if mode == SampleMode::ReserveBps && sizes[SAMPLE_LEVELS - 1] > 10_000 {
+            return Err(PercolatorError::InvalidQuantity);
+        }
+
+        self.pool.sample_mode = mode as u8;
+        self.pool.sample_sizes = sizes;
+        Ok(())
+    }
+
+    /// Current quote-cache sample mode
+    pub fn sample_mode(&self) -> SampleMode {
+        SampleMode::from_u8(self.pool.sample_mode).unwrap_or(SampleMode::ReserveBps)
+    }
+
+    /// Contract quantities the quote-cache ladder samples
+    fn sample_quantities(&self) -> [i64; SAMPLE_LEVELS] {
+        let mut qtys = self.pool.sample_sizes;
+        if self.sample_mode() == SampleMode::ReserveBps {
+            let base = self.curve_base();
+            for qty in qtys.iter_mut() {
+                *qty = (base as i128 * *qty as i128 / 10_000) as i64;
+            }
+        }
+        qtys
+    }
+
+    /// Current curve mode
+    pub fn curve_mode(&self) -> CurveMode {
+        CurveMode::from_u8(self.pool.curve_mode).unwrap_or(CurveMode::ConstantProduct)
+    }
+
+    /// Pre-fill pricing: reprice the fee, then re-centre a pegged curve
+    ///
+    /// The fee is updated first so its oracle deviation is measured against
+    /// the peg the curve was quoting on, not the oracle price it moves to.
+    /// The volatility reference then moves to the new peg so the repeg is
+    /// not sampled again as a realised move on the next fill.
+    pub fn reprice(&mut self, oracle_px: Option<i64>) {
+        self.update_dynamic_fee(oracle_px);
+        if let (CurveMode::OraclePegged, Some(px)) = (self.curve_mode(), oracle_px) {
+            self.repeg(px);
+            self.pool.last_fill_px = px;
+        }
+    }
+
+    /// Re-centre the pegged curve on a new oracle price
+    ///
+    /// Also refreshes header.mark_px so the router sees the peg as mark.
+    pub fn repeg(&mut self, oracle_px: i64) {
+        self.pool.peg_px = oracle_px;
+        self.header.mark_px = oracle_px;
+    }
+
+    /// Base amount the quote-cache ladder is sampled against
+    ///
+    /// Constant product: pool base reserve. Pegged: virtual curve depth.
+    /// Concentrated pools build their ladder from range segments instead.
+    fn curve_base(&self) -> i64 {
+        match self.curve_mode() {
+            CurveMode::ConstantProduct | CurveMode::Concentrated => self.pool.x_reserve,
+            CurveMode::OraclePegged => self.pool.liquidity_depth,
+        }
+    }
+
+    /// Sync pool reserves with the sum of active ranges (Concentrated mode)
+    pub fn sync_range_reserves(&mut self) {
+        let (x, y) = self.ranges.total_reserves();
+        self.pool.x_reserve = x;
+        self.pool.y_reserve = y;
+    }
+
+    /// Quote a fill of `qty` contracts against the pool's curve
+    ///
+    /// `side` is the taker side: Buy takes base from the pool, Sell gives it.
+    pub fn quote(&self, side: Side, qty: i64) -> Result<QuoteResult, PercolatorError> {
+        use crate::math::{quote_buy, quote_buy_pegged, quote_sell, quote_sell_pegged};
+
+        let pool = &self.pool;
+        match (self.curve_mode(), side) {
+            (CurveMode::ConstantProduct, Side::Buy) => {
+                quote_buy(pool.x_reserve, pool.y_reserve, pool.fee_bps, qty, pool.min_liquidity)
+            }
+            (CurveMode::ConstantProduct, Side::Sell) => {
+                quote_sell(pool.x_reserve, pool.y_reserve, pool.fee_bps, qty, pool.min_liquidity)
+            }
+            (CurveMode::OraclePegged, Side::Buy) => quote_buy_pegged(
+                pool.peg_px,
+                pool.liquidity_depth,
+                pool.x_reserve,
+                pool.y_reserve,
+                pool.fee_bps,
+                qty,
+                pool.min_liquidity,
+            ),
+            (CurveMode::OraclePegged, Side::Sell) => quote_sell_pegged(
+                pool.peg_px,
+                pool.liquidity_depth,
+                pool.x_reserve,
+                pool.y_reserve,
+                pool.fee_bps,
+                qty,
+                pool.min_liquidity,
+            ),
+            (CurveMode::Concentrated, _) => self.ranges.quote(side, qty, pool.fee_bps),
+        }
+    }
+
+    /// Execute a fill against the curve, enforcing the taker's limit price
+    ///
+    /// Updates reserves (and range state in Concentrated mode) and returns
+    /// the fill result. Nothing is mutated if the limit is breached.
+    pub fn fill(&mut self, side: Side, qty: i64, limit_px: i64) -> Result<QuoteResult, PercolatorError> {
+        let within_limit = |vwap_px: i64| match side {
+            Side::Buy => vwap_px <= limit_px,
+            Side::Sell => vwap_px >= limit_px,
+        };
+
+        if self.curve_mode() == CurveMode::Concentrated {
+            let swap = self.ranges.simulate(side, qty, self.pool.fee_bps)?;
+            if !within_limit(swap.vwap_px) {
+                return Err(PercolatorError::InvalidPrice);
+            }
+            self.ranges.commit(side, &swap)?;
+            self.sync_range_reserves();
+            return Ok(QuoteResult {
+                quote_amount: swap.quote_amount,
+                vwap_px: swap.vwap_px,
+                new_x: self.pool.x_reserve,
+                new_y: self.pool.y_reserve,
+            });
+        }
+
+        let result = self.quote(side, qty)?;
+        if !within_limit(result.vwap_px) {
+            return Err(PercolatorError::InvalidPrice);
+        }
+        self.pool.x_reserve = result.new_x;
+        self.pool.y_reserve = result.new_y;
+        Ok(result)
+    }
+
+    /// Get spot price: p = y/x (scaled), the peg in OraclePegged mode,
+    /// or the current range price in Concentrated mode
     pub fn spot_price(&self) -> i64 {
+        match self.curve_mode() {
+            CurveMode::OraclePegged => return self.pool.peg_px,
+            CurveMode::Concentrated => return self.ranges.price(),
+            CurveMode::ConstantProduct => {}
+        }
         if self.pool.x_reserve == 0 {
             return 0;
         }
@@ -64,9 +433,18 @@ impl AmmState {
     }
 
     /// Synthesize QuoteCache from AMM curve
-    /// Generates 4 bid and 4 ask levels by sampling the curve at different quantities
+    /// Generates 4 bid and 4 ask levels by sampling the curve at the pool's
+    /// configured ladder (default 1%, 2%, 5%, 10% of curve base)
+    ///
+    /// In OraclePegged mode bps sizes are taken against the virtual depth
+    /// around the peg, and levels the inventory cannot settle are left empty.
+    /// In Concentrated mode with a bps ladder each level is a segment between
+    /// range boundaries; an absolute ladder samples the ranges directly.
+    ///
+    /// Sampled levels are cumulative (quantity up to the sample at its VWAP)
+    /// and the cache is flagged `QuoteCache::CUMULATIVE` so the router can
+    /// difference them into depth. Range segments are already depth.
     pub fn synthesize_quote_cache(&mut self) {
-        use crate::math::{quote_buy, quote_sell};
         use percolator_common::QuoteLevel;
 
         let spot = self.spot_price();
@@ -75,23 +453,23 @@ impl AmmState {
             return;
         }
 
-        // Sample quantities: 1%, 2%, 5%, 10% of reserves (scaled)
-        let sample_fractions = [10_000, 20_000, 50_000, 100_000]; // in basis points (out of 1M)
+        if self.curve_mode() == CurveMode::Concentrated && self.sample_mode() == SampleMode::ReserveBps {
+            let (bids, asks) = self.ranges.quote_levels(self.pool.fee_bps);
+            self.quote_cache.update(self.header.seqno, &bids, &asks);
+            self.quote_cache.flags = 0;
+            return;
+        }
+
+        // Sample quantities from the configured ladder (scaled)
+        let sample_qtys = self.sample_quantities();
 
         let mut bids = [QuoteLevel::default(); 4];
         let mut asks = [QuoteLevel::default(); 4];
 
         // Generate ask levels (buying from AMM = selling to user)
-        for (i, &fraction_bps) in sample_fractions.iter().enumerate() {
-            let qty = (self.pool.x_reserve as i128 * fraction_bps as i128 / 1_000_000) as i64;
+        for (i, &qty) in sample_qtys.iter().enumerate() {
             if qty > 0 {
-                if let Ok(result) = quote_buy(
-                    self.pool.x_reserve,
-                    self.pool.y_reserve,
-                    self.pool.fee_bps,
-                    qty,
-                    self.pool.min_liquidity,
-                ) {
+                if let Ok(result) = self.quote(Side::Buy, qty) {
                     asks[i] = QuoteLevel {
                         px: result.vwap_px,
                         avail_qty: qty,
@@ -101,16 +479,9 @@ impl AmmState {
         }
 
         // Generate bid levels (selling to AMM = buying from user)
-        for (i, &fraction_bps) in sample_fractions.iter().enumerate() {
-            let qty = (self.pool.x_reserve as i128 * fraction_bps as i128 / 1_000_000) as i64;
+        for (i, &qty) in sample_qtys.iter().enumerate() {
             if qty > 0 {
-                if let Ok(result) = quote_sell(
-                    self.pool.x_reserve,
-                    self.pool.y_reserve,
-                    self.pool.fee_bps,
-                    qty,
-                    self.pool.min_liquidity,
-                ) {
+                if let Ok(result) = self.quote(Side::Sell, qty) {
                     bids[i] = QuoteLevel {
                         px: result.vwap_px,
                         avail_qty: qty,
@@ -121,6 +492,7 @@ impl AmmState {
 
         // Update quote cache (bids descending by price, asks ascending by price)
         self.quote_cache.update(self.header.seqno, &bids, &asks);
+        self.quote_cache.flags = QuoteCache::CUMULATIVE;
     }
 }
 
@@ -266,6 +638,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sampled_cache_is_cumulative_depth() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new(header, 1000 * 1_000_000, 60_000_000 * 1_000_000, 5);
+        amm.synthesize_quote_cache();
+
+        let cache = &amm.quote_cache;
+        assert_eq!(cache.flags, QuoteCache::CUMULATIVE);
+
+        // Differenced depth adds up to the largest sample, not the sum of samples
+        let depth = cache.depth(&cache.best_asks);
+        let total: i64 = depth.iter().map(|l| l.avail_qty).sum();
+        assert_eq!(total, cache.best_asks[3].avail_qty);
+        assert!(total < cache.total_ask_qty());
+
+        // Walking the depth costs what the curve quotes for the largest sample
+        let cost: i128 = depth.iter().map(|l| l.px as i128 * l.avail_qty as i128).sum();
+        let quoted = cache.best_asks[3].px as i128 * total as i128;
+        assert!((cost - quoted).abs() <= 4 * total as i128, "cost {} vs quoted {}", cost, quoted);
+
+        // Marginal prices worsen level by level
+        for i in 0..3 {
+            assert!(depth[i].px < depth[i + 1].px);
+        }
+    }
+
     #[test]
     fn test_quote_cache_price_impact() {
         let header = SlabHeader::new(
@@ -421,4 +829,391 @@ mod tests {
         // Spot prices should be the same (y/x ratio is the same)
         assert_eq!(small_spot, large_spot, "Spot price should be scale-independent");
     }
+
+    #[test]
+    fn test_pegged_quote_cache_centred_on_peg() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        // Inventory ratio implies 30k, but the oracle peg is 60k
+        let mut amm = AmmState::new_pegged(
+            header,
+            2000 * 1_000_000,
+            60_000_000 * 1_000_000,
+            5,
+            Pubkey::from([7; 32]),
+            60_000 * 1_000_000,
+            1000 * 1_000_000,
+        );
+        amm.synthesize_quote_cache();
+
+        assert_eq!(amm.curve_mode(), CurveMode::OraclePegged);
+        assert_eq!(amm.spot_price(), 60_000 * 1_000_000);
+        assert_eq!(amm.header.mark_px, 60_000 * 1_000_000);
+
+        let cache = &amm.quote_cache;
+        for i in 0..4 {
+            assert!(cache.best_bids[i].px < 60_000 * 1_000_000, "Bid should be below peg");
+            assert!(cache.best_asks[i].px > 60_000 * 1_000_000, "Ask should be above peg");
+        }
+
+        // Ladder is sampled against depth (1% of 1000 = 10 contracts)
+        assert_eq!(cache.best_asks[0].avail_qty, 10 * 1_000_000);
+    }
+
+    #[test]
+    fn test_pegged_repeg_moves_quotes() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new_pegged(
+            header,
+            1000 * 1_000_000,
+            60_000_000 * 1_000_000,
+            5,
+            Pubkey::from([7; 32]),
+            60_000 * 1_000_000,
+            1000 * 1_000_000,
+        );
+        amm.synthesize_quote_cache();
+        let before = amm.quote_cache;
+
+        // Inventory unchanged, oracle moves up 10%
+        amm.repeg(66_000 * 1_000_000);
+        amm.synthesize_quote_cache();
+        let after = amm.quote_cache;
+
+        assert!(after.best_asks[0].px > before.best_asks[0].px);
+        assert!(after.best_bids[0].px > before.best_bids[0].px);
+        assert!(after.best_bids[0].px < 66_000 * 1_000_000);
+        assert!(after.best_asks[0].px > 66_000 * 1_000_000);
+    }
+
+    #[test]
+    fn test_pegged_fill_moves_inventory_not_price() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new_pegged(
+            header,
+            1000 * 1_000_000,
+            60_000_000 * 1_000_000,
+            5,
+            Pubkey::from([7; 32]),
+            60_000 * 1_000_000,
+            1000 * 1_000_000,
+        );
+
+        let first = amm.quote(Side::Buy, 10 * 1_000_000).unwrap();
+        amm.pool.x_reserve = first.new_x;
+        amm.pool.y_reserve = first.new_y;
+
+        // Same peg: the next identical fill prices the same
+        let second = amm.quote(Side::Buy, 10 * 1_000_000).unwrap();
+        assert_eq!(first.vwap_px, second.vwap_px);
+
+        // LP inventory is now short 10 contracts of base
+        assert_eq!(amm.pool.x_reserve, 990 * 1_000_000);
+    }
+
+    #[test]
+    fn test_constant_product_mode_default() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let amm = AmmState::new(header, 1000 * 1_000_000, 60_000_000 * 1_000_000, 5);
+        assert_eq!(amm.curve_mode(), CurveMode::ConstantProduct);
+        assert_eq!(amm.pool.oracle, Pubkey::default());
+    }
+
+    #[test]
+    fn test_concentrated_fill_updates_ranges() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let owner = Pubkey::from([9; 32]);
+        let mut amm = AmmState::new_concentrated(header, 5, 60_000 * 1_000_000);
+        amm.ranges
+            .add_range(owner, 59_000 * 1_000_000, 61_000 * 1_000_000, 1_000_000_000_000)
+            .unwrap();
+        amm.sync_range_reserves();
+        amm.synthesize_quote_cache();
+
+        assert_eq!(amm.curve_mode(), CurveMode::Concentrated);
+        assert!(amm.quote_cache.best_asks[0].px > 60_000 * 1_000_000);
+        assert!(amm.quote_cache.best_bids[0].px < 60_000 * 1_000_000);
+
+        let x_before = amm.pool.x_reserve;
+
+        // Limit breached: state untouched
+        assert_eq!(
+            amm.fill(Side::Buy, 1_000_000, 60_000 * 1_000_000).unwrap_err(),
+            PercolatorError::InvalidPrice
+        );
+        assert_eq!(amm.pool.x_reserve, x_before);
+
+        let result = amm.fill(Side::Buy, 1_000_000, 61_000 * 1_000_000).unwrap();
+        assert!(result.vwap_px > 60_000 * 1_000_000);
+        assert!(amm.spot_price() > 60_000 * 1_000_000);
+        assert!(amm.pool.x_reserve < x_before);
+    }
+
+    #[test]
+    fn test_dynamic_fee_rises_with_volatility() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new(header, 1000 * 1_000_000, 60_000_000 * 1_000_000, 5);
+        amm.set_dynamic_fees(5, 50);
+        assert_eq!(amm.pool.fee_bps, 5);
+
+        // First fill only records the reference price
+        amm.update_dynamic_fee(None);
+        assert_eq!(amm.pool.fee_bps, 5);
+        amm.fill(Side::Buy, 20 * 1_000_000, i64::MAX).unwrap();
+
+        // ~4% move since the last fill saturates the sample
+        amm.update_dynamic_fee(None);
+        assert!(amm.pool.fee_bps > 5);
+        assert!(amm.pool.fee_bps <= 50);
+        let raised = amm.pool.fee_bps;
+
+        // Quiet fills decay the fee back towards the floor
+        for _ in 0..20 {
+            amm.update_dynamic_fee(None);
+        }
+        assert!(amm.pool.fee_bps < raised);
+        assert_eq!(amm.pool.fee_bps, 5);
+    }
+
+    #[test]
+    fn test_dynamic_fee_oracle_deviation() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new(header, 1000 * 1_000_000, 60_000_000 * 1_000_000, 5);
+        amm.set_dynamic_fees(5, 50);
+        amm.update_dynamic_fee(None);
+
+        // Spot unchanged but 2% away from the oracle
+        amm.update_dynamic_fee(Some(61_200 * 1_000_000));
+        assert!(amm.pool.vol_ema_bps > 0);
+        assert!(amm.pool.fee_bps > 5);
+    }
+
+    #[test]
+    fn test_pegged_fee_sees_pre_repeg_deviation() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new_pegged(
+            header,
+            100 * 1_000_000,
+            6_000_000 * 1_000_000,
+            5,
+            Pubkey::default(),
+            60_000 * 1_000_000,
+            1000 * 1_000_000,
+        );
+        amm.set_dynamic_fees(5, 50);
+        amm.reprice(Some(60_000 * 1_000_000));
+        assert_eq!(amm.pool.fee_bps, 5);
+
+        // Oracle moved 2%: the fee prices the stale peg, then the curve repegs
+        amm.reprice(Some(61_200 * 1_000_000));
+        assert!(amm.pool.fee_bps > 5);
+        assert_eq!(amm.pool.peg_px, 61_200 * 1_000_000);
+
+        // A quiet oracle afterwards decays the EMA: the repeg is sampled once
+        let raised = amm.pool.vol_ema_bps;
+        amm.reprice(Some(61_200 * 1_000_000));
+        assert!(amm.pool.vol_ema_bps < raised);
+    }
+
+    #[test]
+    fn test_pool_version_stamped() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let amm = AmmState::new(header, 1000 * 1_000_000, 60_000_000 * 1_000_000, 5);
+        assert_eq!(amm.header.version, AmmState::VERSION);
+        assert!(amm.header.validate());
+    }
+
+    #[test]
+    fn test_static_fee_unchanged() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new(header, 1000 * 1_000_000, 60_000_000 * 1_000_000, 5);
+        amm.update_dynamic_fee(Some(30_000 * 1_000_000));
+        assert_eq!(amm.pool.fee_bps, 5);
+        assert_eq!(amm.pool.vol_ema_bps, 0);
+    }
+
+    #[test]
+    fn test_pool_layout_offsets() {
+        use core::mem::offset_of;
+
+        // The router reads these fields directly to value AMM LP buckets
+        assert_eq!(offset_of!(AmmState, pool), SlabHeader::LEN + QuoteCache::LEN);
+        assert_eq!(offset_of!(AmmPool, x_reserve), 0);
+        assert_eq!(offset_of!(AmmPool, y_reserve), 8);
+        assert_eq!(offset_of!(AmmPool, oracle), 56);
+        assert_eq!(offset_of!(AmmPool, total_lp_shares), 112);
+    }
+
+    #[test]
+    fn test_absolute_sample_ladder() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new(header, 1000 * 1_000_000, 60_000_000 * 1_000_000, 5);
+        let sizes = [100_000, 500_000, 1_000_000, 5_000_000];
+        amm.set_sample_ladder(SampleMode::Absolute, sizes).unwrap();
+        amm.synthesize_quote_cache();
+
+        // Levels sit at the configured contract sizes, not reserve slices
+        for (i, &size) in sizes.iter().enumerate() {
+            assert_eq!(amm.quote_cache.best_asks[i].avail_qty, size);
+            assert_eq!(amm.quote_cache.best_bids[i].avail_qty, size);
+        }
+    }
+
+    #[test]
+    fn test_bps_sample_ladder() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new(header, 1000 * 1_000_000, 60_000_000 * 1_000_000, 5);
+        amm.set_sample_ladder(SampleMode::ReserveBps, [10, 25, 50, 100]).unwrap();
+        amm.synthesize_quote_cache();
+
+        // 0.1% of 1000 contracts = 1 contract
+        assert_eq!(amm.quote_cache.best_asks[0].avail_qty, 1_000_000);
+        assert_eq!(amm.quote_cache.best_asks[3].avail_qty, 10 * 1_000_000);
+    }
+
+    #[test]
+    fn test_sample_ladder_validation() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            60_000_000_000,
+            5,
+            1_000_000,
+            255,
+        );
+
+        let mut amm = AmmState::new(header, 1000 * 1_000_000, 60_000_000 * 1_000_000, 5);
+
+        // Not ascending
+        assert!(amm.set_sample_ladder(SampleMode::Absolute, [5, 4, 6, 7]).is_err());
+        // Non-positive
+        assert!(amm.set_sample_ladder(SampleMode::Absolute, [0, 4, 6, 7]).is_err());
+        // More than 100% of reserves
+        assert!(amm.set_sample_ladder(SampleMode::ReserveBps, [100, 200, 500, 20_000]).is_err());
+
+        // Rejected ladders leave the default in place
+        assert_eq!(amm.sample_mode(), SampleMode::ReserveBps);
+        assert_eq!(amm.pool.sample_sizes, DEFAULT_SAMPLE_BPS);
+    }
 }
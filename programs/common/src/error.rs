@@ -30,6 +30,17 @@ pub enum PercolatorError {
     InvalidAmount = 112,
     InsufficientBalance = 113,
     StalePrice = 114,
+    WithdrawalRateLimited = 115,
+    ProtocolPaused = 116,
+    ReduceOnly = 117,
+    InvalidFillReceipt = 118,
+    FundingNotDue = 119,
+    SlippageExceeded = 120,
+    FeeLimitExceeded = 121,
+    ExposureCapExceeded = 122,
+    OracleMisaligned = 123,
+    MatcherStale = 124,
+    MatcherNotStale = 125,
 
     // Slab errors (200-299)
     InvalidInstrument = 200,
@@ -8,7 +8,8 @@ use pinocchio::pubkey::Pubkey;
 pub struct SlabHeader {
     /// Magic bytes for validation (b"PERP10\0\0")
     pub magic: [u8; 8],
-    /// Version (=1 for v0)
+    /// Account layout version (=1 for v0; matcher programs bump it when the
+    /// layout past the header and quote cache changes)
     pub version: u32,
     /// Sequence number (incremented on any book/state change)
     pub seqno: u32,
@@ -90,8 +91,12 @@ impl SlabHeader {
     }
 
     /// Validate magic and version
+    ///
+    /// Later layout versions keep this header and the quote cache in place,
+    /// so any version from `VERSION` up is readable here. Owning programs
+    /// check their exact layout version themselves.
     pub fn validate(&self) -> bool {
-        &self.magic == Self::MAGIC && self.version == Self::VERSION
+        &self.magic == Self::MAGIC && self.version >= Self::VERSION
     }
 
     /// Increment sequence number (on any book change)
@@ -122,6 +127,10 @@ mod tests {
         assert_eq!(header.seqno, 0);
         assert_eq!(header.version, 1);
         assert_eq!(header.magic, *SlabHeader::MAGIC);
+
+        // Bumped matcher layouts still expose a readable header
+        assert!(SlabHeader { version: 2, ..header }.validate());
+        assert!(!SlabHeader { version: 0, ..header }.validate());
     }
 
     #[test]
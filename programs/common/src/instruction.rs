@@ -183,6 +183,52 @@ impl<'a> InstructionReader<'a> {
     }
 }
 
+/// commit_fill instruction data, encoded by the router's CPI and decoded by
+/// the AMM so the layout cannot drift; the slab parses the same layout
+///
+/// Layout (22 bytes): discriminator (1) + expected_seqno (4) + side (1) +
+/// qty (8) + limit_px (8)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitFillData {
+    /// Matcher seqno the router read before routing (TOCTOU protection)
+    pub expected_seqno: u32,
+    /// Order side
+    pub side: crate::Side,
+    /// Quantity to fill (1e6 scale)
+    pub qty: i64,
+    /// Limit price (1e6 scale)
+    pub limit_px: i64,
+}
+
+impl CommitFillData {
+    /// commit_fill discriminator
+    pub const DISCRIMINATOR: u8 = 1;
+    /// Encoded length, discriminator included
+    pub const LEN: usize = 22;
+
+    /// Encode full instruction data, discriminator included
+    pub fn encode(&self) -> [u8; Self::LEN] {
+        let mut data = [0u8; Self::LEN];
+        data[0] = Self::DISCRIMINATOR;
+        data[1..5].copy_from_slice(&self.expected_seqno.to_le_bytes());
+        data[5] = self.side as u8;
+        data[6..14].copy_from_slice(&self.qty.to_le_bytes());
+        data[14..22].copy_from_slice(&self.limit_px.to_le_bytes());
+        data
+    }
+
+    /// Decode the instruction data following the discriminator
+    pub fn decode(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut reader = InstructionReader::new(data);
+        Ok(Self {
+            expected_seqno: reader.read_u32()?,
+            side: reader.read_side()?,
+            qty: reader.read_i64()?,
+            limit_px: reader.read_i64()?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -255,4 +301,23 @@ mod tests {
 
         assert!(reader.read_u8().is_err());
     }
+
+    #[test]
+    fn test_commit_fill_data_roundtrip() {
+        let fill = CommitFillData {
+            expected_seqno: 7,
+            side: crate::Side::Sell,
+            qty: 1_500_000,
+            limit_px: 59_000_000_000,
+        };
+        let data = fill.encode();
+        assert_eq!(data[0], CommitFillData::DISCRIMINATOR);
+        assert_eq!(CommitFillData::decode(&data[1..]).unwrap(), fill);
+
+        // Truncated data and unknown sides are rejected
+        assert!(CommitFillData::decode(&data[1..21]).is_err());
+        let mut bad_side = data;
+        bad_side[5] = 2;
+        assert_eq!(CommitFillData::decode(&bad_side[1..]), Err(PercolatorError::InvalidSide));
+    }
 }
@@ -12,6 +12,9 @@ pub mod fill_receipt;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "test-utils")]
+pub mod test_accounts;
+
 pub use types::*;
 pub use math::*;
 pub use error::*;
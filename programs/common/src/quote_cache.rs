@@ -23,8 +23,8 @@ impl Default for QuoteLevel {
 pub struct QuoteCache {
     /// Snapshot of header.seqno when cache was last written
     pub seqno_snapshot: u32,
-    /// Padding
-    pub _padding: u32,
+    /// Level layout flags (see `QuoteCache::CUMULATIVE`)
+    pub flags: u32,
     /// Best 4 bid levels (sorted descending by price)
     pub best_bids: [QuoteLevel; 4],
     /// Best 4 ask levels (sorted ascending by price)
@@ -34,11 +34,16 @@ pub struct QuoteCache {
 impl QuoteCache {
     pub const LEN: usize = core::mem::size_of::<Self>();
 
+    /// Levels are cumulative samples: `avail_qty` is the total quantity
+    /// up to that level and `px` is the VWAP for that quantity, rather
+    /// than independent depth at a single price (AMM sample ladders)
+    pub const CUMULATIVE: u32 = 1;
+
     /// Create empty quote cache
     pub fn new() -> Self {
         Self {
             seqno_snapshot: 0,
-            _padding: 0,
+            flags: 0,
             best_bids: [QuoteLevel::default(); 4],
             best_asks: [QuoteLevel::default(); 4],
         }
@@ -64,6 +69,39 @@ impl QuoteCache {
         }
     }
 
+    /// Levels as independent depth (quantity available at each price)
+    ///
+    /// Cumulative samples are differenced: each level becomes the extra
+    /// quantity beyond the previous sample, priced at the marginal cost of
+    /// that slice. Empty samples and samples that do not grow the quantity
+    /// are dropped. Book levels are returned unchanged.
+    pub fn depth(&self, levels: &[QuoteLevel; 4]) -> [QuoteLevel; 4] {
+        if self.flags & Self::CUMULATIVE == 0 {
+            return *levels;
+        }
+
+        let mut out = [QuoteLevel::default(); 4];
+        let mut n = 0;
+        let mut prev_qty: i128 = 0;
+        let mut prev_cost: i128 = 0;
+        for level in levels {
+            let qty = level.avail_qty as i128;
+            if level.px <= 0 || qty <= prev_qty {
+                continue;
+            }
+            let cost = qty * level.px as i128;
+            let slice = qty - prev_qty;
+            out[n] = QuoteLevel {
+                px: ((cost - prev_cost) / slice) as i64,
+                avail_qty: slice as i64,
+            };
+            n += 1;
+            prev_qty = qty;
+            prev_cost = cost;
+        }
+        out
+    }
+
     /// Get total available quantity across all bid levels
     pub fn total_bid_qty(&self) -> i64 {
         self.best_bids.iter().map(|l| l.avail_qty).sum()
@@ -108,4 +146,34 @@ mod tests {
         assert_eq!(cache.total_bid_qty(), 3_000_000);
         assert_eq!(cache.total_ask_qty(), 1_500_000);
     }
+
+    #[test]
+    fn test_cumulative_levels_become_depth() {
+        let mut cache = QuoteCache::new();
+        // Samples: 1 unit at VWAP 100, 3 units at VWAP 102, 6 units at VWAP 105
+        let asks = [
+            QuoteLevel { px: 100_000_000, avail_qty: 1_000_000 },
+            QuoteLevel { px: 102_000_000, avail_qty: 3_000_000 },
+            QuoteLevel { px: 105_000_000, avail_qty: 6_000_000 },
+        ];
+        cache.update(1, &[], &asks);
+
+        // Book layout walks the levels as given
+        assert_eq!(cache.depth(&cache.best_asks)[1].avail_qty, 3_000_000);
+
+        cache.flags = QuoteCache::CUMULATIVE;
+        let depth = cache.depth(&cache.best_asks);
+        assert_eq!(depth[0].avail_qty, 1_000_000);
+        assert_eq!(depth[0].px, 100_000_000);
+        // 2 more units costing 306 - 100 = 206
+        assert_eq!(depth[1].avail_qty, 2_000_000);
+        assert_eq!(depth[1].px, 103_000_000);
+        // 3 more units costing 630 - 306 = 324
+        assert_eq!(depth[2].avail_qty, 3_000_000);
+        assert_eq!(depth[2].px, 108_000_000);
+        assert_eq!(depth[3].avail_qty, 0);
+
+        let total: i64 = depth.iter().map(|l| l.avail_qty).sum();
+        assert_eq!(total, 6_000_000);
+    }
 }
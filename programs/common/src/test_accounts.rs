@@ -0,0 +1,56 @@
+//! Test-only account builder (feature `test-utils`)
+//!
+//! Lays out an account the way the runtime serializes it for a program
+//! (borrow state, flags, key, owner, lamports, data length, then the data)
+//! so instructions can be exercised against real `AccountInfo`s.
+
+extern crate std;
+
+use pinocchio::{account_info::AccountInfo, entrypoint::NON_DUP_MARKER, pubkey::Pubkey};
+use std::{vec, vec::Vec};
+
+/// Size of the runtime account header preceding the data
+const HEADER_LEN: usize = 88;
+
+/// Header start within the buffer, so the data is 16-byte aligned
+const HEADER_OFFSET: usize = 8;
+
+/// Owned backing storage for one `AccountInfo`
+pub struct TestAccount {
+    buf: Vec<u128>,
+}
+
+impl TestAccount {
+    /// Create a writable account with the given key, owner and data
+    pub fn new(key: Pubkey, owner: Pubkey, data: &[u8]) -> Self {
+        let mut account = Self {
+            buf: vec![0u128; (HEADER_OFFSET + HEADER_LEN + data.len()).div_ceil(16)],
+        };
+        let bytes = account.bytes();
+        bytes[0] = NON_DUP_MARKER;
+        bytes[2] = 1; // writable
+        bytes[8..40].copy_from_slice(&key);
+        bytes[40..72].copy_from_slice(&owner);
+        bytes[80..88].copy_from_slice(&(data.len() as u64).to_le_bytes());
+        bytes[HEADER_LEN..HEADER_LEN + data.len()].copy_from_slice(data);
+        account
+    }
+
+    /// Mark the account as a signer
+    pub fn signer(mut self) -> Self {
+        self.bytes()[1] = 1;
+        self
+    }
+
+    /// Account info pointing at this account's storage
+    pub fn info(&mut self) -> AccountInfo {
+        let raw = self.bytes().as_mut_ptr();
+        unsafe { core::mem::transmute::<*mut u8, AccountInfo>(raw) }
+    }
+
+    /// Header and data bytes
+    fn bytes(&mut self) -> &mut [u8] {
+        let len = self.buf.len() * 16 - HEADER_OFFSET;
+        unsafe { core::slice::from_raw_parts_mut((self.buf.as_mut_ptr() as *mut u8).add(HEADER_OFFSET), len) }
+    }
+}
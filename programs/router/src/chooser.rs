@@ -4,7 +4,7 @@
 //! calculates VWAP for the desired quantity, and selects the optimal
 //! execution path (single slab or split across multiple slabs).
 
-use percolator_common::QuoteCache;
+use percolator_common::{QuoteCache, QuoteLevel};
 use pinocchio::pubkey::Pubkey;
 
 /// Quote from a single slab
@@ -49,7 +49,7 @@ pub fn calculate_buy_vwap(cache: &QuoteCache, qty: i64) -> Option<(i64, i64)> {
     let mut filled_qty: i64 = 0;
 
     // Walk through ask levels (we're buying, so we take from asks)
-    for level in &cache.best_asks {
+    for level in &cache.depth(&cache.best_asks) {
         if level.px == 0 || level.avail_qty == 0 {
             break; // No more levels
         }
@@ -91,7 +91,7 @@ pub fn calculate_sell_vwap(cache: &QuoteCache, qty: i64) -> Option<(i64, i64)> {
     let mut filled_qty: i64 = 0;
 
     // Walk through bid levels (we're selling, so we take from bids)
-    for level in &cache.best_bids {
+    for level in &cache.depth(&cache.best_bids) {
         if level.px == 0 || level.avail_qty == 0 {
             break; // No more levels
         }
@@ -171,6 +171,93 @@ pub fn choose_best_sell(quotes: &[SlabQuote], qty: i64) -> Option<usize> {
     best_idx
 }
 
+/// Maximum slabs a single routed order may split across
+pub const MAX_ROUTE_SLABS: usize = 8;
+
+/// Split an order across slabs by walking their quote levels best-first
+///
+/// Repeatedly takes the best remaining level across all caches (lowest
+/// ask for a buy, highest bid for a sell) until the quantity is filled,
+/// the limit price is crossed or liquidity runs out. No slab is given
+/// more than `max_per_slab`. Ties go to the earlier slab. Cumulative
+/// (AMM) caches are walked as differenced depth.
+///
+/// # Arguments
+/// * `caches` - QuoteCache of each slab (at most MAX_ROUTE_SLABS)
+/// * `side` - 0 = buy, 1 = sell
+/// * `qty` - Quantity to route (scaled by 1e6)
+/// * `limit_px` - Worst acceptable level price (scaled by 1e6)
+/// * `max_per_slab` - Maximum quantity routed to any one slab
+/// * `alloc` - Output: quantity allocated to each slab
+///
+/// # Returns
+/// * Total quantity allocated
+pub fn split_order(
+    caches: &[QuoteCache],
+    side: u8,
+    qty: i64,
+    limit_px: i64,
+    max_per_slab: i64,
+    alloc: &mut [i64],
+) -> i64 {
+    let n = caches.len().min(alloc.len()).min(MAX_ROUTE_SLABS);
+    let mut cursors = [0usize; MAX_ROUTE_SLABS];
+    for a in alloc.iter_mut() {
+        *a = 0;
+    }
+
+    let is_buy = side == 0;
+    let mut depth = [[QuoteLevel::default(); 4]; MAX_ROUTE_SLABS];
+    for (levels, cache) in depth.iter_mut().zip(&caches[..n]) {
+        *levels = cache.depth(if is_buy { &cache.best_asks } else { &cache.best_bids });
+    }
+    let mut remaining = qty.max(0);
+
+    while remaining > 0 {
+        // Find the best unconsumed level across all slabs
+        let mut best: Option<(usize, i64)> = None;
+        for (i, levels) in depth[..n].iter().enumerate() {
+            let Some(level) = levels.get(cursors[i]) else {
+                continue;
+            };
+            if level.px <= 0 || level.avail_qty <= 0 || alloc[i] >= max_per_slab {
+                continue; // No more levels on this slab, or slab at cap
+            }
+            if (is_buy && level.px > limit_px) || (!is_buy && level.px < limit_px) {
+                continue; // Beyond limit
+            }
+
+            let better = match best {
+                None => true,
+                Some((_, px)) => {
+                    if is_buy {
+                        level.px < px
+                    } else {
+                        level.px > px
+                    }
+                }
+            };
+            if better {
+                best = Some((i, level.px));
+            }
+        }
+
+        let Some((i, _)) = best else {
+            break; // Liquidity within limit exhausted
+        };
+
+        let level_qty = depth[i][cursors[i]].avail_qty;
+        let take = remaining.min(level_qty).min(max_per_slab - alloc[i]);
+        alloc[i] += take;
+        remaining -= take;
+        if take == level_qty {
+            cursors[i] += 1;
+        }
+    }
+
+    qty.max(0) - remaining
+}
+
 /// Get quotes from multiple slabs (test helper only, requires alloc)
 ///
 /// # Arguments
@@ -211,7 +298,6 @@ pub fn get_slab_quotes(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use percolator_common::QuoteLevel;
 
     fn make_quote_level(px: i64, qty: i64) -> QuoteLevel {
         QuoteLevel {
@@ -314,6 +400,86 @@ mod tests {
         assert_eq!(best, 1);
     }
 
+    #[test]
+    fn test_split_order_walks_levels_across_slabs() {
+        let mut ob = QuoteCache::new();
+        ob.best_asks[0] = make_quote_level(60_000_000_000, 2_000_000);
+        ob.best_asks[1] = make_quote_level(60_200_000_000, 5_000_000);
+        let mut amm = QuoteCache::new();
+        amm.best_asks[0] = make_quote_level(60_100_000_000, 3_000_000);
+        amm.best_asks[1] = make_quote_level(60_300_000_000, 5_000_000);
+
+        // Buy 6: 2 @ 60.0k (OB), 3 @ 60.1k (AMM), 1 @ 60.2k (OB)
+        let mut alloc = [0i64; 2];
+        let filled = split_order(&[ob, amm], 0, 6_000_000, 61_000_000_000, i64::MAX, &mut alloc);
+        assert_eq!(filled, 6_000_000);
+        assert_eq!(alloc, [3_000_000, 3_000_000]);
+
+        // Limit at 60.1k stops before the second OB level
+        let filled = split_order(&[ob, amm], 0, 6_000_000, 60_100_000_000, i64::MAX, &mut alloc);
+        assert_eq!(filled, 5_000_000);
+        assert_eq!(alloc, [2_000_000, 3_000_000]);
+
+        // More than all liquidity: partial
+        let filled = split_order(&[ob, amm], 0, 100_000_000, 61_000_000_000, i64::MAX, &mut alloc);
+        assert_eq!(filled, 15_000_000);
+        assert_eq!(alloc, [7_000_000, 8_000_000]);
+
+        // Per-slab cap sends the rest to the next best venue
+        let filled = split_order(&[ob, amm], 0, 6_000_000, 61_000_000_000, 2_500_000, &mut alloc);
+        assert_eq!(filled, 5_000_000);
+        assert_eq!(alloc, [2_500_000, 2_500_000]);
+    }
+
+    #[test]
+    fn test_split_order_sell_takes_highest_bids() {
+        let mut a = QuoteCache::new();
+        a.best_bids[0] = make_quote_level(59_000_000_000, 4_000_000);
+        let mut b = QuoteCache::new();
+        b.best_bids[0] = make_quote_level(59_500_000_000, 1_000_000);
+        b.best_bids[1] = make_quote_level(58_000_000_000, 10_000_000);
+
+        let mut alloc = [0i64; 2];
+        let filled = split_order(&[a, b], 1, 8_000_000, 58_500_000_000, i64::MAX, &mut alloc);
+        assert_eq!(filled, 5_000_000);
+        assert_eq!(alloc, [4_000_000, 1_000_000]);
+
+        // Empty caches route nothing
+        let filled = split_order(&[QuoteCache::new()], 1, 8_000_000, 0, i64::MAX, &mut alloc[..1]);
+        assert_eq!(filled, 0);
+        assert_eq!(alloc[0], 0);
+    }
+
+    #[test]
+    fn test_split_order_differences_cumulative_amm_levels() {
+        // Constant-product pool (1000 units, spot 60k) sampled like the AMM
+        // synthesizes its cache: VWAP of buying 10, 20, 50 and 100 units
+        let mut amm = QuoteCache::new();
+        amm.flags = QuoteCache::CUMULATIVE;
+        for (i, units) in [10i64, 20, 50, 100].into_iter().enumerate() {
+            let vwap = 60_000_000_000i128 * 1000 / (1000 - units) as i128;
+            amm.best_asks[i] = make_quote_level(vwap as i64, units * 1_000_000);
+        }
+        let mut ob = QuoteCache::new();
+        ob.best_asks[0] = make_quote_level(62_000_000_000, 100_000_000);
+
+        // The pool holds 100 units of depth, not the 180 the samples add up to
+        let mut alloc = [0i64; 1];
+        let filled = split_order(&[amm], 0, 1_000_000_000, i64::MAX, i64::MAX, &mut alloc);
+        assert_eq!(filled, 100_000_000);
+
+        // Only the first 20 units are marginally cheaper than the book at 62k
+        let mut alloc = [0i64; 2];
+        let filled = split_order(&[ob, amm], 0, 100_000_000, i64::MAX, i64::MAX, &mut alloc);
+        assert_eq!(filled, 100_000_000);
+        assert_eq!(alloc, [80_000_000, 20_000_000]);
+
+        // VWAP over the differenced depth matches the sampled VWAP
+        let (vwap, filled) = calculate_buy_vwap(&amm, 50_000_000).unwrap();
+        assert_eq!(filled, 50_000_000);
+        assert!((vwap - amm.best_asks[2].px).abs() <= 1);
+    }
+
     #[test]
     fn test_insufficient_liquidity() {
         let quotes = vec![
@@ -8,8 +8,9 @@ use pinocchio::{
     ProgramResult,
 };
 
-use crate::instructions::{RouterInstruction, process_deposit, process_withdraw, process_initialize_registry, process_initialize_portfolio, process_execute_cross_slab, process_liquidate_user, process_burn_lp_shares, process_cancel_lp_orders};
-use crate::state::{Vault, Portfolio, SlabRegistry};
+use crate::instructions::{RouterInstruction, VaultTransferAccounts, SlabAccounts, TradeAccounts, MarginAccounts, MarketOrder, process_deposit, process_withdraw, process_initialize_registry, process_initialize_portfolio, process_execute_cross_slab, process_liquidate_user, process_burn_lp_shares, process_cancel_lp_orders, load_withdrawal_queue, process_request_withdrawal, process_cancel_withdrawal, process_process_withdrawals, check_trading_allowed, process_set_pause_flags, process_queue_proposal, process_execute_proposal, process_cancel_proposal, process_deactivate_slab, process_propose_governance, process_accept_governance, process_update_funding, process_execute_order, OrderGuards, check_matcher_liveness, process_quarantine_slab, process_reactivate_slab};
+use crate::matcher::read_slab_matcher;
+use crate::state::{Vault, Portfolio, SlabRegistry, EmergencyMode};
 use percolator_common::{PercolatorError, validate_owner, validate_writable, borrow_account_data_mut, InstructionReader};
 
 entrypoint!(process_instruction);
@@ -36,6 +37,24 @@ pub fn process_instruction(
         5 => RouterInstruction::LiquidateUser,
         6 => RouterInstruction::BurnLpShares,
         7 => RouterInstruction::CancelLpOrders,
+        8 => RouterInstruction::RequestWithdrawal,
+        9 => RouterInstruction::CancelWithdrawal,
+        10 => RouterInstruction::ProcessWithdrawals,
+        11 => RouterInstruction::SetPauseFlags,
+        12 => RouterInstruction::RegisterSlab,
+        13 => RouterInstruction::UpdateSlab,
+        14 => RouterInstruction::DeactivateSlab,
+        15 => RouterInstruction::UpdateLiquidationParams,
+        16 => RouterInstruction::SetTimelock,
+        17 => RouterInstruction::ExecuteProposal,
+        18 => RouterInstruction::CancelProposal,
+        19 => RouterInstruction::ProposeGovernance,
+        20 => RouterInstruction::AcceptGovernance,
+        21 => RouterInstruction::UpdateFunding,
+        22 => RouterInstruction::ExecuteOrder,
+        23 => RouterInstruction::QuarantineSlab,
+        24 => RouterInstruction::ReactivateSlab,
+        25 => RouterInstruction::UpdateExitConfig,
         _ => {
             msg!("Error: Unknown instruction");
             return Err(PercolatorError::InvalidInstruction.into());
@@ -76,6 +95,66 @@ pub fn process_instruction(
             msg!("Instruction: CancelLpOrders");
             process_cancel_lp_orders_inner(program_id, accounts, &instruction_data[1..])
         }
+        RouterInstruction::RequestWithdrawal => {
+            msg!("Instruction: RequestWithdrawal");
+            process_request_withdrawal_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::CancelWithdrawal => {
+            msg!("Instruction: CancelWithdrawal");
+            process_cancel_withdrawal_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::ProcessWithdrawals => {
+            msg!("Instruction: ProcessWithdrawals");
+            process_process_withdrawals_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::SetPauseFlags => {
+            msg!("Instruction: SetPauseFlags");
+            process_set_pause_flags_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::RegisterSlab
+        | RouterInstruction::UpdateSlab
+        | RouterInstruction::UpdateLiquidationParams
+        | RouterInstruction::SetTimelock
+        | RouterInstruction::UpdateExitConfig => {
+            msg!("Instruction: QueueProposal");
+            process_queue_proposal_inner(program_id, accounts, instruction, &instruction_data[1..])
+        }
+        RouterInstruction::DeactivateSlab => {
+            msg!("Instruction: DeactivateSlab");
+            process_deactivate_slab_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::ExecuteProposal => {
+            msg!("Instruction: ExecuteProposal");
+            process_execute_proposal_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::CancelProposal => {
+            msg!("Instruction: CancelProposal");
+            process_cancel_proposal_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::ProposeGovernance => {
+            msg!("Instruction: ProposeGovernance");
+            process_propose_governance_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::AcceptGovernance => {
+            msg!("Instruction: AcceptGovernance");
+            process_accept_governance_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::UpdateFunding => {
+            msg!("Instruction: UpdateFunding");
+            process_update_funding_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::ExecuteOrder => {
+            msg!("Instruction: ExecuteOrder");
+            process_execute_order_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::QuarantineSlab => {
+            msg!("Instruction: QuarantineSlab");
+            process_quarantine_slab_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::ReactivateSlab => {
+            msg!("Instruction: ReactivateSlab");
+            process_reactivate_slab_inner(program_id, accounts, &instruction_data[1..])
+        }
     }
 }
 
@@ -123,31 +202,48 @@ fn process_initialize_inner(program_id: &Pubkey, accounts: &[AccountInfo], data:
 /// Process deposit instruction
 ///
 /// Expected accounts:
-/// 0. `[writable]` Vault account
+/// 0. `[writable]` Vault account (PDA ["vault", mint])
 /// 1. `[writable]` User token account
 /// 2. `[signer]` User authority
 /// 3. `[]` Token program
+/// 4. `[writable]` Vault token account
+/// 5. `[writable]` User portfolio account
 ///
 /// Expected data layout (16 bytes):
 /// - amount: u128 (16 bytes)
 fn process_deposit_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: Deposit instruction requires at least 1 account");
+    if accounts.len() < 6 {
+        msg!("Error: Deposit instruction requires at least 6 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
     let vault_account = &accounts[0];
     validate_owner(vault_account, program_id)?;
     validate_writable(vault_account)?;
+    validate_writable(&accounts[1])?;
+    validate_writable(&accounts[4])?;
+
+    let transfer_accounts = VaultTransferAccounts {
+        vault: vault_account,
+        user_token: &accounts[1],
+        user: &accounts[2],
+        token_program: &accounts[3],
+        vault_token: &accounts[4],
+    };
+
+    let portfolio_account = &accounts[5];
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
 
     let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
 
     // Parse instruction data
     let mut reader = InstructionReader::new(data);
     let amount = reader.read_u128()?;
 
     // Call the instruction handler
-    process_deposit(vault, amount)?;
+    process_deposit(vault, portfolio, &transfer_accounts, program_id, amount)?;
 
     msg!("Deposit processed successfully");
     Ok(())
@@ -156,31 +252,58 @@ fn process_deposit_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[
 /// Process withdraw instruction
 ///
 /// Expected accounts:
-/// 0. `[writable]` Vault account
+/// 0. `[writable]` Vault account (PDA ["vault", mint])
 /// 1. `[writable]` User token account
 /// 2. `[signer]` User authority
 /// 3. `[]` Token program
+/// 4. `[writable]` Vault token account
+/// 5. `[writable]` User portfolio account
+/// 6. `[writable]` Registry account
+///    7..7+K. `[]` Oracle accounts, one per instrument held (K = num_oracles)
+///    7+K... `[]` [amm_pool, programdata, oracle] triples for the portfolio's AMM LP buckets
 ///
-/// Expected data layout (16 bytes):
+/// Expected data layout (17 bytes):
 /// - amount: u128 (16 bytes)
+/// - num_oracles: u8 (1 byte)
 fn process_withdraw_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: Withdraw instruction requires at least 1 account");
+    if accounts.len() < 7 {
+        msg!("Error: Withdraw instruction requires at least 7 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
     let vault_account = &accounts[0];
     validate_owner(vault_account, program_id)?;
     validate_writable(vault_account)?;
+    validate_writable(&accounts[1])?;
+    validate_writable(&accounts[4])?;
+
+    let transfer_accounts = VaultTransferAccounts {
+        vault: vault_account,
+        user_token: &accounts[1],
+        user: &accounts[2],
+        token_program: &accounts[3],
+        vault_token: &accounts[4],
+    };
+
+    let portfolio_account = &accounts[5];
+    let registry_account = &accounts[6];
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
 
     let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
 
     // Parse instruction data
     let mut reader = InstructionReader::new(data);
     let amount = reader.read_u128()?;
+    let num_oracles = reader.read_u8()? as usize;
+    let margin = MarginAccounts::split(&accounts[7..], num_oracles)?;
 
     // Call the instruction handler
-    process_withdraw(vault, amount)?;
+    process_withdraw(vault, portfolio, registry, &transfer_accounts, margin, program_id, amount)?;
 
     msg!("Withdraw processed successfully");
     Ok(())
@@ -233,17 +356,27 @@ fn process_initialize_portfolio_inner(program_id: &Pubkey, accounts: &[AccountIn
 /// 2. `[writable]` Vault account
 /// 3. `[writable]` Registry account
 /// 4. `[]` Router authority PDA
-/// 5..5+N. `[writable]` Slab accounts (N = num_splits)
-/// 5+N..5+2N. `[writable]` Receipt PDAs (N = num_splits)
+///    5..5+N. `[writable]` Slab accounts (N = num_splits)
+///    5+N..5+2N. `[writable]` Receipt PDAs (N = num_splits)
+///    5+2N..5+3N. `[]` Matcher programdata accounts, one per slab (N = num_splits)
+///    5+3N..5+4N. `[writable]` Maker portfolios, one per slab's `lp_owner` (N = num_splits)
+///    5+4N..5+4N+K. `[]` Oracle accounts, one per instrument traded or held after the fills (K = num_oracles)
+///    5+4N+K... `[]` [AMM pool, programdata, oracle] triples, one per active AMM LP bucket
 ///
 /// Instruction data layout:
 /// - num_splits: u8 (1 byte)
+/// - num_oracles: u8 (1 byte)
 /// - For each split (17 bytes):
 ///   - side: u8 (0 = buy, 1 = sell)
 ///   - qty: i64 (quantity in 1e6 scale)
 ///   - limit_px: i64 (limit price in 1e6 scale)
 ///
-/// Total size: 1 + (17 * num_splits) bytes
+/// - Optional order guards (25 bytes):
+///   - flags: u8 (bit 0 = reduce-only, bit 1 = worst VWAP set, bit 2 = max fee set)
+///   - worst_vwap_px: i64 (aggregate VWAP ceiling for buys, floor for sells)
+///   - max_total_fee: u128 (total taker fee limit)
+///
+/// Total size: 2 + (17 * num_splits) [+ 25] bytes
 /// Maximum splits: 8 (to avoid stack overflow)
 fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     if accounts.len() < 5 {
@@ -264,13 +397,17 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
     validate_writable(vault_account)?;
     validate_owner(registry_account, program_id)?;
     validate_writable(registry_account)?;
+    if !user_account.is_signer() {
+        msg!("Error: User must be signer");
+        return Err(PercolatorError::Unauthorized.into());
+    }
 
     // Borrow account data mutably
     let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
     let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
     let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
 
-    // Parse instruction data: num_splits (u8) + splits (17 bytes each)
+    // Parse instruction data: num_splits (u8) + num_oracles (u8) + splits (17 bytes each)
     // Layout per split: side (u8) + qty (i64) + limit_px (i64)
     if data.is_empty() {
         msg!("Error: Instruction data is empty");
@@ -279,22 +416,17 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
 
     let mut reader = InstructionReader::new(data);
     let num_splits = reader.read_u8()? as usize;
+    let num_oracles = reader.read_u8()? as usize;
 
     if num_splits == 0 {
         msg!("Error: num_splits must be > 0");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
-    // Verify we have enough accounts: 5 base + num_splits slabs + num_splits receipts
-    let required_accounts = 5 + (num_splits * 2);
-    if accounts.len() < required_accounts {
-        msg!("Error: Insufficient accounts for ExecuteCrossSlab");
-        return Err(PercolatorError::InvalidInstruction.into());
-    }
-
-    // Split accounts into slabs and receipts
-    let slab_accounts = &accounts[5..5 + num_splits];
-    let receipt_accounts = &accounts[5 + num_splits..5 + num_splits * 2];
+    // Split accounts into slabs, receipts, matcher programdata and maker portfolios,
+    // then oracles and LP pairs
+    let (slab_accounts, rest) = SlabAccounts::split(&accounts[5..], num_splits)?;
+    let margin = MarginAccounts::split(rest, num_oracles)?;
 
     // Parse splits from instruction data (on stack, small)
     // Use a fixed-size buffer to avoid heap allocation
@@ -312,7 +444,7 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
         limit_px: 0,
     }; MAX_SPLITS];
 
-    for i in 0..num_splits {
+    for (i, split) in splits_buffer[..num_splits].iter_mut().enumerate() {
         let side = reader.read_u8()?;
         let qty = reader.read_i64()?;
         let limit_px = reader.read_i64()?;
@@ -324,9 +456,9 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
         }
 
         // Get slab_id from the corresponding account
-        let slab_id = *slab_accounts[i].key();
+        let slab_id = *slab_accounts.slabs[i].key();
 
-        splits_buffer[i] = SlabSplit {
+        *split = SlabSplit {
             slab_id,
             qty,
             side,
@@ -336,22 +468,137 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
 
     let splits = &splits_buffer[..num_splits];
 
+    // Optional order-level guards
+    let guards = if reader.remaining() > 0 {
+        let flags = reader.read_u8()?;
+        let worst_vwap_px = reader.read_i64()?;
+        let max_total_fee = reader.read_u128()?;
+        OrderGuards::from_parts(flags, worst_vwap_px, max_total_fee)
+    } else {
+        OrderGuards::default()
+    };
+
+    // Pause / reduce-only gate (user orders only)
+    check_trading_allowed(registry, portfolio, splits)?;
+
+    // Matcher liveness: no user fills against quotes older than the SLA
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let current_slot = Clock::get()?.slot;
+    check_matcher_liveness(registry, slab_accounts.slabs, splits, current_slot)?;
+
     // Call the instruction handler
+    let trade_accounts = TradeAccounts {
+        router_authority,
+        slabs: slab_accounts,
+        margin,
+    };
     process_execute_cross_slab(
         portfolio,
         user_account.key(),
         vault,
         registry,
-        router_authority,
-        slab_accounts,
-        receipt_accounts,
+        trade_accounts,
         splits,
+        &guards,
     )?;
 
     msg!("ExecuteCrossSlab processed successfully");
     Ok(())
 }
 
+/// Process execute order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Portfolio account
+/// 1. `[signer]` User authority
+/// 2. `[writable]` Vault account
+/// 3. `[writable]` Registry account
+/// 4. `[]` Router authority PDA
+///    5..5+N. `[writable]` Candidate slab accounts (N = num_slabs)
+///    5+N..5+2N. `[writable]` Receipt PDAs (N = num_slabs)
+///    5+2N..5+3N. `[]` Matcher programdata accounts, one per slab (N = num_slabs)
+///    5+3N..5+4N. `[writable]` Maker portfolios, one per slab's `lp_owner` (N = num_slabs)
+///    5+4N..5+4N+K. `[]` Oracle accounts, one per instrument traded or held after the fills (K = num_oracles)
+///    5+4N+K... `[]` [AMM pool, programdata, oracle] triples, one per active AMM LP bucket
+///
+/// Instruction data layout (19 bytes):
+/// - side: u8 (0 = buy, 1 = sell)
+/// - qty: i64 (quantity in 1e6 scale)
+/// - limit_px: i64 (limit price in 1e6 scale)
+/// - num_slabs: u8 (1 byte, max 8)
+/// - num_oracles: u8 (1 byte)
+///
+/// Return data (32 bytes):
+/// - filled_qty: i64 (signed, +buy / -sell)
+/// - vwap_px: i64 (average fill price)
+/// - fee: u64 (total taker fee)
+/// - unfilled_qty: i64 (quantity not routed or not filled)
+fn process_execute_order_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 5 {
+        msg!("Error: ExecuteOrder requires at least 5 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let portfolio_account = &accounts[0];
+    let user_account = &accounts[1];
+    let vault_account = &accounts[2];
+    let registry_account = &accounts[3];
+    let router_authority = &accounts[4];
+
+    // Validate accounts
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_owner(vault_account, program_id)?;
+    validate_writable(vault_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    if !user_account.is_signer() {
+        msg!("Error: User must be signer");
+        return Err(PercolatorError::Unauthorized.into());
+    }
+
+    // Borrow account data mutably
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    let mut reader = InstructionReader::new(data);
+    let side = reader.read_u8()?;
+    let qty = reader.read_i64()?;
+    let limit_px = reader.read_i64()?;
+    let num_slabs = reader.read_u8()? as usize;
+    let num_oracles = reader.read_u8()? as usize;
+
+    let (slab_accounts, rest) = SlabAccounts::split(&accounts[5..], num_slabs)?;
+    let margin = MarginAccounts::split(rest, num_oracles)?;
+
+    // Matcher liveness is checked against the current slot
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let current_slot = Clock::get()?.slot;
+
+    // Call the instruction handler
+    let trade_accounts = TradeAccounts {
+        router_authority,
+        slabs: slab_accounts,
+        margin,
+    };
+    let summary = process_execute_order(
+        portfolio,
+        user_account.key(),
+        vault,
+        registry,
+        trade_accounts,
+        &MarketOrder { side, qty, limit_px },
+        current_slot,
+    )?;
+
+    // Report the aggregate fill to the caller
+    pinocchio::cpi::set_return_data(&summary.encode(qty));
+
+    msg!("ExecuteOrder processed successfully");
+    Ok(())
+}
+
 /// Process liquidate user instruction
 ///
 /// Expected accounts:
@@ -359,9 +606,12 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
 /// 1. `[]` Registry account
 /// 2. `[writable]` Vault account
 /// 3. `[]` Router authority PDA
-/// 4..4+N. `[]` Oracle accounts (N = num_oracles)
-/// 4+N..4+N+M. `[writable]` Slab accounts (M = num_slabs)
-/// 4+N+M..4+N+2M. `[writable]` Receipt PDAs (M = num_slabs)
+///    4..4+N. `[]` Oracle accounts (N = num_oracles)
+///    4+N..4+N+M. `[writable]` Slab accounts (M = num_slabs)
+///    4+N+M..4+N+2M. `[writable]` Receipt PDAs (M = num_slabs)
+///    4+N+2M..4+N+3M. `[]` Matcher programdata accounts, one per slab (M = num_slabs)
+///    4+N+3M..4+N+4M. `[writable]` Maker portfolios, one per slab's `lp_owner` (M = num_slabs)
+///    4+N+4M... `[]` [AMM pool, programdata, oracle] triples, one per active AMM LP bucket
 ///
 /// Instruction data layout:
 /// - num_oracles: u8 (1 byte)
@@ -407,7 +657,7 @@ fn process_liquidate_user_inner(program_id: &Pubkey, accounts: &[AccountInfo], d
     let current_ts = reader.read_u64()?;
 
     // Verify we have enough accounts
-    let required_accounts = 4 + num_oracles + num_slabs * 2;
+    let required_accounts = 4 + num_oracles + num_slabs * 4;
     if accounts.len() < required_accounts {
         msg!("Error: Insufficient accounts for LiquidateUser");
         return Err(PercolatorError::InvalidInstruction.into());
@@ -415,18 +665,22 @@ fn process_liquidate_user_inner(program_id: &Pubkey, accounts: &[AccountInfo], d
 
     // Split accounts
     let oracle_accounts = &accounts[4..4 + num_oracles];
-    let slab_accounts = &accounts[4 + num_oracles..4 + num_oracles + num_slabs];
-    let receipt_accounts = &accounts[4 + num_oracles + num_slabs..4 + num_oracles + num_slabs * 2];
+    let (slab_accounts, lp_accounts) = SlabAccounts::split(&accounts[4 + num_oracles..], num_slabs)?;
+    let trade_accounts = TradeAccounts {
+        router_authority,
+        slabs: slab_accounts,
+        margin: MarginAccounts {
+            oracles: oracle_accounts,
+            lp: lp_accounts,
+        },
+    };
 
     // Call the instruction handler
     process_liquidate_user(
         portfolio,
         registry,
         vault,
-        router_authority,
-        oracle_accounts,
-        slab_accounts,
-        receipt_accounts,
+        trade_accounts,
         is_preliq,
         current_ts,
     )?;
@@ -563,3 +817,466 @@ fn process_cancel_lp_orders_inner(program_id: &Pubkey, accounts: &[AccountInfo],
     msg!("CancelLpOrders processed successfully");
     Ok(())
 }
+
+/// Process request withdrawal instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Withdrawal queue account (PDA ["withdrawal_queue", portfolio])
+/// 1. `[writable]` Portfolio account
+/// 2. `[signer]` User (portfolio owner)
+/// 3. `[writable]` Registry account
+///    4..4+K. `[]` Oracle accounts, one per instrument held (K = num_oracles)
+///    4+K... `[]` [amm_pool, programdata, oracle] triples for the portfolio's AMM LP buckets
+///
+/// Expected data layout (17 bytes):
+/// - amount: u128 (16 bytes)
+/// - num_oracles: u8 (1 byte)
+fn process_request_withdrawal_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 4 {
+        msg!("Error: RequestWithdrawal instruction requires at least 4 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let queue_account = &accounts[0];
+    let portfolio_account = &accounts[1];
+    let user_account = &accounts[2];
+    let registry_account = &accounts[3];
+
+    validate_owner(queue_account, program_id)?;
+    validate_writable(queue_account)?;
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+
+    let queue = load_withdrawal_queue(queue_account, portfolio_account, program_id)?;
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    let mut reader = InstructionReader::new(data);
+    let amount = reader.read_u128()?;
+    let num_oracles = reader.read_u8()? as usize;
+    let margin = MarginAccounts::split(&accounts[4..], num_oracles)?;
+
+    process_request_withdrawal(queue, portfolio, user_account, registry, margin, amount)?;
+
+    msg!("RequestWithdrawal processed successfully");
+    Ok(())
+}
+
+/// Process cancel withdrawal instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Withdrawal queue account (PDA ["withdrawal_queue", portfolio])
+/// 1. `[writable]` Portfolio account
+/// 2. `[signer]` User (portfolio owner)
+/// 3. `[writable]` Registry account
+///
+/// Expected data layout (8 bytes):
+/// - ticket: u64 (8 bytes)
+fn process_cancel_withdrawal_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 4 {
+        msg!("Error: CancelWithdrawal instruction requires at least 4 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let queue_account = &accounts[0];
+    let portfolio_account = &accounts[1];
+    let user_account = &accounts[2];
+    let registry_account = &accounts[3];
+
+    validate_owner(queue_account, program_id)?;
+    validate_writable(queue_account)?;
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+
+    let queue = load_withdrawal_queue(queue_account, portfolio_account, program_id)?;
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    let mut reader = InstructionReader::new(data);
+    let ticket = reader.read_u64()?;
+
+    process_cancel_withdrawal(queue, portfolio, user_account, registry, ticket)?;
+
+    msg!("CancelWithdrawal processed successfully");
+    Ok(())
+}
+
+/// Process queued withdrawals instruction (permissionless)
+///
+/// Expected accounts:
+/// 0. `[writable]` Withdrawal queue account (PDA ["withdrawal_queue", portfolio])
+/// 1. `[writable]` Portfolio account
+/// 2. `[writable]` Registry account
+/// 3. `[writable]` Vault account (PDA ["vault", mint])
+/// 4. `[writable]` Vault token account
+/// 5. `[writable]` Portfolio owner's token account
+/// 6. `[]` Portfolio owner
+/// 7. `[]` Token program
+///
+/// Expected data layout: none
+fn process_process_withdrawals_inner(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    if accounts.len() < 8 {
+        msg!("Error: ProcessWithdrawals instruction requires at least 8 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let queue_account = &accounts[0];
+    let portfolio_account = &accounts[1];
+    let registry_account = &accounts[2];
+    let vault_account = &accounts[3];
+
+    validate_owner(queue_account, program_id)?;
+    validate_writable(queue_account)?;
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_owner(vault_account, program_id)?;
+    validate_writable(vault_account)?;
+    validate_writable(&accounts[4])?;
+    validate_writable(&accounts[5])?;
+
+    let transfer_accounts = VaultTransferAccounts {
+        vault: vault_account,
+        vault_token: &accounts[4],
+        user_token: &accounts[5],
+        user: &accounts[6],
+        token_program: &accounts[7],
+    };
+
+    let queue = load_withdrawal_queue(queue_account, portfolio_account, program_id)?;
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+
+    process_process_withdrawals(queue, portfolio, vault, registry, &transfer_accounts, program_id)?;
+
+    msg!("ProcessWithdrawals processed successfully");
+    Ok(())
+}
+
+/// Process set pause flags instruction (governance only)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Expected data layout (1 or 18 bytes):
+/// - pause_flags: u8 (PAUSE_* / REDUCE_ONLY bitmask)
+/// - optional emergency exit mode:
+///   - active: u8
+///   - exit_multiplier_bps: u64
+///   - expires_at_secs: u64 (0 = no expiry)
+fn process_set_pause_flags_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: SetPauseFlags instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    let mut reader = InstructionReader::new(data);
+    let pause_flags = reader.read_u8()?;
+    let emergency = if data.len() > 1 {
+        Some(EmergencyMode {
+            active: reader.read_u8()? != 0,
+            exit_multiplier_bps: reader.read_u64()?,
+            expires_at_secs: reader.read_u64()?,
+        })
+    } else {
+        None
+    };
+
+    process_set_pause_flags(registry, governance_account, pause_flags, emergency)?;
+
+    msg!("SetPauseFlags processed successfully");
+    Ok(())
+}
+
+/// Borrow the registry for a governance instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+fn governance_accounts<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo],
+) -> Result<(&'a mut SlabRegistry, &'a AccountInfo), PercolatorError> {
+    if accounts.len() < 2 {
+        msg!("Error: Governance instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    let registry_account = &accounts[0];
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    Ok((registry, &accounts[1]))
+}
+
+/// Process queue proposal instruction (governance only)
+///
+/// Used by RegisterSlab, UpdateSlab, UpdateLiquidationParams, SetTimelock
+/// and UpdateExitConfig: the instruction data is validated and queued as a
+/// proposal keyed by its discriminator, executable after the timelock.
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Expected data layout: see `GovernanceAction::decode`
+fn process_queue_proposal_inner(program_id: &Pubkey, accounts: &[AccountInfo], kind: RouterInstruction, data: &[u8]) -> ProgramResult {
+    let (registry, governance) = governance_accounts(program_id, accounts)?;
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let current_ts = Clock::get()?.unix_timestamp as u64;
+
+    process_queue_proposal(registry, governance, kind as u8, data, current_ts)?;
+
+    msg!("Proposal queued");
+    Ok(())
+}
+
+/// Process execute proposal instruction (permissionless)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[]` Slab account (RegisterSlab / UpdateSlab proposals only)
+/// 2. `[]` Slab matcher's programdata account (RegisterSlab / UpdateSlab proposals only)
+///
+/// Expected data layout (8 bytes):
+/// - id: u64 (8 bytes)
+fn process_execute_proposal_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.is_empty() {
+        msg!("Error: ExecuteProposal instruction requires at least 1 account");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    let mut reader = InstructionReader::new(data);
+    let id = reader.read_u64()?;
+
+    // Hash the matcher bytecode so slab proposals pin the deployed version
+    let matcher = if accounts.len() >= 3 {
+        Some(read_slab_matcher(&accounts[1], &accounts[2])?)
+    } else {
+        None
+    };
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let current_ts = Clock::get()?.unix_timestamp as u64;
+
+    process_execute_proposal(registry, id, matcher.as_ref(), current_ts)?;
+
+    msg!("ExecuteProposal processed successfully");
+    Ok(())
+}
+
+/// Process cancel proposal instruction (governance only)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Expected data layout (8 bytes):
+/// - id: u64 (8 bytes)
+fn process_cancel_proposal_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (registry, governance) = governance_accounts(program_id, accounts)?;
+
+    let mut reader = InstructionReader::new(data);
+    let id = reader.read_u64()?;
+
+    process_cancel_proposal(registry, governance, id)?;
+
+    msg!("CancelProposal processed successfully");
+    Ok(())
+}
+
+/// Process deactivate slab instruction (governance only)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Expected data layout (32 bytes):
+/// - slab_id: Pubkey (32 bytes)
+fn process_deactivate_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (registry, governance) = governance_accounts(program_id, accounts)?;
+
+    let mut reader = InstructionReader::new(data);
+    let slab_id = Pubkey::from(reader.read_bytes::<32>()?);
+
+    process_deactivate_slab(registry, governance, &slab_id)?;
+
+    msg!("DeactivateSlab processed successfully");
+    Ok(())
+}
+
+/// Process reactivate slab instruction (governance only)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Expected data layout (32 bytes):
+/// - slab_id: Pubkey (32 bytes)
+fn process_reactivate_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (registry, governance) = governance_accounts(program_id, accounts)?;
+
+    let mut reader = InstructionReader::new(data);
+    let slab_id = Pubkey::from(reader.read_bytes::<32>()?);
+
+    process_reactivate_slab(registry, governance, &slab_id)?;
+
+    msg!("ReactivateSlab processed successfully");
+    Ok(())
+}
+
+/// Process propose governance instruction (governance only)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Expected data layout (32 bytes):
+/// - new_governance: Pubkey (32 bytes)
+fn process_propose_governance_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (registry, governance) = governance_accounts(program_id, accounts)?;
+
+    let mut reader = InstructionReader::new(data);
+    let new_governance = Pubkey::from(reader.read_bytes::<32>()?);
+
+    process_propose_governance(registry, governance, new_governance)?;
+
+    msg!("ProposeGovernance processed successfully");
+    Ok(())
+}
+
+/// Process accept governance instruction (pending governance only)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Pending governance authority
+///
+/// Expected data layout: none
+fn process_accept_governance_inner(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    let (registry, pending) = governance_accounts(program_id, accounts)?;
+
+    process_accept_governance(registry, pending)?;
+
+    msg!("AcceptGovernance processed successfully");
+    Ok(())
+}
+
+/// Process update funding instruction (permissionless crank)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[]` Registered slab account (funding mark)
+/// 2. `[]` Oracle account for the slab's instrument (funding index)
+///
+/// Expected data layout: none
+fn process_update_funding_inner(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: UpdateFunding instruction requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let current_ts = Clock::get()?.unix_timestamp as u64;
+
+    process_update_funding(registry, &accounts[1], &accounts[2], current_ts)?;
+
+    msg!("UpdateFunding processed successfully");
+    Ok(())
+}
+
+/// Process quarantine slab instruction (permissionless)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[]` Slab account
+///
+/// Expected data layout: none
+fn process_quarantine_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: QuarantineSlab instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let current_slot = Clock::get()?.slot;
+
+    process_quarantine_slab(registry, &accounts[1], current_slot)?;
+
+    msg!("QuarantineSlab processed successfully");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use percolator_common::test_accounts::TestAccount;
+    use pinocchio::program_error::ProgramError;
+
+    /// Portfolio, user, vault, registry and router authority accounts
+    fn trade_accounts(user_signs: bool) -> [TestAccount; 5] {
+        let user = TestAccount::new(Pubkey::from([2; 32]), Pubkey::default(), &[]);
+        [
+            TestAccount::new(Pubkey::from([1; 32]), crate::ID, &[0; 64]),
+            if user_signs { user.signer() } else { user },
+            TestAccount::new(Pubkey::from([3; 32]), crate::ID, &[0; 64]),
+            TestAccount::new(Pubkey::from([4; 32]), crate::ID, &[0; 64]),
+            TestAccount::new(Pubkey::from([5; 32]), Pubkey::default(), &[]),
+        ]
+    }
+
+    #[test]
+    fn test_execute_cross_slab_requires_user_signature() {
+        // One buy split: num_splits, num_oracles, side, qty, limit_px
+        let mut data = [0u8; 19];
+        data[0] = 1;
+        data[3..11].copy_from_slice(&1_000_000i64.to_le_bytes());
+        data[11..19].copy_from_slice(&50_000_000_000i64.to_le_bytes());
+
+        let mut unsigned = trade_accounts(false);
+        let infos = unsigned.each_mut().map(|a| a.info());
+        assert_eq!(
+            process_execute_cross_slab_inner(&crate::ID, &infos, &data),
+            Err(ProgramError::from(PercolatorError::Unauthorized))
+        );
+
+        // A signing owner gets past the check (and fails later on the stub accounts)
+        let mut signed = trade_accounts(true);
+        let infos = signed.each_mut().map(|a| a.info());
+        assert_ne!(
+            process_execute_cross_slab_inner(&crate::ID, &infos, &data),
+            Err(ProgramError::from(PercolatorError::Unauthorized))
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Router events
+//!
+//! Events are emitted with `sol_log_data` as two slices: an 8-byte tag
+//! followed by a little-endian payload. Indexers decode by tag.
+
+use crate::state::{EmergencyMode, ExitConfig, SlabEntry, SlabRegistry};
+use pinocchio::{log::sol_log_data, pubkey::Pubkey};
+
+/// Slab registered (payload: slab entry)
+pub const SLAB_REGISTERED: &[u8; 8] = b"slab_reg";
+/// Slab parameters updated (payload: slab entry)
+pub const SLAB_UPDATED: &[u8; 8] = b"slab_upd";
+/// Slab deactivated (payload: index u16, slab_id)
+pub const SLAB_DEACTIVATED: &[u8; 8] = b"slab_off";
+/// Slab quarantined for stale quotes (payload: index u16, slab_id)
+pub const SLAB_QUARANTINED: &[u8; 8] = b"slab_qrn";
+/// Slab reactivated (payload: index u16, slab_id)
+pub const SLAB_REACTIVATED: &[u8; 8] = b"slab_on_";
+/// Global liquidation parameters updated (payload: liquidation params)
+pub const LIQUIDATION_PARAMS_UPDATED: &[u8; 8] = b"liq_parm";
+/// Withdrawal exit limits updated (payload: bucket params, thresholds)
+pub const EXIT_CONFIG_UPDATED: &[u8; 8] = b"exit_cfg";
+/// Pause flags set (payload: flags u8, emergency exit mode)
+pub const PAUSE_FLAGS_SET: &[u8; 8] = b"pause_st";
+/// Proposal queued (payload: id u64, kind u8, eta u64)
+pub const PROPOSAL_QUEUED: &[u8; 8] = b"prop_que";
+/// Proposal executed (payload: id u64, kind u8, eta u64)
+pub const PROPOSAL_EXECUTED: &[u8; 8] = b"prop_exe";
+/// Proposal cancelled (payload: id u64, kind u8, eta u64)
+pub const PROPOSAL_CANCELLED: &[u8; 8] = b"prop_can";
+/// Governance transfer proposed (payload: pending governance)
+pub const GOVERNANCE_PROPOSED: &[u8; 8] = b"gov_prop";
+/// Governance transfer accepted (payload: new governance)
+pub const GOVERNANCE_ACCEPTED: &[u8; 8] = b"gov_acpt";
+
+/// Slab entry payload length
+pub const SLAB_ENTRY_EVENT_LEN: usize = 2 + 32 * 3 + 8 * 5 + 16 + 2 + 1;
+
+/// Little-endian payload writer over a fixed buffer
+struct EventWriter<const N: usize> {
+    buf: [u8; N],
+    pos: usize,
+}
+
+impl<const N: usize> EventWriter<N> {
+    fn new() -> Self {
+        Self { buf: [0; N], pos: 0 }
+    }
+
+    fn bytes(mut self, data: &[u8]) -> Self {
+        self.buf[self.pos..self.pos + data.len()].copy_from_slice(data);
+        self.pos += data.len();
+        self
+    }
+
+    fn finish(self) -> [u8; N] {
+        debug_assert_eq!(self.pos, N);
+        self.buf
+    }
+}
+
+/// Encode a slab entry: index, slab_id, version_hash, oracle_id, imr, mmr,
+/// maker_fee_cap, taker_fee_cap, latency_sla_ms, max_exposure, instrument_idx, active
+pub fn encode_slab_entry(index: u16, entry: &SlabEntry) -> [u8; SLAB_ENTRY_EVENT_LEN] {
+    EventWriter::new()
+        .bytes(&index.to_le_bytes())
+        .bytes(entry.slab_id.as_ref())
+        .bytes(&entry.version_hash)
+        .bytes(entry.oracle_id.as_ref())
+        .bytes(&entry.imr.to_le_bytes())
+        .bytes(&entry.mmr.to_le_bytes())
+        .bytes(&entry.maker_fee_cap.to_le_bytes())
+        .bytes(&entry.taker_fee_cap.to_le_bytes())
+        .bytes(&entry.latency_sla_ms.to_le_bytes())
+        .bytes(&entry.max_exposure.to_le_bytes())
+        .bytes(&entry.instrument_idx.to_le_bytes())
+        .bytes(&[entry.active as u8])
+        .finish()
+}
+
+/// Emit a slab registered/updated event
+pub fn emit_slab_entry(tag: &[u8; 8], index: u16, entry: &SlabEntry) {
+    let payload = encode_slab_entry(index, entry);
+    sol_log_data(&[tag, &payload]);
+}
+
+/// Emit a slab status event (deactivated, quarantined, reactivated)
+pub fn emit_slab_status(tag: &[u8; 8], index: u16, slab_id: &Pubkey) {
+    let payload: [u8; 34] = EventWriter::new()
+        .bytes(&index.to_le_bytes())
+        .bytes(slab_id.as_ref())
+        .finish();
+    sol_log_data(&[tag, &payload]);
+}
+
+/// Emit a liquidation params event: imr, mmr, liq_band_bps, preliq_buffer,
+/// preliq_band_bps, router_cap_per_slab, oracle_tolerance_bps
+pub fn emit_liquidation_params(registry: &SlabRegistry) {
+    let payload: [u8; 8 * 6 + 16] = EventWriter::new()
+        .bytes(&registry.imr.to_le_bytes())
+        .bytes(&registry.mmr.to_le_bytes())
+        .bytes(&registry.liq_band_bps.to_le_bytes())
+        .bytes(&registry.preliq_buffer.to_le_bytes())
+        .bytes(&registry.preliq_band_bps.to_le_bytes())
+        .bytes(&registry.router_cap_per_slab.to_le_bytes())
+        .bytes(&registry.oracle_tolerance_bps.to_le_bytes())
+        .finish();
+    sol_log_data(&[LIQUIDATION_PARAMS_UPDATED, &payload]);
+}
+
+/// Emit the exit limits: bucket params then thresholds, in field order
+pub fn emit_exit_config(config: &ExitConfig) {
+    let (params, thresholds) = (&config.params, &config.thresholds);
+    let payload: [u8; 8 * 5 + 16 * 3] = EventWriter::new()
+        .bytes(&params.user_pct_per_hour_bps.to_le_bytes())
+        .bytes(&params.user_hard_max_per_hour.to_le_bytes())
+        .bytes(&params.rolling_window_secs.to_le_bytes())
+        .bytes(&params.tvl_pct_per_hour_bps.to_le_bytes())
+        .bytes(&params.global_hard_max_bps.to_le_bytes())
+        .bytes(&thresholds.free_pnl_threshold_per_day.to_le_bytes())
+        .bytes(&thresholds.principal_fast_lane_pct_bps.to_le_bytes())
+        .bytes(&thresholds.principal_fast_lane_hard_max.to_le_bytes())
+        .finish();
+    sol_log_data(&[EXIT_CONFIG_UPDATED, &payload]);
+}
+
+/// Emit a pause flags event: flags, emergency active, exit_multiplier_bps,
+/// expires_at_secs
+pub fn emit_pause_flags(pause_flags: u8, emergency: &EmergencyMode) {
+    let payload: [u8; 18] = EventWriter::new()
+        .bytes(&[pause_flags, emergency.active as u8])
+        .bytes(&emergency.exit_multiplier_bps.to_le_bytes())
+        .bytes(&emergency.expires_at_secs.to_le_bytes())
+        .finish();
+    sol_log_data(&[PAUSE_FLAGS_SET, &payload]);
+}
+
+/// Emit a proposal lifecycle event
+pub fn emit_proposal(tag: &[u8; 8], id: u64, kind: u8, eta: u64) {
+    let payload: [u8; 17] = EventWriter::new()
+        .bytes(&id.to_le_bytes())
+        .bytes(&[kind])
+        .bytes(&eta.to_le_bytes())
+        .finish();
+    sol_log_data(&[tag, &payload]);
+}
+
+/// Emit a governance transfer event
+pub fn emit_governance(tag: &[u8; 8], governance: &Pubkey) {
+    sol_log_data(&[tag, governance.as_ref()]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slab_entry_encoding() {
+        let entry = SlabEntry {
+            slab_id: Pubkey::from([1; 32]),
+            version_hash: [2; 32],
+            oracle_id: Pubkey::from([3; 32]),
+            imr: 500,
+            mmr: 250,
+            maker_fee_cap: 10,
+            taker_fee_cap: 20,
+            latency_sla_ms: 1000,
+            max_exposure: 7,
+            routed_notional: 0,
+            registered_ts: 0,
+            last_quote_slot: 0,
+            last_quote_seqno: 0,
+            instrument_idx: 4,
+            active: true,
+            _padding: [0; 9],
+        };
+
+        let payload = encode_slab_entry(3, &entry);
+        assert_eq!(&payload[0..2], &3u16.to_le_bytes());
+        assert_eq!(&payload[2..34], &[1; 32]);
+        assert_eq!(&payload[66..98], &[3; 32]);
+        assert_eq!(&payload[98..106], &500u64.to_le_bytes());
+        assert_eq!(&payload[138..154], &7u128.to_le_bytes());
+        assert_eq!(&payload[154..156], &4u16.to_le_bytes());
+        assert_eq!(payload[156], 1);
+    }
+}
@@ -1,23 +1,108 @@
 //! Deposit instruction - deposit collateral to vault
 
-use crate::state::Vault;
+use crate::pda::derive_vault_pda;
+use crate::state::{Portfolio, Vault};
+use crate::token::{read_token_account, transfer};
 use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
+
+/// Accounts moving tokens between a user and the vault
+pub struct VaultTransferAccounts<'a> {
+    /// Vault state account (PDA ["vault", mint], token authority)
+    pub vault: &'a AccountInfo,
+    /// Vault token account (Vault.token_account)
+    pub vault_token: &'a AccountInfo,
+    /// User token account
+    pub user_token: &'a AccountInfo,
+    /// User authority (owner of user_token)
+    pub user: &'a AccountInfo,
+    /// SPL Token program
+    pub token_program: &'a AccountInfo,
+}
+
+/// Validate vault/user token accounts for a transfer
+///
+/// Checks the vault PDA, that both token accounts hold the vault mint,
+/// that each token account is owned by the expected authority, and that
+/// the portfolio belongs to `accounts.user` under the vault's router.
+/// Callers check whether the user must also sign.
+pub fn validate_vault_transfer(
+    vault: &Vault,
+    portfolio: &Portfolio,
+    accounts: &VaultTransferAccounts,
+    program_id: &Pubkey,
+) -> Result<(), PercolatorError> {
+    let (expected_vault, _) = derive_vault_pda(&vault.mint, program_id);
+    if accounts.vault.key() != &expected_vault {
+        msg!("Error: Vault is not the PDA for its mint");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    if &portfolio.user != accounts.user.key() {
+        msg!("Error: Portfolio does not belong to user");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+    if portfolio.router_id != vault.router_id {
+        msg!("Error: Portfolio and vault belong to different routers");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+
+    if accounts.vault_token.key() != &vault.token_account {
+        msg!("Error: Vault token account mismatch");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    let vault_token = read_token_account(accounts.vault_token)?;
+    if vault_token.mint != vault.mint || &vault_token.owner != accounts.vault.key() {
+        msg!("Error: Vault token account has wrong mint or owner");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let user_token = read_token_account(accounts.user_token)?;
+    if user_token.mint != vault.mint || &user_token.owner != accounts.user.key() {
+        msg!("Error: User token account has wrong mint or owner");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    Ok(())
+}
 
 /// Process deposit instruction
 ///
-/// Deposits collateral from user's token account to the router vault.
-/// Updates vault balance and total_pledged tracking.
+/// Transfers collateral from the user's token account into the vault
+/// token account, then updates vault balance and credits the user's
+/// portfolio principal.
 pub fn process_deposit(
     vault: &mut Vault,
+    portfolio: &mut Portfolio,
+    accounts: &VaultTransferAccounts,
+    program_id: &Pubkey,
     amount: u128,
 ) -> Result<(), PercolatorError> {
     // Validate amount
     if amount == 0 {
         return Err(PercolatorError::InvalidQuantity);
     }
+    let token_amount = u64::try_from(amount).map_err(|_| PercolatorError::InvalidQuantity)?;
+
+    if !accounts.user.is_signer() {
+        msg!("Error: User must be signer");
+        return Err(PercolatorError::Unauthorized);
+    }
+    validate_vault_transfer(vault, portfolio, accounts, program_id)?;
+
+    // User signs the transfer into the vault
+    transfer(
+        accounts.token_program,
+        accounts.user_token,
+        accounts.vault_token,
+        accounts.user,
+        token_amount,
+        &[],
+    )?;
 
-    // Deposit to vault
+    // Deposit to vault and credit principal
     vault.deposit(amount);
+    portfolio.credit_deposit(amount);
 
     Ok(())
 }
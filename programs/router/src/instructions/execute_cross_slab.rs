@@ -1,6 +1,7 @@
 //! Execute cross-slab order - v0 main instruction
 
-use crate::state::{Portfolio, Vault, SlabRegistry};
+use crate::instructions::MarginAccounts;
+use crate::state::{reduces_position, Portfolio, Vault, SlabRegistry, PAUSE_TRADING};
 use percolator_common::*;
 use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
 
@@ -17,38 +18,394 @@ pub struct SlabSplit {
     pub limit_px: i64,
 }
 
+/// Aggregate of the fills applied to the taker in one execution
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FillSummary {
+    /// Signed filled quantity (+buy, -sell, 1e6 scale)
+    pub filled_qty: i64,
+    /// Sum of fill notionals: |qty| * vwap_px / 1e6
+    pub notional: u128,
+    /// Total taker fee paid
+    pub fee: u128,
+}
+
+impl FillSummary {
+    /// Encoded length: filled_qty, vwap_px, fee, unfilled_qty
+    pub const LEN: usize = 8 * 4;
+
+    /// Add one slab's fill
+    pub fn add_fill(&mut self, filled_qty: i64, notional: u128, fee: u128) {
+        self.filled_qty = self.filled_qty.saturating_add(filled_qty);
+        self.notional = self.notional.saturating_add(notional);
+        self.fee = self.fee.saturating_add(fee);
+    }
+
+    /// Aggregate VWAP across all fills (1e6 scale), 0 if nothing filled
+    pub fn vwap_px(&self) -> i64 {
+        if self.filled_qty == 0 {
+            return 0;
+        }
+        (self.notional * 1_000_000 / self.filled_qty.unsigned_abs() as u128).min(i64::MAX as u128) as i64
+    }
+
+    /// Encode for return data (little-endian):
+    /// filled_qty (i64), vwap_px (i64), fee (u64), unfilled_qty (i64)
+    pub fn encode(&self, requested_qty: i64) -> [u8; Self::LEN] {
+        let unfilled = requested_qty.saturating_sub(self.filled_qty.saturating_abs());
+        let mut out = [0u8; Self::LEN];
+        out[0..8].copy_from_slice(&self.filled_qty.to_le_bytes());
+        out[8..16].copy_from_slice(&self.vwap_px().to_le_bytes());
+        out[16..24].copy_from_slice(&(self.fee.min(u64::MAX as u128) as u64).to_le_bytes());
+        out[24..32].copy_from_slice(&unfilled.to_le_bytes());
+        out
+    }
+}
+
+/// Order-level protections checked across all splits of an execution
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrderGuards {
+    /// Worst acceptable aggregate VWAP (1e6 scale): a ceiling for buys,
+    /// a floor for sells
+    pub worst_vwap_px: Option<i64>,
+    /// Maximum total taker fee across all fills
+    pub max_total_fee: Option<u128>,
+    /// Reject any split that would increase |exposure|
+    pub reduce_only: bool,
+}
+
+impl OrderGuards {
+    /// Encoded length: flags (u8), worst_vwap_px (i64), max_total_fee (u128)
+    pub const LEN: usize = 1 + 8 + 16;
+
+    /// Flag: reduce-only
+    pub const FLAG_REDUCE_ONLY: u8 = 1 << 0;
+    /// Flag: worst_vwap_px is set
+    pub const FLAG_WORST_VWAP: u8 = 1 << 1;
+    /// Flag: max_total_fee is set
+    pub const FLAG_MAX_FEE: u8 = 1 << 2;
+
+    /// Build from the encoded flags and values
+    pub fn from_parts(flags: u8, worst_vwap_px: i64, max_total_fee: u128) -> Self {
+        Self {
+            worst_vwap_px: (flags & Self::FLAG_WORST_VWAP != 0).then_some(worst_vwap_px),
+            max_total_fee: (flags & Self::FLAG_MAX_FEE != 0).then_some(max_total_fee),
+            reduce_only: flags & Self::FLAG_REDUCE_ONLY != 0,
+        }
+    }
+
+    /// Check the splits before any fill
+    ///
+    /// Under reduce-only every split must move the portfolio's net
+    /// exposure in its instrument (across all slabs, including earlier
+    /// splits on the same instrument) toward zero without crossing it.
+    /// A worst VWAP needs all splits on one side.
+    pub fn check_splits(
+        &self,
+        registry: &SlabRegistry,
+        portfolio: &Portfolio,
+        splits: &[SlabSplit],
+    ) -> Result<(), PercolatorError> {
+        if self.worst_vwap_px.is_some() {
+            if let Some(first) = splits.iter().find(|s| s.qty != 0) {
+                if splits.iter().any(|s| s.qty != 0 && s.side != first.side) {
+                    msg!("Error: Worst VWAP requires all splits on one side");
+                    return Err(PercolatorError::InvalidSide);
+                }
+            }
+        }
+
+        if !self.reduce_only {
+            return Ok(());
+        }
+
+        for_each_net_change(registry, portfolio, splits, |current, new| {
+            if !reduces_position(current, new) {
+                msg!("Error: Reduce-only order would increase exposure");
+                return Err(PercolatorError::ReduceOnly);
+            }
+            Ok(())
+        })
+    }
+
+    /// Check the aggregate of all fills once every receipt has been read
+    pub fn check_fills(&self, summary: &FillSummary) -> Result<(), PercolatorError> {
+        if let Some(worst) = self.worst_vwap_px {
+            let vwap = summary.vwap_px();
+            let breached = (summary.filled_qty > 0 && vwap > worst)
+                || (summary.filled_qty < 0 && vwap < worst);
+            if breached {
+                msg!("Error: Aggregate VWAP worse than limit");
+                return Err(PercolatorError::SlippageExceeded);
+            }
+        }
+
+        if let Some(max_fee) = self.max_total_fee {
+            if summary.fee > max_fee {
+                msg!("Error: Total fee exceeds limit");
+                return Err(PercolatorError::FeeLimitExceeded);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Per-slab accounts for a cross-slab execution, one of each per split
+#[derive(Clone, Copy)]
+pub struct SlabAccounts<'a> {
+    /// Slab accounts to execute on
+    pub slabs: &'a [AccountInfo],
+    /// Receipt accounts the slabs write fills to
+    pub receipts: &'a [AccountInfo],
+    /// Matcher programdata accounts (version-checked)
+    pub programdata: &'a [AccountInfo],
+    /// Maker portfolios, resolved from each slab's `lp_owner`
+    pub maker_portfolios: &'a [AccountInfo],
+}
+
+impl<'a> SlabAccounts<'a> {
+    /// Split `count` slabs, receipts, programdata and maker portfolios (in
+    /// that order) off the front of `accounts`, returning the remainder
+    pub fn split(
+        accounts: &'a [AccountInfo],
+        count: usize,
+    ) -> Result<(Self, &'a [AccountInfo]), PercolatorError> {
+        if accounts.len() < count * 4 {
+            msg!("Error: Insufficient slab accounts");
+            return Err(PercolatorError::InvalidInstruction);
+        }
+
+        let slab_accounts = Self {
+            slabs: &accounts[..count],
+            receipts: &accounts[count..count * 2],
+            programdata: &accounts[count * 2..count * 3],
+            maker_portfolios: &accounts[count * 3..count * 4],
+        };
+        Ok((slab_accounts, &accounts[count * 4..]))
+    }
+
+    /// Accounts for the first `count` slabs
+    pub fn prefix(&self, count: usize) -> Self {
+        Self {
+            slabs: &self.slabs[..count],
+            receipts: &self.receipts[..count],
+            programdata: &self.programdata[..count],
+            maker_portfolios: &self.maker_portfolios[..count],
+        }
+    }
+
+    /// True if every slab has a receipt, programdata and maker portfolio
+    fn is_consistent(&self, splits: usize) -> bool {
+        self.slabs.len() == splits
+            && self.receipts.len() == splits
+            && self.programdata.len() == splits
+            && self.maker_portfolios.len() == splits
+    }
+}
+
+/// Accounts a trade reads beyond the portfolio, vault and registry
+#[derive(Clone, Copy)]
+pub struct TradeAccounts<'a> {
+    /// Router authority PDA (signs the commit_fill CPIs)
+    pub router_authority: &'a AccountInfo,
+    /// Per-split slab, receipt, programdata and maker portfolio accounts
+    pub slabs: SlabAccounts<'a>,
+    /// Oracles and AMM LP triples for marking the portfolio
+    pub margin: MarginAccounts<'a>,
+}
+
+/// Check a user order against the registry pause flags
+///
+/// Rejects all trading while paused; under reduce-only or the
+/// new-positions pause every split must be allowed against the
+/// portfolio's net exposure in its instrument (across all slabs,
+/// including earlier splits on the same instrument).
+/// Called for user-initiated orders only, so liquidations (which have
+/// their own pause flag) are not blocked by a trading pause.
+pub fn check_trading_allowed(
+    registry: &SlabRegistry,
+    portfolio: &Portfolio,
+    splits: &[SlabSplit],
+) -> Result<(), PercolatorError> {
+    if registry.is_paused(PAUSE_TRADING) {
+        msg!("Error: Trading is paused");
+        return Err(PercolatorError::ProtocolPaused);
+    }
+
+    for_each_net_change(registry, portfolio, splits, |current, new| {
+        if !registry.allows_position_change(current, new) {
+            msg!("Error: Order would increase risk while reduce-only");
+            return Err(PercolatorError::ReduceOnly);
+        }
+        Ok(())
+    })
+}
+
+/// Walk the splits in order, passing each one's (current, new) net
+/// exposure in its instrument, with earlier splits already applied
+fn for_each_net_change(
+    registry: &SlabRegistry,
+    portfolio: &Portfolio,
+    splits: &[SlabSplit],
+    mut check: impl FnMut(i64, i64) -> Result<(), PercolatorError>,
+) -> Result<(), PercolatorError> {
+    let mut nets = net_exposure_by_instrument(portfolio);
+    for split in splits {
+        let (_, entry) = registry.find_slab(&split.slab_id).ok_or_else(|| {
+            msg!("Error: Slab not registered");
+            PercolatorError::SlabNotRegistered
+        })?;
+        let net = nets
+            .get_mut(entry.instrument_idx as usize)
+            .ok_or(PercolatorError::InvalidInstrument)?;
+
+        let signed = if split.side == 0 { split.qty } else { split.qty.saturating_neg() };
+        let new = net.saturating_add(signed);
+        check(*net, new)?;
+        *net = new;
+    }
+    Ok(())
+}
+
+/// Validate a slab's fill receipt against the split it was written for
+///
+/// The receipt must have been written by this commit (used flag set and
+/// seqno equal to the seqno the router sent), must not overfill the split
+/// and must respect its limit price. Accepts either sign convention for
+/// `filled_qty`; direction comes from the split side.
+///
+/// # Returns
+/// * Signed filled quantity (+buy, -sell)
+pub fn validate_fill_receipt(
+    receipt: &FillReceipt,
+    expected_seqno: u32,
+    split: &SlabSplit,
+) -> Result<i64, PercolatorError> {
+    if !receipt.is_used() || receipt.seqno_committed != expected_seqno {
+        msg!("Error: Stale or unwritten fill receipt");
+        return Err(PercolatorError::InvalidFillReceipt);
+    }
+
+    let filled = receipt.filled_qty.unsigned_abs();
+    if filled > split.qty.unsigned_abs() || receipt.fee < 0 {
+        msg!("Error: Fill receipt exceeds order");
+        return Err(PercolatorError::InvalidFillReceipt);
+    }
+
+    if filled > 0 {
+        let within_limit = if split.side == 0 {
+            receipt.vwap_px <= split.limit_px
+        } else {
+            receipt.vwap_px >= split.limit_px
+        };
+        if receipt.vwap_px <= 0 || !within_limit {
+            msg!("Error: Fill receipt price violates limit");
+            return Err(PercolatorError::InvalidFillReceipt);
+        }
+    }
+
+    let filled = filled as i64;
+    Ok(if split.side == 0 { filled } else { -filled })
+}
+
+/// Borrow the maker (LP) portfolio behind a slab
+///
+/// The portfolio must belong to the slab's `lp_owner` under the same
+/// router. Trading against one's own slab is rejected so the taker and
+/// maker portfolios are never the same account.
+fn borrow_maker_portfolio<'a>(
+    maker_account: &'a AccountInfo,
+    header: &SlabHeader,
+    taker: &Portfolio,
+) -> Result<&'a mut Portfolio, PercolatorError> {
+    if header.lp_owner == taker.user {
+        msg!("Error: Cannot trade against own slab");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+
+    validate_owner(maker_account, &crate::ID)?;
+    validate_writable(maker_account)?;
+
+    let maker = unsafe { borrow_account_data_mut::<Portfolio>(maker_account)? };
+    if maker.user != header.lp_owner || maker.router_id != taker.router_id {
+        msg!("Error: Maker portfolio does not belong to slab LP");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+    Ok(maker)
+}
+
+/// Read a fill receipt written by a slab
+///
+/// The receipt account must be owned by the slab's program, since only
+/// the owner can have written it.
+fn read_fill_receipt(
+    receipt_account: &AccountInfo,
+    slab_account: &AccountInfo,
+) -> Result<FillReceipt, PercolatorError> {
+    if receipt_account.owner() != slab_account.owner() {
+        msg!("Error: Fill receipt not owned by slab program");
+        return Err(PercolatorError::InvalidFillReceipt);
+    }
+
+    let data = receipt_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+    if data.len() < FillReceipt::LEN {
+        msg!("Error: Fill receipt account too small");
+        return Err(PercolatorError::InvalidFillReceipt);
+    }
+
+    Ok(unsafe { core::ptr::read_unaligned(data.as_ptr() as *const FillReceipt) })
+}
+
 /// Process execute cross-slab order (v0 main instruction)
 ///
 /// This is the core v0 instruction that proves portfolio netting.
 /// Router reads QuoteCache from multiple slabs, splits the order,
-/// CPIs to each slab's commit_fill, aggregates receipts, and
-/// updates portfolio with net exposure.
+/// CPIs to each slab's commit_fill, validates each fill receipt, and
+/// updates portfolio with the actual fills.
 ///
 /// # Arguments
 /// * `portfolio` - User's portfolio account
 /// * `user` - User pubkey (signer)
 /// * `vault` - Collateral vault
 /// * `registry` - Slab registry with insurance state
-/// * `router_authority` - Router authority PDA (for CPI signing)
-/// * `slab_accounts` - Array of slab accounts to execute on
-/// * `receipt_accounts` - Array of receipt PDAs (one per slab)
+/// * `accounts` - Router authority, slab accounts, and the oracles (one
+///   per instrument traded or held after the fills) and LP triples
 /// * `splits` - How to split the order across slabs
+/// * `guards` - Order-level reduce-only, worst VWAP and max fee checks
 ///
 /// # Returns
-/// * Updates portfolio with net exposures
-/// * Accrues insurance fees from taker fills
-/// * Checks margin on net exposure (capital efficiency!)
+/// * Rejects unregistered slabs and matchers whose bytecode hash changed
+/// * Rejects slabs whose mark deviates from the oracle by more than
+///   `oracle_tolerance_bps`
+/// * Records matcher quote updates for SLA liveness tracking
+/// * Refreshes AMM LP bucket margin from live pool inventory
+/// * Updates portfolio exposures, entry prices, realized PnL and fees from
+///   each slab's fill receipt
+/// * Applies the mirror exposure and fee (net of insurance) to each maker portfolio
+/// * Accrues insurance fees from taker fill notional
+/// * Marks equity to the oracles and checks margin on net exposure per
+///   instrument at the oracle mark (capital efficiency!)
+/// * Skips zero-quantity splits
+/// * Rejects splits whose total quantity on a slab exceeds
+///   `router_cap_per_slab` and fills that push a slab's routed notional
+///   past its `max_exposure`
+/// * Rejects the whole order if an order-level guard is breached
 /// * All-or-nothing atomicity
+/// * Aggregate of the taker's fills
 pub fn process_execute_cross_slab(
     portfolio: &mut Portfolio,
     user: &Pubkey,
     vault: &mut Vault,
     registry: &mut SlabRegistry,
-    router_authority: &AccountInfo,
-    slab_accounts: &[AccountInfo],
-    receipt_accounts: &[AccountInfo],
+    accounts: TradeAccounts,
     splits: &[SlabSplit],
-) -> Result<(), PercolatorError> {
+    guards: &OrderGuards,
+) -> Result<FillSummary, PercolatorError> {
+    let TradeAccounts { router_authority, slabs: slab_accounts, margin } = accounts;
+    let oracle_accounts = margin.oracles;
+
     // Verify portfolio belongs to user
     if &portfolio.user != user {
         msg!("Error: Portfolio does not belong to user");
@@ -58,9 +415,9 @@ pub fn process_execute_cross_slab(
     // Apply PnL vesting and haircut catchup on user touch
     use crate::state::on_user_touch;
     use pinocchio::sysvars::{clock::Clock, Sysvar};
-    let current_slot = Clock::get()
-        .map(|clock| clock.slot)
-        .unwrap_or(portfolio.last_slot);
+    let clock = Clock::get().ok();
+    let current_slot = clock.map(|c| c.slot).unwrap_or(portfolio.last_slot);
+    let current_ts = clock.map(|c| c.unix_timestamp as u64).unwrap_or(0);
 
     on_user_touch(
         portfolio.principal,
@@ -73,9 +430,12 @@ pub fn process_execute_cross_slab(
         current_slot,
     );
 
+    // Revalue AMM LP exposure so it counts toward margin
+    crate::instructions::refresh_amm_lp_margin(portfolio, registry, margin.lp, current_ts)?;
+
     // Verify we have matching number of slabs and receipts
-    if slab_accounts.len() != receipt_accounts.len() || slab_accounts.len() != splits.len() {
-        msg!("Error: Mismatched slab/receipt/split counts");
+    if !slab_accounts.is_consistent(splits.len()) {
+        msg!("Error: Mismatched slab account and split counts");
         return Err(PercolatorError::InvalidInstruction);
     }
 
@@ -87,48 +447,75 @@ pub fn process_execute_cross_slab(
         return Err(PercolatorError::InvalidAccount);
     }
 
+    // Reduce-only and single-side checks before any CPI
+    guards.check_splits(registry, portfolio, splits)?;
+
     // Phase 1: Read QuoteCache from each slab (v0 - skip validation for now)
     // In production, we'd validate seqno consistency here (TOCTOU safety)
 
     // Phase 2: CPI to each slab's commit_fill
     msg!("Executing fills on slabs");
 
+    let mut summary = FillSummary::default();
     for (i, split) in splits.iter().enumerate() {
-        let slab_account = &slab_accounts[i];
-        let receipt_account = &receipt_accounts[i];
+        if split.qty == 0 {
+            continue; // Nothing routed to this slab
+        }
+        let slab_account = &slab_accounts.slabs[i];
+        let receipt_account = &slab_accounts.receipts[i];
+
+        // Per-slab quantity cap for this transaction, across all splits to the slab
+        let slab_qty = splits[..=i]
+            .iter()
+            .filter(|s| s.slab_id == split.slab_id)
+            .fold(0u64, |acc, s| acc.saturating_add(s.qty.unsigned_abs()));
+        if slab_qty > registry.router_cap_per_slab {
+            msg!("Error: Split exceeds router cap per slab");
+            return Err(PercolatorError::ExposureCapExceeded);
+        }
+
+        // Verify the matcher's bytecode against its registered hash (P11)
+        let slab_idx = crate::matcher::verify_matcher(registry, slab_account, &slab_accounts.programdata[i])?;
+        let instrument_idx = registry.slabs[slab_idx as usize].instrument_idx;
 
         // Get slab program ID from account owner
         let slab_program_id = slab_account.owner();
 
         // Read current seqno from slab for TOCTOU protection
-        let slab_data = slab_account
-            .try_borrow_data()
-            .map_err(|_| PercolatorError::InvalidAccount)?;
-        if slab_data.len() < 4 {
-            msg!("Error: Invalid slab account data");
-            return Err(PercolatorError::InvalidAccount);
+        let header = crate::matcher::read_slab_header(slab_account)?;
+        let expected_seqno = header.seqno;
+
+        // A venue mispriced against the oracle never fills
+        if !crate::instructions::slab_oracle_aligned(registry, slab_idx, &header, oracle_accounts)? {
+            msg!("Error: Slab mark deviates from oracle");
+            return Err(PercolatorError::OracleMisaligned);
+        }
+
+        // The instrument's pinned oracle, passed on so pegged venues repeg
+        let pinned_oracle = &registry.instruments[instrument_idx as usize].oracle;
+        let oracle_account = oracle_accounts
+            .iter()
+            .find(|oracle| oracle.key() == pinned_oracle)
+            .ok_or(PercolatorError::InvalidAccount)?;
+
+        // Note matcher quote updates before our own fill rewrites the cache
+        let quote_seqno = crate::instructions::read_quote_cache(slab_account)?.seqno_snapshot;
+        registry.slabs[slab_idx as usize].observe_quotes(quote_seqno, current_slot);
+
+        // Build commit_fill instruction data (22 bytes, shared with the matchers)
+        let instruction_data = CommitFillData {
+            expected_seqno,
+            side: if split.side == 0 { Side::Buy } else { Side::Sell },
+            qty: split.qty,
+            limit_px: split.limit_px,
         }
-        // Seqno is at offset 0 in SlabHeader (first field)
-        let expected_seqno = u32::from_le_bytes([
-            slab_data[0],
-            slab_data[1],
-            slab_data[2],
-            slab_data[3],
-        ]);
-
-        // Build commit_fill instruction data (22 bytes total)
-        // Layout: discriminator (1) + expected_seqno (4) + side (1) + qty (8) + limit_px (8)
-        let mut instruction_data = [0u8; 22];
-        instruction_data[0] = 1; // CommitFill discriminator
-        instruction_data[1..5].copy_from_slice(&expected_seqno.to_le_bytes());
-        instruction_data[5] = split.side;
-        instruction_data[6..14].copy_from_slice(&split.qty.to_le_bytes());
-        instruction_data[14..22].copy_from_slice(&split.limit_px.to_le_bytes());
+        .encode();
 
         // Build account metas for CPI
         // 0. slab_account (writable)
         // 1. receipt_account (writable)
         // 2. router_authority (signer PDA)
+        // 3. oracle_account (the instrument's pinned oracle)
         use pinocchio::{
             instruction::{AccountMeta, Instruction},
             program::invoke_signed,
@@ -138,6 +525,7 @@ pub fn process_execute_cross_slab(
             AccountMeta::writable(slab_account.key()),
             AccountMeta::writable(receipt_account.key()),
             AccountMeta::writable_signer(router_authority.key()),
+            AccountMeta::readonly(oracle_account.key()),
         ];
 
         let instruction = Instruction {
@@ -159,107 +547,82 @@ pub fn process_execute_cross_slab(
 
         invoke_signed(
             &instruction,
-            &[slab_account, receipt_account, router_authority],
+            &[slab_account, receipt_account, router_authority, oracle_account],
             &[signer],
         )
         .map_err(|_| PercolatorError::CpiFailed)?;
-    }
 
-    // Phase 3: Aggregate fills and update portfolio
-    // For each split, update the portfolio exposure
-    for (i, split) in splits.iter().enumerate() {
-        // In v0, assume fill is successful
-        let filled_qty = split.qty;
+        // The fill's cache update is ours, not matcher liveness
+        let quote_seqno = crate::instructions::read_quote_cache(slab_account)?.seqno_snapshot;
+        registry.slabs[slab_idx as usize].acknowledge_quotes(quote_seqno);
 
-        // Update portfolio exposure for this slab/instrument
-        // For v0, we'll use slab index and instrument 0 (simplified)
-        let slab_idx = i as u16;
-        let instrument_idx = 0u16;
+        // Phase 3: Apply the slab's actual fill from its receipt
+        let receipt = read_fill_receipt(receipt_account, slab_account)?;
+        let filled_qty = validate_fill_receipt(&receipt, expected_seqno, split)?;
 
-        // Get current exposure
-        let current_exposure = portfolio.get_exposure(slab_idx, instrument_idx);
+        // Exposure is keyed by registry slab index and instrument.
+        // Reducing or flipping realizes PnL at the fill's VWAP into portfolio.pnl,
+        // after settling funding owed on the position so far
+        let fill_px = receipt.vwap_px as u64;
+        let cum_funding = registry.instruments[instrument_idx as usize].cum_funding;
+        let old_notional = portfolio.position_notional(slab_idx, instrument_idx);
+        portfolio.apply_fill(slab_idx, instrument_idx, filled_qty, fill_px, cum_funding);
 
-        // Update based on side: Buy = add qty, Sell = subtract qty
-        let new_exposure = if split.side == 0 {
-            // Buy
-            current_exposure + filled_qty
-        } else {
-            // Sell
-            current_exposure - filled_qty
-        };
+        // Bound the notional routed through this matcher (E_max)
+        let new_notional = portfolio.position_notional(slab_idx, instrument_idx);
+        registry.slabs[slab_idx as usize].route_notional(old_notional, new_notional)?;
 
-        portfolio.update_exposure(slab_idx, instrument_idx, new_exposure);
-    }
+        // Taker fee is paid from the user's cash
+        let fee = receipt.fee as u128;
+        portfolio.charge_fee(fee);
 
-    // Phase 3.5: Accrue insurance fees from taker fills
-    // Calculate total notional across all splits and accrue insurance
-    let mut total_notional: u128 = 0;
-    for split in splits.iter() {
-        // Notional = qty * price (both in 1e6 scale, so divide by 1e6)
-        // For v0 simplified: use limit_px as execution price
-        let notional = ((split.qty.abs() as u128) * (split.limit_px.abs() as u128)) / 1_000_000;
-        total_notional = total_notional.saturating_add(notional);
-    }
+        // Accrue insurance from fill notional: |filled_qty| * vwap_px / 1e6
+        let notional = (filled_qty.unsigned_abs() as u128 * receipt.vwap_px as u128) / 1_000_000;
+        let accrual = registry.insurance_state.accrue_from_fill(notional, &registry.insurance_params);
 
-    if total_notional > 0 {
-        let accrual = registry.insurance_state.accrue_from_fill(
-            total_notional,
-            &registry.insurance_params,
-        );
-        if accrual > 0 {
-            msg!("Insurance accrued from fills");
-        }
+        // Maker takes the other side and the rest of the fee, so value is conserved
+        let maker = borrow_maker_portfolio(&slab_accounts.maker_portfolios[i], &header, portfolio)?;
+        maker.apply_fill(slab_idx, instrument_idx, -filled_qty, fill_px, cum_funding);
+        maker.credit_fee(fee.saturating_sub(accrual));
+
+        summary.add_fill(filled_qty, notional, fee);
     }
 
-    // Phase 4: Calculate IM on net exposure (THE CAPITAL EFFICIENCY PROOF!)
-    // For v0, use simplified margin calculation:
-    // - Calculate net exposure across all slabs for same instrument
-    // - IM = abs(net_exposure) * notional_value * imr_factor
-    let net_exposure = calculate_net_exposure(portfolio);
-    let im_required = calculate_initial_margin(net_exposure, splits);
+    // Order-level slippage and fee limits across all receipts
+    guards.check_fills(&summary)?;
 
-    msg!("Calculated margin on net exposure");
+    // Phase 4: Mark to market and calculate IM/MM on net exposure (THE CAPITAL EFFICIENCY PROOF!)
+    // - Equity = principal + realized PnL + unrealized PnL at the oracle marks
+    // - Net exposure across all slabs per instrument (BTC never nets against ETH)
+    // - IM/MM = sum of |net| * contract_size * oracle mark * IMR/MMR
+    crate::instructions::mark_portfolio(portfolio, registry, oracle_accounts, current_ts)?;
 
-    portfolio.update_margin(im_required, im_required / 2); // MM = IM / 2 for v0
+    msg!("Calculated margin on net exposure");
 
-    // Phase 5: Check if portfolio has sufficient margin
+    // Phase 5: Check if portfolio has sufficient margin (principal + LP buckets)
     // For v0, we assume equity is managed separately via vault
     // In production, this would check vault.equity >= portfolio.im
-    if !portfolio.has_sufficient_margin() {
+    if !portfolio.has_sufficient_margin_venue_aware() {
         msg!("Error: Insufficient margin");
         return Err(PercolatorError::PortfolioInsufficientMargin);
     }
 
     let _ = vault; // Will be used in production for equity checks
-    let _ = receipt_accounts; // Will be used for real CPI
 
     msg!("ExecuteCrossSlab completed successfully");
-    Ok(())
+    Ok(summary)
 }
 
-/// Calculate net exposure across all slabs for the same instrument (v0 simplified)
-fn calculate_net_exposure(portfolio: &Portfolio) -> i64 {
-    // For v0, sum all exposures (assuming same instrument across slabs)
-    let mut net = 0i64;
+/// Net exposure per instrument across all slabs, indexed by registry instrument index
+pub fn net_exposure_by_instrument(portfolio: &Portfolio) -> [i64; MAX_INSTRUMENTS] {
+    let mut nets = [0i64; MAX_INSTRUMENTS];
     for i in 0..portfolio.exposure_count as usize {
-        net += portfolio.exposures[i].2;
-    }
-    net
-}
-
-/// Calculate initial margin requirement (v0 simplified)
-fn calculate_initial_margin(net_exposure: i64, splits: &[SlabSplit]) -> u128 {
-    // For v0, simplified: IM = abs(net_exposure) * avg_price * 0.1 (10% IMR)
-    if splits.is_empty() {
-        return 0;
+        let (_slab_idx, instrument_idx, qty) = portfolio.exposures[i];
+        if let Some(net) = nets.get_mut(instrument_idx as usize) {
+            *net += qty;
+        }
     }
-
-    let abs_exposure = net_exposure.abs() as u128;
-    let avg_price = splits[0].limit_px as u128; // Use first split price
-
-    // IM = abs(net_exposure) * price * 0.1 / 1e6 (scale factor)
-    // For v0 proof: if net_exposure = 0, IM = 0!
-    (abs_exposure * avg_price * 10) / (100 * 1_000_000)
+    nets
 }
 
 // Exclude test module from BPF builds to avoid stack overflow from test-only functions
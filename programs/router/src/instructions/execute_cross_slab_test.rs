@@ -163,7 +163,7 @@ mod margin_calculation_tests {
 
 #[cfg(test)]
 mod net_exposure_calculation_tests {
-    use super::super::calculate_net_exposure;
+    use super::super::net_exposure_by_instrument;
     use crate::state::Portfolio;
     use pinocchio::pubkey::Pubkey;
 
@@ -180,7 +180,7 @@ mod net_exposure_calculation_tests {
         portfolio.update_exposure(1, 0, -5 * SCALE);
         portfolio.update_exposure(2, 0, 3 * SCALE);
 
-        let net = calculate_net_exposure(&portfolio);
+        let net = net_exposure_by_instrument(&portfolio)[0];
         assert_eq!(net, 8 * SCALE);
     }
 
@@ -194,7 +194,7 @@ mod net_exposure_calculation_tests {
         portfolio.update_exposure(0, 0, 10 * SCALE);
         portfolio.update_exposure(1, 0, -10 * SCALE);
 
-        let net = calculate_net_exposure(&portfolio);
+        let net = net_exposure_by_instrument(&portfolio)[0];
         assert_eq!(net, 0, "Net exposure should be zero");
 
         // When net = 0, IM calculation should yield 0
@@ -202,3 +202,280 @@ mod net_exposure_calculation_tests {
         assert_eq!(im, 0, "Zero net MUST produce zero IM");
     }
 }
+
+#[cfg(test)]
+mod fill_receipt_tests {
+    use crate::instructions::{validate_fill_receipt, FillSummary, SlabSplit};
+    use percolator_common::{FillReceipt, PercolatorError};
+    use pinocchio::pubkey::Pubkey;
+
+    const SCALE: i64 = 1_000_000;
+
+    fn split(side: u8, qty: i64, limit_px: i64) -> SlabSplit {
+        SlabSplit { slab_id: Pubkey::default(), qty, side, limit_px }
+    }
+
+    fn receipt(seqno: u32, filled_qty: i64, vwap_px: i64, fee: i64) -> FillReceipt {
+        let mut receipt = FillReceipt::new();
+        receipt.write(seqno, filled_qty, vwap_px, filled_qty.abs() * vwap_px / SCALE, fee);
+        receipt
+    }
+
+    /// Partial fills move exposure by the filled amount, not the order size
+    #[test]
+    fn test_partial_fill_uses_receipt_qty() {
+        let buy = split(0, 10 * SCALE, 50_000 * SCALE);
+        let r = receipt(7, 4 * SCALE, 49_990 * SCALE, 20 * SCALE);
+        assert_eq!(validate_fill_receipt(&r, 7, &buy), Ok(4 * SCALE));
+
+        // Sells are negative whichever sign the slab wrote
+        let sell = split(1, 10 * SCALE, 50_000 * SCALE);
+        let r = receipt(7, 3 * SCALE, 50_010 * SCALE, 0);
+        assert_eq!(validate_fill_receipt(&r, 7, &sell), Ok(-3 * SCALE));
+        let r = receipt(7, -3 * SCALE, 50_010 * SCALE, 0);
+        assert_eq!(validate_fill_receipt(&r, 7, &sell), Ok(-3 * SCALE));
+    }
+
+    /// Summary aggregates fills across slabs and encodes for return data
+    #[test]
+    fn test_fill_summary_aggregates_fills() {
+        let mut summary = FillSummary::default();
+        assert_eq!(summary.vwap_px(), 0);
+
+        // Sell 2 @ 50,000 and 1 @ 49,970
+        summary.add_fill(-2 * SCALE, 100_000 * SCALE as u128, 50 * SCALE as u128);
+        summary.add_fill(-SCALE, 49_970 * SCALE as u128, 25 * SCALE as u128);
+        assert_eq!(summary.filled_qty, -3 * SCALE);
+        assert_eq!(summary.vwap_px(), 49_990 * SCALE);
+
+        let encoded = summary.encode(5 * SCALE);
+        assert_eq!(encoded[0..8], (-3 * SCALE).to_le_bytes());
+        assert_eq!(encoded[8..16], (49_990 * SCALE).to_le_bytes());
+        assert_eq!(encoded[16..24], (75 * SCALE as u64).to_le_bytes());
+        assert_eq!(encoded[24..32], (2 * SCALE).to_le_bytes());
+    }
+
+    #[test]
+    fn test_stale_or_unwritten_receipt_rejected() {
+        let buy = split(0, SCALE, 50_000 * SCALE);
+
+        assert_eq!(
+            validate_fill_receipt(&FillReceipt::new(), 0, &buy),
+            Err(PercolatorError::InvalidFillReceipt)
+        );
+        // Receipt left over from an earlier commit
+        let r = receipt(6, SCALE, 50_000 * SCALE, 0);
+        assert_eq!(validate_fill_receipt(&r, 7, &buy), Err(PercolatorError::InvalidFillReceipt));
+    }
+
+    #[test]
+    fn test_overfill_and_limit_violations_rejected() {
+        let buy = split(0, SCALE, 50_000 * SCALE);
+        let sell = split(1, SCALE, 50_000 * SCALE);
+
+        let overfill = receipt(1, 2 * SCALE, 50_000 * SCALE, 0);
+        assert!(validate_fill_receipt(&overfill, 1, &buy).is_err());
+
+        let above_limit = receipt(1, SCALE, 50_001 * SCALE, 0);
+        assert!(validate_fill_receipt(&above_limit, 1, &buy).is_err());
+        assert!(validate_fill_receipt(&above_limit, 1, &sell).is_ok());
+
+        let below_limit = receipt(1, SCALE, 49_999 * SCALE, 0);
+        assert!(validate_fill_receipt(&below_limit, 1, &sell).is_err());
+
+        let negative_fee = receipt(1, SCALE, 50_000 * SCALE, -1);
+        assert!(validate_fill_receipt(&negative_fee, 1, &buy).is_err());
+
+        // Nothing filled: no price to check
+        let empty = receipt(1, 0, 0, 0);
+        assert_eq!(validate_fill_receipt(&empty, 1, &buy), Ok(0));
+    }
+}
+
+#[cfg(test)]
+mod instrument_netting_tests {
+    use crate::instructions::net_exposure_by_instrument;
+    use crate::state::Portfolio;
+    use pinocchio::pubkey::Pubkey;
+
+    const SCALE: i64 = 1_000_000;
+    const BTC: u16 = 0;
+    const ETH: u16 = 1;
+
+    /// Slabs trading the same instrument net; different instruments never do
+    #[test]
+    fn test_nets_are_per_instrument() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        portfolio.update_exposure(0, BTC, 10 * SCALE);
+        portfolio.update_exposure(1, BTC, -10 * SCALE);
+        portfolio.update_exposure(2, ETH, -5 * SCALE);
+        portfolio.update_exposure(3, BTC, 2 * SCALE);
+
+        let nets = net_exposure_by_instrument(&portfolio);
+        assert_eq!(nets[BTC as usize], 2 * SCALE);
+        assert_eq!(nets[ETH as usize], -5 * SCALE);
+
+        // BTC long against ETH short is not flat
+        let mut hedged = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        hedged.update_exposure(0, BTC, 5 * SCALE);
+        hedged.update_exposure(1, ETH, -5 * SCALE);
+        let nets = net_exposure_by_instrument(&hedged);
+        assert_eq!(nets[BTC as usize], 5 * SCALE);
+        assert_eq!(nets[ETH as usize], -5 * SCALE);
+    }
+}
+
+#[cfg(test)]
+mod order_guard_tests {
+    use crate::instructions::{check_trading_allowed, FillSummary, OrderGuards, SlabParams, SlabSplit};
+    use crate::state::{Portfolio, SlabRegistry, PAUSE_NEW_POSITIONS, REDUCE_ONLY};
+    use percolator_common::PercolatorError;
+    use pinocchio::pubkey::Pubkey;
+
+    const SCALE: i64 = 1_000_000;
+
+    fn split(slab: u8, side: u8, qty: i64) -> SlabSplit {
+        SlabSplit { slab_id: Pubkey::from([slab; 32]), qty, side, limit_px: 50_000 * SCALE }
+    }
+
+    fn setup() -> (SlabRegistry, Portfolio) {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        let params = SlabParams {
+            version_hash: [0; 32],
+            oracle_id: Pubkey::default(),
+            oracle: Pubkey::default(),
+            imr: 500,
+            mmr: 250,
+            maker_fee_cap: 10,
+            taker_fee_cap: 20,
+            latency_sla_ms: 100,
+            max_exposure: 1_000_000,
+        };
+        // Slabs 1 and 2 trade instrument 0, slab 3 instrument 1
+        for (slab, instrument) in [(1u8, 0u16), (2, 0), (3, 1)] {
+            registry.register_slab(Pubkey::from([slab; 32]), instrument, &params, 0).unwrap();
+        }
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.update_exposure(0, 0, 5 * SCALE); // long 5 on slab 1
+        (registry, portfolio)
+    }
+
+    #[test]
+    fn test_reduce_only_rejects_increasing_splits() {
+        let (registry, portfolio) = setup();
+        let guards = OrderGuards { reduce_only: true, ..OrderGuards::default() };
+
+        // Selling down the long is fine, in one split or two, on any slab of the instrument
+        assert_eq!(guards.check_splits(&registry, &portfolio, &[split(1, 1, 5 * SCALE)]), Ok(()));
+        assert_eq!(
+            guards.check_splits(&registry, &portfolio, &[split(1, 1, 3 * SCALE), split(2, 1, 2 * SCALE)]),
+            Ok(())
+        );
+        assert_eq!(guards.check_splits(&registry, &portfolio, &[split(2, 1, SCALE)]), Ok(()));
+
+        // Adding, flipping (also across slabs) or opening another instrument is not
+        for splits in [
+            &[split(1, 0, SCALE)][..],
+            &[split(2, 0, SCALE)][..],
+            &[split(1, 1, 6 * SCALE)][..],
+            &[split(1, 1, 3 * SCALE), split(2, 1, 3 * SCALE)][..],
+            &[split(3, 1, SCALE)][..],
+        ] {
+            assert_eq!(guards.check_splits(&registry, &portfolio, splits), Err(PercolatorError::ReduceOnly));
+        }
+
+        // Without the flag anything goes
+        assert_eq!(OrderGuards::default().check_splits(&registry, &portfolio, &[split(3, 1, SCALE)]), Ok(()));
+    }
+
+    #[test]
+    fn test_reduce_only_uses_net_instrument_exposure() {
+        let (registry, mut portfolio) = setup();
+        let guards = OrderGuards { reduce_only: true, ..OrderGuards::default() };
+
+        // Long 5 on slab 1 hedged by short 5 on slab 2: flat in the instrument
+        portfolio.update_exposure(1, 0, -5 * SCALE);
+        for splits in [&[split(1, 1, SCALE)][..], &[split(2, 0, SCALE)][..]] {
+            assert_eq!(guards.check_splits(&registry, &portfolio, splits), Err(PercolatorError::ReduceOnly));
+        }
+
+        // Net short 2: only buys reduce it, whichever slab they route to
+        portfolio.update_exposure(0, 0, 3 * SCALE);
+        assert_eq!(guards.check_splits(&registry, &portfolio, &[split(1, 0, 2 * SCALE)]), Ok(()));
+        assert_eq!(
+            guards.check_splits(&registry, &portfolio, &[split(1, 0, SCALE), split(2, 0, 2 * SCALE)]),
+            Err(PercolatorError::ReduceOnly)
+        );
+    }
+
+    #[test]
+    fn test_pause_flags_use_net_instrument_exposure() {
+        let (mut registry, portfolio) = setup();
+
+        for flag in [REDUCE_ONLY, PAUSE_NEW_POSITIONS] {
+            registry.pause_flags = flag;
+
+            // Selling down the long on either slab of the instrument is fine
+            assert_eq!(check_trading_allowed(&registry, &portfolio, &[split(2, 1, 5 * SCALE)]), Ok(()));
+
+            // Two reducing splits that together flip the position are not
+            assert_eq!(
+                check_trading_allowed(&registry, &portfolio, &[split(1, 1, 3 * SCALE), split(2, 1, 3 * SCALE)]),
+                Err(PercolatorError::ReduceOnly)
+            );
+            assert_eq!(
+                check_trading_allowed(&registry, &portfolio, &[split(2, 1, 6 * SCALE)]),
+                Err(PercolatorError::ReduceOnly)
+            );
+        }
+
+        // Opening a position in another instrument is a new position
+        registry.pause_flags = PAUSE_NEW_POSITIONS;
+        assert_eq!(
+            check_trading_allowed(&registry, &portfolio, &[split(3, 1, SCALE)]),
+            Err(PercolatorError::ReduceOnly)
+        );
+        // ...while adding to the net long is allowed, but not under reduce-only
+        assert_eq!(check_trading_allowed(&registry, &portfolio, &[split(2, 0, SCALE)]), Ok(()));
+        registry.pause_flags = REDUCE_ONLY;
+        assert_eq!(
+            check_trading_allowed(&registry, &portfolio, &[split(2, 0, SCALE)]),
+            Err(PercolatorError::ReduceOnly)
+        );
+    }
+
+    #[test]
+    fn test_worst_vwap_and_max_fee() {
+        let (registry, portfolio) = setup();
+        let guards = OrderGuards::from_parts(
+            OrderGuards::FLAG_WORST_VWAP | OrderGuards::FLAG_MAX_FEE,
+            50_000 * SCALE,
+            100 * SCALE as u128,
+        );
+        assert!(!guards.reduce_only);
+
+        // Mixed sides have no meaningful aggregate VWAP
+        assert_eq!(
+            guards.check_splits(&registry, &portfolio, &[split(1, 0, SCALE), split(2, 1, SCALE)]),
+            Err(PercolatorError::InvalidSide)
+        );
+
+        // Buy 2 @ 49,990 and 50,030: VWAP 50,010 is above the ceiling
+        let mut buy = FillSummary::default();
+        buy.add_fill(SCALE, 49_990 * SCALE as u128, 0);
+        assert_eq!(guards.check_fills(&buy), Ok(()));
+        buy.add_fill(SCALE, 50_030 * SCALE as u128, 0);
+        assert_eq!(guards.check_fills(&buy), Err(PercolatorError::SlippageExceeded));
+
+        // Sell at 50,010 clears the floor, but the fee is over the limit
+        let mut sell = FillSummary::default();
+        sell.add_fill(-SCALE, 50_010 * SCALE as u128, 100 * SCALE as u128);
+        assert_eq!(guards.check_fills(&sell), Ok(()));
+        sell.add_fill(0, 0, 1);
+        assert_eq!(guards.check_fills(&sell), Err(PercolatorError::FeeLimitExceeded));
+
+        // Unset guards never reject
+        assert_eq!(OrderGuards::from_parts(0, 1, 0).check_fills(&buy), Ok(()));
+    }
+}
@@ -0,0 +1,143 @@
+//! Execute order instruction - routes a single order across slabs
+
+use crate::chooser::{split_order, MAX_ROUTE_SLABS};
+use crate::instructions::{
+    check_trading_allowed, observe_matcher_quotes, process_execute_cross_slab,
+    slab_oracle_aligned, FillSummary, OrderGuards, SlabSplit, TradeAccounts,
+};
+use crate::state::{Portfolio, SlabRegistry, Vault};
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
+
+/// Read a slab's QuoteCache (stored right after its header)
+pub fn read_quote_cache(slab_account: &AccountInfo) -> Result<QuoteCache, PercolatorError> {
+    let data = slab_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+    if data.len() < SlabHeader::LEN + QuoteCache::LEN {
+        msg!("Error: Slab account too small for quote cache");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    Ok(unsafe { core::ptr::read_unaligned(data[SlabHeader::LEN..].as_ptr() as *const QuoteCache) })
+}
+
+/// An order to route across slabs
+#[derive(Debug, Clone, Copy)]
+pub struct MarketOrder {
+    /// Side (0 = buy, 1 = sell)
+    pub side: u8,
+    /// Order quantity (1e6 scale, positive)
+    pub qty: i64,
+    /// Worst acceptable price (1e6 scale)
+    pub limit_px: i64,
+}
+
+/// Process execute order instruction
+///
+/// Reads the QuoteCache of every passed slab whose mark is aligned with
+/// its instrument's oracle and whose quotes are within its latency SLA
+/// (other slabs are skipped), splits the order across them by walking
+/// price levels best-first up to `limit_px` (at most
+/// `router_cap_per_slab` quantity per slab), and executes the split through the
+/// cross-slab path (commit_fill CPIs, margin check, settlement). Fills
+/// immediately or not at all per slab: quantity not covered by quoted
+/// liquidity within the limit is left unfilled.
+///
+/// # Arguments
+/// * `accounts` - Router authority, candidate slab accounts, oracles and LP triples
+/// * `order` - Side, quantity and limit price to route
+/// * `current_slot` - Clock slot the quote latency SLA is checked against
+///
+/// # Returns
+/// * Aggregate of the fills
+pub fn process_execute_order(
+    portfolio: &mut Portfolio,
+    user: &Pubkey,
+    vault: &mut Vault,
+    registry: &mut SlabRegistry,
+    accounts: TradeAccounts,
+    order: &MarketOrder,
+    current_slot: u64,
+) -> Result<FillSummary, PercolatorError> {
+    let MarketOrder { side, qty, limit_px } = *order;
+    let slab_accounts = accounts.slabs;
+    if side > 1 {
+        msg!("Error: Invalid side");
+        return Err(PercolatorError::InvalidSide);
+    }
+    if qty <= 0 {
+        msg!("Error: Order quantity must be positive");
+        return Err(PercolatorError::InvalidQuantity);
+    }
+    if limit_px <= 0 {
+        msg!("Error: Limit price must be positive");
+        return Err(PercolatorError::InvalidPrice);
+    }
+
+    let num_slabs = slab_accounts.slabs.len();
+    if num_slabs == 0 || num_slabs > MAX_ROUTE_SLABS {
+        msg!("Error: Invalid number of slabs");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    // Snapshot each aligned, live venue's quotes; other venues get none
+    let mut caches = [QuoteCache::new(); MAX_ROUTE_SLABS];
+    for (cache, slab_account) in caches.iter_mut().zip(slab_accounts.slabs.iter()) {
+        let (slab_idx, _) = registry.find_slab(slab_account.key()).ok_or_else(|| {
+            msg!("Error: Slab not registered");
+            PercolatorError::SlabNotRegistered
+        })?;
+        let header = crate::matcher::read_slab_header(slab_account)?;
+        if !slab_oracle_aligned(registry, slab_idx, &header, accounts.margin.oracles)? {
+            msg!("Skipping slab misaligned with oracle");
+            continue;
+        }
+        if !observe_matcher_quotes(registry, slab_idx, slab_account, current_slot)? {
+            msg!("Skipping slab with stale quotes");
+            continue;
+        }
+        *cache = read_quote_cache(slab_account)?;
+    }
+
+    // Route across venues
+    let mut alloc = [0i64; MAX_ROUTE_SLABS];
+    let max_per_slab = registry.router_cap_qty();
+    let routed = split_order(
+        &caches[..num_slabs],
+        side,
+        qty,
+        limit_px,
+        max_per_slab,
+        &mut alloc[..num_slabs],
+    );
+    if routed == 0 {
+        msg!("Error: No liquidity within limit");
+        return Err(PercolatorError::InsufficientLiquidity);
+    }
+
+    let mut splits_buffer = [SlabSplit {
+        slab_id: Pubkey::default(),
+        qty: 0,
+        side,
+        limit_px,
+    }; MAX_ROUTE_SLABS];
+    for (i, split) in splits_buffer[..num_slabs].iter_mut().enumerate() {
+        split.slab_id = *slab_accounts.slabs[i].key();
+        split.qty = alloc[i];
+    }
+    let splits = &splits_buffer[..num_slabs];
+
+    // Pause / reduce-only gate (user order)
+    check_trading_allowed(registry, portfolio, splits)?;
+
+    process_execute_cross_slab(
+        portfolio,
+        user,
+        vault,
+        registry,
+        accounts,
+        splits,
+        &OrderGuards::default(),
+    )
+}
@@ -0,0 +1,575 @@
+//! Governance instructions - slab (matcher) whitelist, global risk params,
+//! time-locked proposals and two-step governance transfer
+
+use crate::events;
+use crate::instructions::RouterInstruction;
+use crate::matcher::SlabMatcher;
+use crate::state::{ExitBucketParams, Proposals, SlabRegistry, WithdrawalThresholds, MAX_TIMELOCK_SECS};
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
+
+/// Per-slab parameters set by governance
+#[derive(Debug, Clone, Copy)]
+pub struct SlabParams {
+    /// Version hash of the matcher program
+    pub version_hash: [u8; 32],
+    /// Oracle program ID for price feeds
+    pub oracle_id: Pubkey,
+    /// Oracle price account for the slab's instrument (owned by `oracle_id`)
+    pub oracle: Pubkey,
+    /// Initial margin ratio (basis points)
+    pub imr: u64,
+    /// Maintenance margin ratio (basis points)
+    pub mmr: u64,
+    /// Maximum maker fee (basis points)
+    pub maker_fee_cap: u64,
+    /// Maximum taker fee (basis points)
+    pub taker_fee_cap: u64,
+    /// Latency SLA (milliseconds)
+    pub latency_sla_ms: u64,
+    /// Maximum exposure per user (per instrument)
+    pub max_exposure: u128,
+}
+
+impl SlabParams {
+    /// Check ratios and fee caps are sane: 0 < MMR <= IMR <= 100%, fees <= 100%,
+    /// and an oracle account is set
+    pub fn validate(&self) -> Result<(), PercolatorError> {
+        if self.mmr == 0 || self.mmr > self.imr || self.imr > 10_000 {
+            msg!("Error: Invalid IMR/MMR");
+            return Err(PercolatorError::InvalidRiskParams);
+        }
+        if self.maker_fee_cap > 10_000 || self.taker_fee_cap > 10_000 {
+            msg!("Error: Invalid fee caps");
+            return Err(PercolatorError::InvalidRiskParams);
+        }
+        if self.oracle == Pubkey::default() {
+            msg!("Error: Slab params require an oracle account");
+            return Err(PercolatorError::InvalidAccount);
+        }
+        Ok(())
+    }
+}
+
+/// Global liquidation parameters set by governance
+#[derive(Debug, Clone, Copy)]
+pub struct LiquidationParams {
+    pub imr: u64,
+    pub mmr: u64,
+    pub liq_band_bps: u64,
+    pub preliq_buffer: i128,
+    pub preliq_band_bps: u64,
+    pub router_cap_per_slab: u64,
+    pub oracle_tolerance_bps: u64,
+}
+
+/// Require the registry governance authority to have signed
+pub fn require_governance(registry: &SlabRegistry, governance: &AccountInfo) -> Result<(), PercolatorError> {
+    if !governance.is_signer() || governance.key() != &registry.governance {
+        msg!("Error: Governance must sign");
+        return Err(PercolatorError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Action carried by a time-locked proposal
+#[derive(Debug, Clone, Copy)]
+pub enum GovernanceAction {
+    /// Whitelist a matcher
+    RegisterSlab { slab_id: Pubkey, params: SlabParams },
+    /// Replace a matcher's parameters
+    UpdateSlab { slab_id: Pubkey, params: SlabParams },
+    /// Replace global margin and liquidation parameters
+    UpdateLiquidationParams(LiquidationParams),
+    /// Change the proposal timelock
+    SetTimelock(u64),
+    /// Replace withdrawal exit bucket limits and thresholds
+    UpdateExitConfig { params: ExitBucketParams, thresholds: WithdrawalThresholds },
+}
+
+/// Read slab parameters
+///
+/// Layout (168 bytes):
+/// - version_hash: [u8; 32]
+/// - oracle_id: Pubkey (32 bytes)
+/// - oracle: Pubkey (32 bytes)
+/// - imr, mmr, maker_fee_cap, taker_fee_cap, latency_sla_ms: u64 (8 bytes each)
+/// - max_exposure: u128 (16 bytes)
+fn read_slab_params(reader: &mut InstructionReader) -> Result<SlabParams, PercolatorError> {
+    Ok(SlabParams {
+        version_hash: reader.read_bytes::<32>()?,
+        oracle_id: Pubkey::from(reader.read_bytes::<32>()?),
+        oracle: Pubkey::from(reader.read_bytes::<32>()?),
+        imr: reader.read_u64()?,
+        mmr: reader.read_u64()?,
+        maker_fee_cap: reader.read_u64()?,
+        taker_fee_cap: reader.read_u64()?,
+        latency_sla_ms: reader.read_u64()?,
+        max_exposure: reader.read_u128()?,
+    })
+}
+
+impl GovernanceAction {
+    /// Decode and validate an action from its queuing instruction's data
+    ///
+    /// `kind` is the queuing instruction's discriminator:
+    /// - RegisterSlab / UpdateSlab: slab_id (32 bytes) + slab params (168 bytes)
+    /// - UpdateLiquidationParams: imr, mmr, liq_band_bps (u64), preliq_buffer (i128),
+    ///   preliq_band_bps, router_cap_per_slab (quantity), oracle_tolerance_bps (u64)
+    /// - SetTimelock: timelock_secs (u64)
+    /// - UpdateExitConfig: user_pct_per_hour_bps (u64), user_hard_max_per_hour (i128),
+    ///   rolling_window_secs, tvl_pct_per_hour_bps, global_hard_max_bps (u64),
+    ///   free_pnl_threshold_per_day (i128), principal_fast_lane_pct_bps (u64),
+    ///   principal_fast_lane_hard_max (i128)
+    pub fn decode(kind: u8, payload: &[u8]) -> Result<Self, PercolatorError> {
+        let mut reader = InstructionReader::new(payload);
+
+        let action = match kind {
+            k if k == RouterInstruction::RegisterSlab as u8 || k == RouterInstruction::UpdateSlab as u8 => {
+                let slab_id = Pubkey::from(reader.read_bytes::<32>()?);
+                let params = read_slab_params(&mut reader)?;
+                params.validate()?;
+                if k == RouterInstruction::RegisterSlab as u8 {
+                    GovernanceAction::RegisterSlab { slab_id, params }
+                } else {
+                    GovernanceAction::UpdateSlab { slab_id, params }
+                }
+            }
+            k if k == RouterInstruction::UpdateLiquidationParams as u8 => {
+                let params = LiquidationParams {
+                    imr: reader.read_u64()?,
+                    mmr: reader.read_u64()?,
+                    liq_band_bps: reader.read_u64()?,
+                    preliq_buffer: reader.read_u128()? as i128,
+                    preliq_band_bps: reader.read_u64()?,
+                    router_cap_per_slab: reader.read_u64()?,
+                    oracle_tolerance_bps: reader.read_u64()?,
+                };
+                if params.mmr == 0 || params.mmr > params.imr || params.imr > 10_000 || params.preliq_buffer < 0 {
+                    msg!("Error: Invalid liquidation params");
+                    return Err(PercolatorError::InvalidRiskParams);
+                }
+                GovernanceAction::UpdateLiquidationParams(params)
+            }
+            k if k == RouterInstruction::SetTimelock as u8 => {
+                let timelock_secs = reader.read_u64()?;
+                if timelock_secs > MAX_TIMELOCK_SECS {
+                    msg!("Error: Timelock too long");
+                    return Err(PercolatorError::InvalidInstruction);
+                }
+                GovernanceAction::SetTimelock(timelock_secs)
+            }
+            k if k == RouterInstruction::UpdateExitConfig as u8 => {
+                let params = ExitBucketParams {
+                    user_pct_per_hour_bps: reader.read_u64()?,
+                    user_hard_max_per_hour: reader.read_u128()? as i128,
+                    rolling_window_secs: reader.read_u64()?,
+                    tvl_pct_per_hour_bps: reader.read_u64()?,
+                    global_hard_max_bps: reader.read_u64()?,
+                };
+                let thresholds = WithdrawalThresholds {
+                    free_pnl_threshold_per_day: reader.read_u128()? as i128,
+                    principal_fast_lane_pct_bps: reader.read_u64()?,
+                    principal_fast_lane_hard_max: reader.read_u128()? as i128,
+                };
+                if !params.is_valid() || !thresholds.is_valid() {
+                    msg!("Error: Invalid exit limits");
+                    return Err(PercolatorError::InvalidRiskParams);
+                }
+                GovernanceAction::UpdateExitConfig { params, thresholds }
+            }
+            _ => {
+                msg!("Error: Unknown proposal kind");
+                return Err(PercolatorError::InvalidInstruction);
+            }
+        };
+
+        Ok(action)
+    }
+
+    /// Apply the action to the registry, emitting the matching event
+    ///
+    /// Slab actions require the matcher's current bytecode hash, which must
+    /// equal the proposed `version_hash`: a matcher upgraded during the
+    /// timelock cannot be registered under the old hash.
+    pub fn apply(
+        &self,
+        registry: &mut SlabRegistry,
+        matcher: Option<&SlabMatcher>,
+        current_ts: u64,
+    ) -> Result<(), PercolatorError> {
+        if let GovernanceAction::RegisterSlab { slab_id, params } | GovernanceAction::UpdateSlab { slab_id, params } = self {
+            let matcher = matcher.ok_or_else(|| {
+                msg!("Error: Slab proposal requires the matcher programdata");
+                PercolatorError::InvalidAccount
+            })?;
+            if &matcher.slab_id != slab_id {
+                msg!("Error: Matcher does not belong to the proposed slab");
+                return Err(PercolatorError::InvalidSlab);
+            }
+            if matcher.version_hash != params.version_hash {
+                msg!("Error: Matcher bytecode does not match proposed version hash");
+                return Err(PercolatorError::SlabVersionMismatch);
+            }
+        }
+
+        match self {
+            GovernanceAction::RegisterSlab { slab_id, params } => {
+                if registry.find_slab(slab_id).is_some() {
+                    msg!("Error: Slab already registered");
+                    return Err(PercolatorError::InvalidSlab);
+                }
+
+                let matcher = matcher.ok_or(PercolatorError::InvalidAccount)?;
+                if matcher.contract_size == 0 {
+                    msg!("Error: Slab has no contract size");
+                    return Err(PercolatorError::InvalidInstrument);
+                }
+                let instrument_idx =
+                    registry.add_instrument(&matcher.instrument, matcher.contract_size, &params.oracle)?;
+
+                let idx = registry
+                    .register_slab(*slab_id, instrument_idx, params, current_ts)
+                    .map_err(|_| {
+                        msg!("Error: Slab registry is full");
+                        PercolatorError::PoolFull
+                    })?;
+
+                events::emit_slab_entry(events::SLAB_REGISTERED, idx, &registry.slabs[idx as usize]);
+            }
+            GovernanceAction::UpdateSlab { slab_id, params } => {
+                let idx = registry.update_slab(slab_id, params)?;
+
+                events::emit_slab_entry(events::SLAB_UPDATED, idx, &registry.slabs[idx as usize]);
+            }
+            GovernanceAction::UpdateLiquidationParams(params) => {
+                registry.update_liquidation_params(
+                    params.imr,
+                    params.mmr,
+                    params.liq_band_bps,
+                    params.preliq_buffer,
+                    params.preliq_band_bps,
+                    params.router_cap_per_slab,
+                    params.oracle_tolerance_bps,
+                );
+
+                events::emit_liquidation_params(registry);
+            }
+            GovernanceAction::SetTimelock(timelock_secs) => {
+                registry.proposals.timelock_secs = *timelock_secs;
+            }
+            GovernanceAction::UpdateExitConfig { params, thresholds } => {
+                // The emergency multiplier stays with SetPauseFlags
+                registry.exit_config.params = *params;
+                registry.exit_config.thresholds = *thresholds;
+
+                events::emit_exit_config(&registry.exit_config);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Process queue proposal (RegisterSlab, UpdateSlab, UpdateLiquidationParams, SetTimelock,
+/// UpdateExitConfig)
+///
+/// Validates the action now and queues it to execute after the
+/// timelock. Emits `PROPOSAL_QUEUED`.
+///
+/// # Returns
+/// * Proposal ID
+pub fn process_queue_proposal(
+    registry: &mut SlabRegistry,
+    governance: &AccountInfo,
+    kind: u8,
+    payload: &[u8],
+    current_ts: u64,
+) -> Result<u64, PercolatorError> {
+    require_governance(registry, governance)?;
+    GovernanceAction::decode(kind, payload)?;
+
+    let (id, eta) = registry.proposals.queue(kind, payload, current_ts)?;
+
+    events::emit_proposal(events::PROPOSAL_QUEUED, id, kind, eta);
+    Ok(id)
+}
+
+/// Process execute proposal (permissionless once the timelock has passed)
+///
+/// `matcher` is the slab's current matcher version, required for
+/// RegisterSlab and UpdateSlab proposals.
+/// Emits `PROPOSAL_EXECUTED` plus the action's own event.
+pub fn process_execute_proposal(
+    registry: &mut SlabRegistry,
+    id: u64,
+    matcher: Option<&SlabMatcher>,
+    current_ts: u64,
+) -> Result<(), PercolatorError> {
+    let proposal = *registry.proposals.find(id).ok_or_else(|| {
+        msg!("Error: Proposal not found");
+        PercolatorError::InvalidInstruction
+    })?;
+
+    if !Proposals::is_executable(&proposal, current_ts) {
+        msg!("Error: Proposal is not executable (timelock or grace period)");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    let action = GovernanceAction::decode(proposal.kind, proposal.payload())?;
+    action.apply(registry, matcher, current_ts)?;
+    registry.proposals.take(id);
+
+    events::emit_proposal(events::PROPOSAL_EXECUTED, id, proposal.kind, proposal.eta);
+    Ok(())
+}
+
+/// Process cancel proposal (governance only). Emits `PROPOSAL_CANCELLED`.
+pub fn process_cancel_proposal(
+    registry: &mut SlabRegistry,
+    governance: &AccountInfo,
+    id: u64,
+) -> Result<(), PercolatorError> {
+    require_governance(registry, governance)?;
+
+    let proposal = registry.proposals.take(id).ok_or_else(|| {
+        msg!("Error: Proposal not found");
+        PercolatorError::InvalidInstruction
+    })?;
+
+    events::emit_proposal(events::PROPOSAL_CANCELLED, id, proposal.kind, proposal.eta);
+    Ok(())
+}
+
+/// Process deactivate slab instruction
+///
+/// Removes a slab from routing immediately (emergency action, not
+/// time-locked). Emits `SLAB_DEACTIVATED`.
+pub fn process_deactivate_slab(
+    registry: &mut SlabRegistry,
+    governance: &AccountInfo,
+    slab_id: &Pubkey,
+) -> Result<(), PercolatorError> {
+    require_governance(registry, governance)?;
+
+    let (idx, _) = registry.find_slab(slab_id).ok_or_else(|| {
+        msg!("Error: Slab not registered");
+        PercolatorError::SlabNotRegistered
+    })?;
+    registry.slabs[idx as usize].active = false;
+
+    events::emit_slab_status(events::SLAB_DEACTIVATED, idx, slab_id);
+    Ok(())
+}
+
+/// Process reactivate slab instruction
+///
+/// Returns a deactivated or quarantined slab to routing immediately, at
+/// its existing registry index. Emits `SLAB_REACTIVATED`.
+pub fn process_reactivate_slab(
+    registry: &mut SlabRegistry,
+    governance: &AccountInfo,
+    slab_id: &Pubkey,
+) -> Result<(), PercolatorError> {
+    require_governance(registry, governance)?;
+
+    let idx = registry.reactivate_slab(slab_id)?;
+
+    events::emit_slab_status(events::SLAB_REACTIVATED, idx, slab_id);
+    Ok(())
+}
+
+/// Process propose governance (step 1 of transfer, current governance only)
+///
+/// Pass the default pubkey to withdraw a pending proposal.
+/// Emits `GOVERNANCE_PROPOSED`.
+pub fn process_propose_governance(
+    registry: &mut SlabRegistry,
+    governance: &AccountInfo,
+    new_governance: Pubkey,
+) -> Result<(), PercolatorError> {
+    require_governance(registry, governance)?;
+
+    registry.pending_governance = new_governance;
+
+    events::emit_governance(events::GOVERNANCE_PROPOSED, &new_governance);
+    Ok(())
+}
+
+/// Process accept governance (step 2 of transfer, pending governance signs)
+///
+/// Emits `GOVERNANCE_ACCEPTED`.
+pub fn process_accept_governance(
+    registry: &mut SlabRegistry,
+    pending: &AccountInfo,
+) -> Result<(), PercolatorError> {
+    if registry.pending_governance == Pubkey::default()
+        || !pending.is_signer()
+        || pending.key() != &registry.pending_governance
+    {
+        msg!("Error: Pending governance must sign");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    registry.governance = registry.pending_governance;
+    registry.pending_governance = Pubkey::default();
+
+    events::emit_governance(events::GOVERNANCE_ACCEPTED, &registry.governance);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::MAX_EXIT_WINDOW_SECS;
+
+    fn params() -> SlabParams {
+        SlabParams {
+            version_hash: [0; 32],
+            oracle_id: Pubkey::default(),
+            oracle: Pubkey::from([5; 32]),
+            imr: 500,
+            mmr: 250,
+            maker_fee_cap: 10,
+            taker_fee_cap: 20,
+            latency_sla_ms: 1000,
+            max_exposure: 1_000_000,
+        }
+    }
+
+    #[test]
+    fn test_slab_params_validation() {
+        assert!(params().validate().is_ok());
+        assert!(SlabParams { mmr: 0, ..params() }.validate().is_err());
+        assert!(SlabParams { mmr: 600, ..params() }.validate().is_err());
+        assert!(SlabParams { imr: 20_000, mmr: 250, ..params() }.validate().is_err());
+        assert!(SlabParams { taker_fee_cap: 10_001, ..params() }.validate().is_err());
+        assert!(SlabParams { oracle: Pubkey::default(), ..params() }.validate().is_err());
+    }
+
+    fn register_payload(slab_id: Pubkey, imr: u64, oracle: Pubkey) -> [u8; 200] {
+        let mut payload = [0u8; 200];
+        payload[0..32].copy_from_slice(slab_id.as_ref());
+        payload[32..64].copy_from_slice(&[9; 32]); // version_hash
+        payload[96..128].copy_from_slice(oracle.as_ref());
+        payload[128..136].copy_from_slice(&imr.to_le_bytes());
+        payload[136..144].copy_from_slice(&250u64.to_le_bytes());
+        payload
+    }
+
+    #[test]
+    fn test_time_locked_registration() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        registry.proposals.timelock_secs = 100;
+        let slab_id = Pubkey::from([7; 32]);
+
+        let kind = RouterInstruction::RegisterSlab as u8;
+        let (id, _) = registry.proposals.queue(kind, &register_payload(slab_id, 500, Pubkey::from([5; 32])), 1_000).unwrap();
+
+        let matcher = SlabMatcher {
+            slab_id,
+            instrument: Pubkey::from([3; 32]),
+            contract_size: 1_000_000,
+            version_hash: [9; 32],
+        };
+
+        // Too early
+        assert_eq!(
+            process_execute_proposal(&mut registry, id, Some(&matcher), 1_050),
+            Err(PercolatorError::Unauthorized)
+        );
+        assert!(registry.find_slab(&slab_id).is_none());
+
+        // Matcher programdata missing or upgraded since the proposal
+        assert_eq!(
+            process_execute_proposal(&mut registry, id, None, 1_100),
+            Err(PercolatorError::InvalidAccount)
+        );
+        let upgraded = SlabMatcher { version_hash: [8; 32], ..matcher };
+        assert_eq!(
+            process_execute_proposal(&mut registry, id, Some(&upgraded), 1_100),
+            Err(PercolatorError::SlabVersionMismatch)
+        );
+
+        // After the timelock, anyone can execute once
+        process_execute_proposal(&mut registry, id, Some(&matcher), 1_100).unwrap();
+        assert_eq!(registry.find_slab(&slab_id).unwrap().1.imr, 500);
+        assert_eq!(registry.find_slab(&slab_id).unwrap().1.version_hash, [9; 32]);
+        assert_eq!(registry.find_instrument(&matcher.instrument), Some(0));
+        assert_eq!(registry.instruments[0].oracle, Pubkey::from([5; 32]));
+        assert!(process_execute_proposal(&mut registry, id, Some(&matcher), 1_100).is_err());
+
+        // A second slab on the instrument cannot bring a different oracle
+        let other = SlabMatcher { slab_id: Pubkey::from([8; 32]), ..matcher };
+        let payload = register_payload(other.slab_id, 500, Pubkey::from([6; 32]));
+        let (id, _) = registry.proposals.queue(kind, &payload, 1_100).unwrap();
+        assert_eq!(
+            process_execute_proposal(&mut registry, id, Some(&other), 1_200),
+            Err(PercolatorError::InvalidInstrument)
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_actions() {
+        let kind = RouterInstruction::RegisterSlab as u8;
+        // MMR > IMR
+        assert!(GovernanceAction::decode(kind, &register_payload(Pubkey::default(), 100, Pubkey::from([5; 32]))).is_err());
+        // No oracle account
+        assert!(GovernanceAction::decode(kind, &register_payload(Pubkey::default(), 500, Pubkey::default())).is_err());
+        // Truncated
+        assert!(GovernanceAction::decode(kind, &[0; 40]).is_err());
+
+        let kind = RouterInstruction::SetTimelock as u8;
+        assert!(GovernanceAction::decode(kind, &(MAX_TIMELOCK_SECS + 1).to_le_bytes()).is_err());
+        assert!(GovernanceAction::decode(kind, &3600u64.to_le_bytes()).is_ok());
+
+        // Exit limits out of bounds
+        let kind = RouterInstruction::UpdateExitConfig as u8;
+        assert!(GovernanceAction::decode(kind, &exit_config_payload(10_001, 3600)).is_err());
+        assert!(GovernanceAction::decode(kind, &exit_config_payload(2000, 0)).is_err());
+        assert!(GovernanceAction::decode(kind, &exit_config_payload(2000, MAX_EXIT_WINDOW_SECS + 1)).is_err());
+        assert!(GovernanceAction::decode(kind, &exit_config_payload(2000, 3600)[..80]).is_err());
+
+        // DeactivateSlab is instant, not a proposal kind
+        assert!(GovernanceAction::decode(RouterInstruction::DeactivateSlab as u8, &[0; 32]).is_err());
+    }
+
+    fn exit_config_payload(user_pct_bps: u64, window_secs: u64) -> [u8; 88] {
+        let mut payload = [0u8; 88];
+        payload[0..8].copy_from_slice(&user_pct_bps.to_le_bytes());
+        payload[8..24].copy_from_slice(&1_000_000_000i128.to_le_bytes());
+        payload[24..32].copy_from_slice(&window_secs.to_le_bytes());
+        payload[32..40].copy_from_slice(&300u64.to_le_bytes()); // tvl_pct_per_hour_bps
+        payload[40..48].copy_from_slice(&10_000u64.to_le_bytes()); // global_hard_max_bps
+        payload[48..64].copy_from_slice(&100_000_000i128.to_le_bytes()); // free PnL per day
+        payload[64..72].copy_from_slice(&500u64.to_le_bytes()); // fast lane pct
+        payload[72..88].copy_from_slice(&5_000_000_000i128.to_le_bytes()); // fast lane max
+        payload
+    }
+
+    #[test]
+    fn test_time_locked_exit_config_update() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        registry.proposals.timelock_secs = 100;
+        registry.exit_config.emergency.active = true;
+
+        let kind = RouterInstruction::UpdateExitConfig as u8;
+        let (id, _) = registry.proposals.queue(kind, &exit_config_payload(1000, 1800), 1_000).unwrap();
+
+        assert_eq!(
+            process_execute_proposal(&mut registry, id, None, 1_050),
+            Err(PercolatorError::Unauthorized)
+        );
+        assert_eq!(registry.exit_config.params.user_pct_per_hour_bps, 2000);
+
+        process_execute_proposal(&mut registry, id, None, 1_100).unwrap();
+        let config = registry.exit_config;
+        assert_eq!(config.params.user_pct_per_hour_bps, 1000);
+        assert_eq!(config.params.user_hard_max_per_hour, 1_000_000_000);
+        assert_eq!(config.params.rolling_window_secs, 1800);
+        assert_eq!(config.params.tvl_pct_per_hour_bps, 300);
+        assert_eq!(config.thresholds.free_pnl_threshold_per_day, 100_000_000);
+        assert_eq!(config.thresholds.principal_fast_lane_hard_max, 5_000_000_000);
+        // Emergency mode is untouched
+        assert!(config.emergency.active);
+    }
+}
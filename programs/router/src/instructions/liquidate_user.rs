@@ -1,8 +1,9 @@
 //! Liquidate user positions via reduce-only cross-slab execution
 
-use crate::state::{Portfolio, SlabRegistry, Vault};
+use crate::instructions::TradeAccounts;
+use crate::state::{Portfolio, SlabRegistry, Vault, PAUSE_LIQUIDATIONS};
 use percolator_common::*;
-use pinocchio::{account_info::AccountInfo, msg};
+use pinocchio::msg;
 
 /// Liquidation mode based on health
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -46,10 +47,10 @@ pub fn determine_mode(health: i128, preliq_buffer: i128) -> Option<LiquidationMo
 /// * `portfolio` - User's portfolio account (to be liquidated)
 /// * `registry` - Slab registry with liquidation parameters
 /// * `vault` - Collateral vault
-/// * `router_authority` - Router authority PDA (for CPI signing)
-/// * `oracle_accounts` - Oracle price feed accounts (for price validation)
-/// * `slab_accounts` - Array of slab accounts to execute on
-/// * `receipt_accounts` - Array of receipt PDAs (one per slab)
+/// * `accounts` - Router authority PDA (for CPI signing), slabs to execute
+///   on with their receipts, matcher programdata (version-checked) and
+///   maker portfolios, oracles (one per instrument held, for margin marks
+///   and price validation) and the portfolio's AMM LP triples
 /// * `is_preliq` - Force pre-liquidation mode (if false, auto-determine)
 /// * `current_ts` - Current timestamp (for rate limiting)
 ///
@@ -62,17 +63,27 @@ pub fn process_liquidate_user(
     portfolio: &mut Portfolio,
     registry: &mut SlabRegistry,
     vault: &mut Vault,
-    router_authority: &AccountInfo,
-    oracle_accounts: &[AccountInfo],
-    slab_accounts: &[AccountInfo],
-    receipt_accounts: &[AccountInfo],
+    accounts: TradeAccounts,
     is_preliq: bool,
     current_ts: u64,
 ) -> Result<(), PercolatorError> {
+    let TradeAccounts { router_authority, slabs: slab_accounts, margin } = accounts;
+    let oracle_accounts = margin.oracles;
+
+    if registry.is_paused(PAUSE_LIQUIDATIONS) {
+        msg!("Error: Liquidations are paused");
+        return Err(PercolatorError::ProtocolPaused);
+    }
+
     msg!("Liquidate: Starting liquidation check");
 
-    // Step 1: Calculate health = equity - MM
-    let health = portfolio.equity.saturating_sub(portfolio.mm as i128);
+    // Step 0: Revalue AMM LP exposure so it cannot hide from MM, and
+    // mark positions to the oracles so health reflects current prices
+    crate::instructions::refresh_amm_lp_margin(portfolio, registry, margin.lp, current_ts)?;
+    crate::instructions::mark_portfolio(portfolio, registry, oracle_accounts, current_ts)?;
+
+    // Step 1: Calculate health = equity - MM (principal + LP buckets)
+    let health = portfolio.equity.saturating_sub(portfolio.calculate_total_mm() as i128);
     msg!("Liquidate: Health calculated");
 
     // Store health in portfolio for tracking
@@ -146,16 +157,28 @@ pub fn process_liquidate_user(
             continue;
         }
 
-        // Extract price (at offset 72: magic(8) + version(1) + bump(1) + padding(6) + authority(32) + instrument(32) + price(8))
-        let price_bytes = [
-            oracle_data[72], oracle_data[73], oracle_data[74], oracle_data[75],
-            oracle_data[76], oracle_data[77], oracle_data[78], oracle_data[79],
-        ];
+        // Only the oracle account pinned to an instrument prices it; its
+        // instrument (offset 48: magic(8) + version(1) + bump(1) + padding(6) + authority(32))
+        // must match
+        let instrument_idx = match registry.find_instrument_by_oracle(oracle_account.key()) {
+            Some(idx) => idx,
+            None => {
+                msg!("Warning: Oracle not pinned to a registered instrument, skipping");
+                continue;
+            }
+        };
+        if oracle_data[48..80] != registry.instruments[instrument_idx as usize].id {
+            msg!("Warning: Oracle instrument mismatch, skipping");
+            continue;
+        }
+
+        // Extract price (at offset 80, after instrument)
+        let mut price_bytes = [0u8; 8];
+        price_bytes.copy_from_slice(&oracle_data[80..88]);
         let price = i64::from_le_bytes(price_bytes);
 
-        // Use index as instrument_idx for v0 (in production, would map instrument pubkey to index)
         oracle_prices[oracle_count] = OraclePrice {
-            instrument_idx: i as u16,
+            instrument_idx,
             price,
         };
         oracle_count += 1;
@@ -173,31 +196,34 @@ pub fn process_liquidate_user(
     }; MAX_SLABS_FOR_LIQ];
     let mut slab_count = 0;
 
-    for (i, slab_account) in slab_accounts.iter().enumerate() {
+    for (i, slab_account) in slab_accounts.slabs.iter().enumerate() {
         if i >= MAX_SLABS_FOR_LIQ {
             break;
         }
 
-        // Read SlabHeader to get mark price
-        let slab_data = slab_account.try_borrow_data()
-            .map_err(|_| PercolatorError::InvalidAccount)?;
-
-        if slab_data.len() < 96 {
-            msg!("Warning: Slab account too small, skipping");
-            continue;
-        }
-
-        // mark_px is at offset 88 in SlabHeader
-        let mark_bytes = [
-            slab_data[88], slab_data[89], slab_data[90], slab_data[91],
-            slab_data[92], slab_data[93], slab_data[94], slab_data[95],
-        ];
-        let mark_price = i64::from_le_bytes(mark_bytes);
+        // Exposures are keyed by registry index and instrument
+        let (slab_idx, entry) = match registry.find_slab(slab_account.key()) {
+            Some(found) => found,
+            None => {
+                msg!("Warning: Slab not registered, skipping");
+                continue;
+            }
+        };
+        let instrument_idx = entry.instrument_idx;
+
+        // Read SlabHeader to get mark price; one bad slab must not block the liquidation
+        let mark_price = match crate::matcher::read_slab_header(slab_account) {
+            Ok(header) => header.mark_px,
+            Err(_) => {
+                msg!("Warning: Invalid slab header, skipping");
+                continue;
+            }
+        };
 
         slab_infos[slab_count] = SlabInfo {
             slab_id: *slab_account.key(),
-            slab_idx: i as u16,
-            instrument_idx: i as u16, // v0: use slab index as instrument index
+            slab_idx,
+            instrument_idx,
             mark_price,
         };
         slab_count += 1;
@@ -230,15 +256,17 @@ pub fn process_liquidate_user(
         &user_pubkey,
         vault,
         registry,
-        router_authority,
-        &slab_accounts[..plan.split_count],
-        &receipt_accounts[..plan.split_count],
+        TradeAccounts {
+            slabs: slab_accounts.prefix(plan.split_count),
+            ..accounts
+        },
         plan.get_splits(),
+        &crate::instructions::OrderGuards::default(),
     )?;
     msg!("Liquidate: Execution complete via cross-slab logic");
 
     // Step 7: Update portfolio health and timestamp
-    portfolio.health = portfolio.equity.saturating_sub(portfolio.mm as i128);
+    portfolio.health = portfolio.equity.saturating_sub(portfolio.calculate_total_mm() as i128);
     portfolio.last_liquidation_ts = current_ts;
 
     msg!("Liquidate: Portfolio updated");
@@ -262,8 +290,9 @@ pub fn process_liquidate_user(
         );
 
         if payout > 0 {
-            // Apply insurance payout to portfolio equity
-            portfolio.equity = portfolio.equity.saturating_add(payout as i128);
+            // Apply insurance payout to realized PnL so it survives the next mark
+            portfolio.pnl = portfolio.pnl.saturating_add(payout as i128);
+            portfolio.update_equity(portfolio.equity.saturating_add(payout as i128));
             msg!("Insurance payout applied to cover bad debt");
         }
 
@@ -294,7 +323,6 @@ pub fn process_liquidate_user(
 
     let _ = vault; // Will be used in production
     let _ = router_authority; // Will be used for CPI signing
-    let _ = oracle_accounts; // Will be used for price reading
 
     Ok(())
 }
@@ -342,7 +370,7 @@ mod tests {
     fn test_liquidation_mode_price_bands() {
         use crate::state::{SlabRegistry, SlabEntry};
         use pinocchio::pubkey::Pubkey;
-        use percolator_common::MAX_SLABS;
+        use percolator_common::{MAX_INSTRUMENTS, MAX_SLABS};
 
         // Create registry with different bands for pre-liq vs hard liq
         let registry = SlabRegistry {
@@ -350,7 +378,8 @@ mod tests {
             governance: Pubkey::default(),
             slab_count: 0,
             bump: 0,
-            _padding: [0; 5],
+            pause_flags: 0,
+            _padding: [0; 4],
             imr: 500,
             mmr: 250,
             liq_band_bps: 200,      // 2% for hard liquidation
@@ -364,6 +393,17 @@ mod tests {
             insurance_state: crate::state::insurance::InsuranceState::default(),
             pnl_vesting_params: crate::state::pnl_vesting::PnlVestingParams::default(),
             global_haircut: crate::state::pnl_vesting::GlobalHaircut::default(),
+            exit_config: crate::state::exit_bucket::ExitConfig::default(),
+            global_exit_bucket: crate::state::exit_bucket::GlobalExitBucket::default(),
+            queued_withdrawal_total: 0,
+            withdrawal_ticket: 0,
+            _padding3: [0; 8],
+            pending_governance: Pubkey::default(),
+            proposals: crate::state::proposal::Proposals::new(crate::state::proposal::DEFAULT_TIMELOCK_SECS),
+            instrument_count: 0,
+            _padding4: [0; 6],
+            funding_params: crate::state::funding::FundingParams::default(),
+            instruments: [crate::state::InstrumentEntry::empty(); MAX_INSTRUMENTS],
             slabs: [SlabEntry {
                 slab_id: Pubkey::default(),
                 version_hash: [0; 32],
@@ -374,9 +414,13 @@ mod tests {
                 taker_fee_cap: 0,
                 latency_sla_ms: 0,
                 max_exposure: 0,
+                routed_notional: 0,
                 registered_ts: 0,
+                last_quote_slot: 0,
+                last_quote_seqno: 0,
+                instrument_idx: 0,
                 active: false,
-                _padding: [0; 7],
+                _padding: [0; 9],
             }; MAX_SLABS],
         };
 
@@ -0,0 +1,128 @@
+//! AMM LP margin refresh
+//!
+//! Revalues every active AMM LP bucket from live pool inventory and the
+//! oracle price on each portfolio touch, so directional AMM exposure is
+//! always counted in total IM/MM. Only registered AMM venues whose
+//! program bytecode matches the registry are read, valued at their
+//! instrument's pinned oracle.
+
+use crate::matcher::verify_matcher;
+use crate::oracle::{CustomAdapter, OracleAdapter};
+use crate::state::{AmmInventory, Portfolio, SlabRegistry, VenueKind, MAX_LP_BUCKETS};
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg};
+
+/// AMM pool layout (from programs/amm/src/state.rs):
+/// SlabHeader, QuoteCache, then AmmPool with
+/// x_reserve [0..8], y_reserve [8..16], total_lp_shares [112..120]
+const AMM_POOL_OFFSET: usize = SlabHeader::LEN + QuoteCache::LEN;
+const X_RESERVE_OFFSET: usize = AMM_POOL_OFFSET;
+const Y_RESERVE_OFFSET: usize = AMM_POOL_OFFSET + 8;
+const TOTAL_LP_SHARES_OFFSET: usize = AMM_POOL_OFFSET + 112;
+const AMM_MIN_LEN: usize = TOTAL_LP_SHARES_OFFSET + 8;
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// Read pool inventory from an AMM account
+fn read_amm_inventory(amm_account: &AccountInfo) -> Result<AmmInventory, PercolatorError> {
+    let data = amm_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+
+    if data.len() < AMM_MIN_LEN || &data[0..8] != SlabHeader::MAGIC {
+        msg!("Error: Invalid AMM account");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    Ok(AmmInventory {
+        base: read_i64(&data, X_RESERVE_OFFSET),
+        quote: read_i64(&data, Y_RESERVE_OFFSET),
+        total_shares: read_i64(&data, TOTAL_LP_SHARES_OFFSET) as u64,
+    })
+}
+
+/// Refresh margin on every active AMM LP bucket
+///
+/// # Arguments
+/// * `portfolio` - Portfolio being touched
+/// * `registry` - Registry (venues, IMR/MMR, pinned oracles)
+/// * `lp_accounts` - [amm_pool, programdata, oracle] triples, one per
+///   active AMM bucket; the oracle is the pool instrument's pinned oracle
+/// * `current_ts` - Unix timestamp recorded on the refreshed buckets
+///
+/// Fails if any active AMM bucket is missing its triple, so LP exposure
+/// cannot be left out of margin by omitting accounts, or if a pool is not
+/// a registered venue running its registered program.
+pub fn refresh_amm_lp_margin(
+    portfolio: &mut Portfolio,
+    registry: &SlabRegistry,
+    lp_accounts: &[AccountInfo],
+    current_ts: u64,
+) -> Result<(), PercolatorError> {
+    let triples = lp_accounts.chunks_exact(3);
+    if !triples.remainder().is_empty() {
+        msg!("Error: LP accounts must be [amm_pool, programdata, oracle] triples");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    // Bit i set = bucket i refreshed
+    let mut refreshed: u32 = 0;
+
+    for triple in triples {
+        let (amm_account, programdata_account, oracle_account) = (&triple[0], &triple[1], &triple[2]);
+
+        let bucket_idx = (0..portfolio.lp_bucket_count as usize)
+            .find(|&i| {
+                let bucket = &portfolio.lp_buckets[i];
+                bucket.active
+                    && bucket.venue.venue_kind == VenueKind::Amm
+                    && &bucket.venue.market_id == amm_account.key()
+            })
+            .ok_or_else(|| {
+                msg!("Error: AMM account has no LP bucket in portfolio");
+                PercolatorError::InvalidAccount
+            })?;
+
+        // Registered venue running its registered program (bytecode hash)
+        let venue_idx = verify_matcher(registry, amm_account, programdata_account)?;
+        let entry = &registry.slabs[venue_idx as usize];
+        let instrument = &registry.instruments[entry.instrument_idx as usize];
+        let inventory = read_amm_inventory(amm_account)?;
+
+        // Valued at the instrument's pinned oracle
+        if oracle_account.key() != &instrument.oracle {
+            msg!("Error: Oracle is not the venue instrument's pinned oracle");
+            return Err(PercolatorError::InvalidAccount);
+        }
+        if oracle_account.owner() != &entry.oracle_id
+            || crate::instructions::read_oracle_instrument(oracle_account)? != instrument.id
+        {
+            msg!("Error: Pinned oracle has wrong owner or instrument");
+            return Err(PercolatorError::InvalidAccount);
+        }
+
+        let oracle_px = CustomAdapter::new()
+            .read_price(oracle_account)
+            .map_err(|_| PercolatorError::InvalidAccount)?
+            .price;
+        if oracle_px <= 0 {
+            return Err(PercolatorError::InvalidPrice);
+        }
+
+        portfolio.lp_buckets[bucket_idx].refresh_amm_margin(&inventory, oracle_px, entry.imr, entry.mmr, current_ts);
+        refreshed |= 1 << bucket_idx;
+    }
+
+    // Every active AMM bucket must have been revalued
+    for i in 0..(portfolio.lp_bucket_count as usize).min(MAX_LP_BUCKETS) {
+        let bucket = &portfolio.lp_buckets[i];
+        if bucket.active && bucket.is_amm() && refreshed & (1 << i) == 0 {
+            msg!("Error: Missing AMM accounts for LP bucket");
+            return Err(PercolatorError::InvalidAccount);
+        }
+    }
+
+    Ok(())
+}
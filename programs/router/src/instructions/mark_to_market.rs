@@ -0,0 +1,426 @@
+//! Portfolio mark-to-market
+//!
+//! Marks the portfolio to the oracle prices supplied with the instruction
+//! on each portfolio touch: funding is settled, equity includes unrealized
+//! PnL at the marks, and the principal IM/MM is recomputed from the net
+//! position per instrument, so margin checks and liquidation see current
+//! risk rather than the price of the last trade.
+
+use crate::instructions::net_exposure_by_instrument;
+use crate::oracle::{CustomAdapter, OracleAdapter};
+use crate::state::{Portfolio, SlabRegistry};
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
+
+/// Oracle instrument ID offset (from programs/oracle/src/state.rs):
+/// magic(8) + version(1) + bump(1) + padding(6) + authority(32)
+const ORACLE_INSTRUMENT_OFFSET: usize = 48;
+
+/// `calculate_im`/`calculate_mm` scale: qty and contract size are both 1e6 scale
+const MARGIN_SCALE: u128 = PRICE_MULTIPLIER as u128 * PRICE_MULTIPLIER as u128;
+
+/// Accounts used to value a portfolio on touch
+#[derive(Clone, Copy)]
+pub struct MarginAccounts<'a> {
+    /// One oracle per instrument the portfolio holds (or trades)
+    pub oracles: &'a [AccountInfo],
+    /// [amm_pool, programdata, oracle] triples for the portfolio's AMM LP buckets
+    pub lp: &'a [AccountInfo],
+}
+
+impl<'a> MarginAccounts<'a> {
+    /// Split the leading `count` oracle accounts from the trailing LP triples
+    pub fn split(accounts: &'a [AccountInfo], count: usize) -> Result<Self, PercolatorError> {
+        if accounts.len() < count {
+            msg!("Error: Insufficient oracle accounts");
+            return Err(PercolatorError::InvalidInstruction);
+        }
+        let (oracles, lp) = accounts.split_at(count);
+        Ok(Self { oracles, lp })
+    }
+}
+
+/// IMR/MMR (bps) per instrument: the strictest of the registered slabs the
+/// portfolio holds it on, each falling back to the registry default if unset
+fn instrument_margin_ratios(
+    portfolio: &Portfolio,
+    registry: &SlabRegistry,
+) -> ([u64; MAX_INSTRUMENTS], [u64; MAX_INSTRUMENTS]) {
+    let mut imrs = [0u64; MAX_INSTRUMENTS];
+    let mut mmrs = [0u64; MAX_INSTRUMENTS];
+
+    for i in 0..portfolio.exposure_count as usize {
+        let (slab_idx, instrument_idx, qty) = portfolio.exposures[i];
+        let (Some(entry), Some(imr), Some(mmr)) = (
+            registry.slabs.get(slab_idx as usize),
+            imrs.get_mut(instrument_idx as usize),
+            mmrs.get_mut(instrument_idx as usize),
+        ) else {
+            continue;
+        };
+        if qty == 0 {
+            continue;
+        }
+
+        let entry_imr = if entry.imr > 0 { entry.imr } else { registry.imr };
+        let entry_mmr = if entry.mmr > 0 { entry.mmr } else { registry.mmr };
+        *imr = (*imr).max(entry_imr);
+        *mmr = (*mmr).max(entry_mmr);
+    }
+
+    (imrs, mmrs)
+}
+
+/// Calculate principal IM and MM from per-instrument net positions
+///
+/// Each instrument's net is margined at `marks[instrument_idx]` (1e6 scale)
+/// with its registry contract size; the results are summed. Positions on
+/// different slabs of the same instrument net before margining.
+///
+/// # Returns
+/// * (IM, MM) in collateral units (1e6 scale)
+pub fn calculate_principal_margin(
+    portfolio: &Portfolio,
+    registry: &SlabRegistry,
+    marks: &[u64; MAX_INSTRUMENTS],
+) -> (u128, u128) {
+    let nets = net_exposure_by_instrument(portfolio);
+    let (imrs, mmrs) = instrument_margin_ratios(portfolio, registry);
+
+    let mut im = 0u128;
+    let mut mm = 0u128;
+    for (idx, &net) in nets.iter().enumerate() {
+        if net == 0 {
+            continue; // Flat instruments need no margin
+        }
+
+        let contract_size = registry.instruments[idx].contract_size;
+        im = im.saturating_add(calculate_im(net, contract_size, marks[idx], imrs[idx]) / MARGIN_SCALE);
+        mm = mm.saturating_add(calculate_mm(net, contract_size, marks[idx], mmrs[idx]) / MARGIN_SCALE);
+    }
+
+    (im, mm)
+}
+
+/// Read an oracle account's instrument ID
+pub fn read_oracle_instrument(oracle_account: &AccountInfo) -> Result<Pubkey, PercolatorError> {
+    let data = oracle_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+    if data.len() < ORACLE_INSTRUMENT_OFFSET + 32 {
+        msg!("Error: Invalid oracle account");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let mut instrument = [0u8; 32];
+    instrument.copy_from_slice(&data[ORACLE_INSTRUMENT_OFFSET..ORACLE_INSTRUMENT_OFFSET + 32]);
+    Ok(instrument)
+}
+
+/// Read oracle marks for the portfolio's instruments
+///
+/// Each oracle must be the account pinned to its instrument in the
+/// registry and be owned by the oracle program of every slab the
+/// portfolio holds the instrument on. Fails if an instrument with an
+/// open net position has no oracle, so a position cannot be left out of
+/// equity or margin by omitting accounts.
+///
+/// # Returns
+/// * Mark price (1e6 scale) per instrument index, 0 if not supplied
+pub fn read_oracle_marks(
+    portfolio: &Portfolio,
+    registry: &SlabRegistry,
+    oracle_accounts: &[AccountInfo],
+) -> Result<[u64; MAX_INSTRUMENTS], PercolatorError> {
+    let mut marks = [0u64; MAX_INSTRUMENTS];
+
+    for oracle_account in oracle_accounts {
+        let instrument_idx = registry.find_instrument_by_oracle(oracle_account.key()).ok_or_else(|| {
+            msg!("Error: Oracle not pinned to a registered instrument");
+            PercolatorError::InvalidAccount
+        })?;
+        if read_oracle_instrument(oracle_account)? != registry.instruments[instrument_idx as usize].id {
+            msg!("Error: Oracle does not price its pinned instrument");
+            return Err(PercolatorError::InvalidAccount);
+        }
+
+        for i in 0..portfolio.exposure_count as usize {
+            let (slab_idx, exp_instrument_idx, _) = portfolio.exposures[i];
+            if exp_instrument_idx == instrument_idx
+                && oracle_account.owner() != &registry.slabs[slab_idx as usize].oracle_id
+            {
+                msg!("Error: Oracle not owned by venue oracle program");
+                return Err(PercolatorError::InvalidAccount);
+            }
+        }
+
+        let oracle_px = CustomAdapter::new()
+            .read_price(oracle_account)
+            .map_err(|_| PercolatorError::InvalidAccount)?
+            .price;
+        if oracle_px <= 0 {
+            return Err(PercolatorError::InvalidPrice);
+        }
+        marks[instrument_idx as usize] = oracle_px as u64;
+    }
+
+    // Every open instrument must have been priced
+    let nets = net_exposure_by_instrument(portfolio);
+    if nets.iter().zip(marks.iter()).any(|(&net, &mark)| net != 0 && mark == 0) {
+        msg!("Error: Missing oracle for an open instrument");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    Ok(marks)
+}
+
+/// Read the oracle price of a slab's instrument from the supplied oracles
+///
+/// The oracle must be the account pinned to the slab's instrument, price
+/// that instrument and be owned by the slab's oracle program.
+///
+/// # Returns
+/// * Oracle price (1e6 scale)
+pub fn read_slab_oracle_price(
+    registry: &SlabRegistry,
+    slab_idx: u16,
+    oracle_accounts: &[AccountInfo],
+) -> Result<i64, PercolatorError> {
+    let entry = &registry.slabs[slab_idx as usize];
+    let instrument = &registry.instruments[entry.instrument_idx as usize];
+
+    for oracle_account in oracle_accounts {
+        if oracle_account.key() != &instrument.oracle {
+            continue;
+        }
+        if oracle_account.owner() != &entry.oracle_id
+            || read_oracle_instrument(oracle_account)? != instrument.id
+        {
+            msg!("Error: Pinned oracle has wrong owner or instrument");
+            return Err(PercolatorError::InvalidAccount);
+        }
+
+        let oracle_px = CustomAdapter::new()
+            .read_price(oracle_account)
+            .map_err(|_| PercolatorError::InvalidAccount)?
+            .price;
+        if oracle_px <= 0 {
+            return Err(PercolatorError::InvalidPrice);
+        }
+        return Ok(oracle_px);
+    }
+
+    msg!("Error: Missing oracle for slab instrument");
+    Err(PercolatorError::InvalidAccount)
+}
+
+/// Check a slab's mark price against its instrument's oracle
+///
+/// # Returns
+/// * Whether the slab's header `mark_px` is within `oracle_tolerance_bps`
+///   of the oracle price
+pub fn slab_oracle_aligned(
+    registry: &SlabRegistry,
+    slab_idx: u16,
+    header: &SlabHeader,
+    oracle_accounts: &[AccountInfo],
+) -> Result<bool, PercolatorError> {
+    let oracle_px = read_slab_oracle_price(registry, slab_idx, oracle_accounts)?;
+    Ok(crate::liquidation::validate_oracle_alignment(
+        header.mark_px,
+        oracle_px,
+        registry.oracle_tolerance_bps,
+    ))
+}
+
+/// Mark the portfolio to market at oracle prices
+///
+/// # Arguments
+/// * `portfolio` - Portfolio being touched
+/// * `registry` - Registry (instruments, IMR/MMR per slab, global fallback)
+/// * `oracle_accounts` - One oracle per instrument the portfolio holds
+/// * `current_ts` - Unix timestamp recorded as the portfolio's last mark
+///
+/// Settles funding owed on every position, sets equity to principal +
+/// realized PnL (net of fees and funding) + unrealized PnL at the marks,
+/// then the principal IM/MM at the same marks.
+pub fn mark_portfolio(
+    portfolio: &mut Portfolio,
+    registry: &SlabRegistry,
+    oracle_accounts: &[AccountInfo],
+    current_ts: u64,
+) -> Result<(), PercolatorError> {
+    let marks = read_oracle_marks(portfolio, registry, oracle_accounts)?;
+
+    portfolio.settle_funding(&registry.cum_funding());
+    portfolio.mark_to_market(&marks, current_ts);
+    let (im, mm) = calculate_principal_margin(portfolio, registry, &marks);
+    portfolio.update_margin(im, mm);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALE: i64 = 1_000_000;
+
+    fn registry() -> SlabRegistry {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        let btc = registry.add_instrument(&Pubkey::from([1; 32]), 1_000_000, &Pubkey::from([21; 32])).unwrap();
+        let eth = registry.add_instrument(&Pubkey::from([2; 32]), 1_000_000, &Pubkey::from([22; 32])).unwrap();
+        let margin = |imr, mmr| crate::instructions::SlabParams {
+            version_hash: [0; 32],
+            oracle_id: Pubkey::default(),
+            oracle: Pubkey::default(),
+            imr,
+            mmr,
+            maker_fee_cap: 0,
+            taker_fee_cap: 0,
+            latency_sla_ms: 0,
+            max_exposure: 0,
+        };
+        // Slab 0: BTC at 5%/2.5%, slab 1: BTC at 10%/5%, slab 2: ETH at registry defaults
+        registry.register_slab(Pubkey::from([10; 32]), btc, &margin(500, 250), 0).unwrap();
+        registry.register_slab(Pubkey::from([11; 32]), btc, &margin(1_000, 500), 0).unwrap();
+        registry.register_slab(Pubkey::from([12; 32]), eth, &margin(0, 0), 0).unwrap();
+        registry
+    }
+
+    fn marks(btc: u64, eth: u64) -> [u64; MAX_INSTRUMENTS] {
+        let mut marks = [0u64; MAX_INSTRUMENTS];
+        marks[0] = btc;
+        marks[1] = eth;
+        marks
+    }
+
+    #[test]
+    fn test_margin_at_mark_with_slab_ratios() {
+        let registry = registry();
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        // 2 BTC long on slab 0, bought at 40k; mark is 50k
+        portfolio.apply_fill(0, 0, 2 * SCALE, 40_000 * PRICE_MULTIPLIER, 0);
+        let (im, mm) = calculate_principal_margin(&portfolio, &registry, &marks(50_000 * PRICE_MULTIPLIER, 0));
+
+        // Notional 100k at 5% / 2.5%, priced at the mark and not the entry
+        assert_eq!(im, 5_000 * PRICE_MULTIPLIER as u128);
+        assert_eq!(mm, 2_500 * PRICE_MULTIPLIER as u128);
+    }
+
+    #[test]
+    fn test_margin_nets_per_instrument_and_sums() {
+        let registry = registry();
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        // BTC: +3 on slab 0, -1 on slab 1 → net +2, strictest ratio is slab 1's 10%/5%
+        portfolio.update_exposure(0, 0, 3 * SCALE);
+        portfolio.update_exposure(1, 0, -SCALE);
+        // ETH: -10 on slab 2 at the registry defaults
+        portfolio.update_exposure(2, 1, -10 * SCALE);
+
+        let (im, mm) = calculate_principal_margin(
+            &portfolio,
+            &registry,
+            &marks(50_000 * PRICE_MULTIPLIER, 3_000 * PRICE_MULTIPLIER),
+        );
+
+        let eth_notional = 30_000 * PRICE_MULTIPLIER as u128;
+        assert_eq!(
+            im,
+            10_000 * PRICE_MULTIPLIER as u128 + eth_notional * registry.imr as u128 / 10_000
+        );
+        assert_eq!(
+            mm,
+            5_000 * PRICE_MULTIPLIER as u128 + eth_notional * registry.mmr as u128 / 10_000
+        );
+    }
+
+    #[test]
+    fn test_flat_instrument_needs_no_margin_or_mark() {
+        let registry = registry();
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        portfolio.update_exposure(0, 0, 5 * SCALE);
+        portfolio.update_exposure(1, 0, -5 * SCALE);
+
+        assert_eq!(calculate_principal_margin(&portfolio, &registry, &marks(0, 0)), (0, 0));
+    }
+
+    fn oracle_data(instrument: Pubkey, price: u64) -> [u8; 128] {
+        let mut data = [0u8; 128];
+        data[0..8].copy_from_slice(b"PRCLORCL");
+        data[48..80].copy_from_slice(&instrument);
+        data[80..88].copy_from_slice(&(price as i64).to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_marks_require_pinned_oracle_account() {
+        use percolator_common::test_accounts::TestAccount;
+
+        let registry = registry();
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.update_exposure(0, 0, SCALE);
+
+        let data = oracle_data(Pubkey::from([1; 32]), 50_000 * PRICE_MULTIPLIER);
+        let mut pinned = TestAccount::new(Pubkey::from([21; 32]), Pubkey::default(), &data);
+        let marks = read_oracle_marks(&portfolio, &registry, &[pinned.info()]).unwrap();
+        assert_eq!(marks[0], 50_000 * PRICE_MULTIPLIER);
+
+        // Same owner program and instrument, but not the pinned account
+        let spoofed_data = oracle_data(Pubkey::from([1; 32]), PRICE_MULTIPLIER);
+        let mut spoofed = TestAccount::new(Pubkey::from([99; 32]), Pubkey::default(), &spoofed_data);
+        assert_eq!(
+            read_oracle_marks(&portfolio, &registry, &[spoofed.info()]),
+            Err(PercolatorError::InvalidAccount)
+        );
+
+        // The ETH oracle cannot price BTC
+        let mut eth = TestAccount::new(Pubkey::from([22; 32]), Pubkey::default(), &data);
+        assert_eq!(
+            read_oracle_marks(&portfolio, &registry, &[eth.info()]),
+            Err(PercolatorError::InvalidAccount)
+        );
+    }
+
+    #[test]
+    fn test_slab_alignment_requires_pinned_oracle_account() {
+        use percolator_common::test_accounts::TestAccount;
+
+        let registry = registry();
+        let instrument = Pubkey::from([1; 32]);
+        let mark_px = 50_000 * PRICE_MULTIPLIER as i64;
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            instrument,
+            mark_px,
+            0,
+            1_000_000,
+            0,
+        );
+
+        // A spoofed oracle at the slab's mark, same owner program and instrument
+        let data = oracle_data(instrument, 50_000 * PRICE_MULTIPLIER);
+        let mut spoofed = TestAccount::new(Pubkey::from([99; 32]), Pubkey::default(), &data);
+        assert_eq!(
+            slab_oracle_aligned(&registry, 0, &header, &[spoofed.info()]),
+            Err(PercolatorError::InvalidAccount)
+        );
+
+        // The pinned oracle is used even when passed after the spoofed one
+        let real_data = oracle_data(instrument, 40_000 * PRICE_MULTIPLIER);
+        let mut pinned = TestAccount::new(Pubkey::from([21; 32]), Pubkey::default(), &real_data);
+        assert_eq!(
+            read_slab_oracle_price(&registry, 0, &[spoofed.info(), pinned.info()]),
+            Ok(40_000 * PRICE_MULTIPLIER as i64)
+        );
+        assert_eq!(
+            slab_oracle_aligned(&registry, 0, &header, &[spoofed.info(), pinned.info()]),
+            Ok(false)
+        );
+    }
+}
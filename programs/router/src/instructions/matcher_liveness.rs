@@ -0,0 +1,89 @@
+//! Matcher liveness - quote freshness against each slab's latency SLA
+//!
+//! The router stamps the slot whenever it sees a slab's QuoteCache seqno
+//! change. Quotes older than the slab's `latency_sla_ms` are not routed
+//! to, and a matcher stale for `QUARANTINE_SLA_MULTIPLE` SLAs can be
+//! quarantined by anyone until governance reactivates it.
+
+use crate::events;
+use crate::instructions::{read_quote_cache, SlabSplit};
+use crate::state::SlabRegistry;
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg};
+
+/// Observe a slab's QuoteCache and check it against the slab's SLA
+///
+/// # Returns
+/// * True if the slab's quotes are within its latency SLA
+pub fn observe_matcher_quotes(
+    registry: &mut SlabRegistry,
+    slab_idx: u16,
+    slab_account: &AccountInfo,
+    current_slot: u64,
+) -> Result<bool, PercolatorError> {
+    let cache = read_quote_cache(slab_account)?;
+    let entry = &mut registry.slabs[slab_idx as usize];
+    entry.observe_quotes(cache.seqno_snapshot, current_slot);
+    Ok(!entry.quotes_stale(current_slot))
+}
+
+/// Reject user splits on matchers whose quotes are older than their SLA
+///
+/// # Arguments
+/// * `slabs` - Slab accounts, one per split
+/// * `splits` - The order's splits (zero-quantity splits are ignored)
+pub fn check_matcher_liveness(
+    registry: &mut SlabRegistry,
+    slabs: &[AccountInfo],
+    splits: &[SlabSplit],
+    current_slot: u64,
+) -> Result<(), PercolatorError> {
+    for (split, slab_account) in splits.iter().zip(slabs.iter()) {
+        if split.qty == 0 {
+            continue;
+        }
+
+        let (slab_idx, _) = registry.find_slab(&split.slab_id).ok_or_else(|| {
+            msg!("Error: Slab not registered");
+            PercolatorError::SlabNotRegistered
+        })?;
+        if !observe_matcher_quotes(registry, slab_idx, slab_account, current_slot)? {
+            msg!("Error: Matcher quotes older than its latency SLA");
+            return Err(PercolatorError::MatcherStale);
+        }
+    }
+
+    Ok(())
+}
+
+/// Process quarantine slab instruction (permissionless)
+///
+/// Deactivates a slab whose quotes have not changed for more than
+/// `QUARANTINE_SLA_MULTIPLE` times its latency SLA. Governance brings it
+/// back with ReactivateSlab. Emits `SLAB_QUARANTINED`.
+///
+/// # Returns
+/// * Registry index of the quarantined slab
+pub fn process_quarantine_slab(
+    registry: &mut SlabRegistry,
+    slab_account: &AccountInfo,
+    current_slot: u64,
+) -> Result<u16, PercolatorError> {
+    let (slab_idx, _) = registry.find_slab(slab_account.key()).ok_or_else(|| {
+        msg!("Error: Slab not registered");
+        PercolatorError::SlabNotRegistered
+    })?;
+
+    observe_matcher_quotes(registry, slab_idx, slab_account, current_slot)?;
+    let entry = &mut registry.slabs[slab_idx as usize];
+    if !entry.quotes_chronically_stale(current_slot) {
+        msg!("Error: Matcher quotes are not chronically stale");
+        return Err(PercolatorError::MatcherNotStale);
+    }
+
+    entry.active = false;
+    events::emit_slab_status(events::SLAB_QUARANTINED, slab_idx, slab_account.key());
+
+    msg!("Slab quarantined");
+    Ok(slab_idx)
+}
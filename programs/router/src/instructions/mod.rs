@@ -5,18 +5,34 @@ pub mod initialize_portfolio;
 pub mod deposit;
 pub mod withdraw;
 pub mod execute_cross_slab;
+pub mod execute_order;
 pub mod liquidate_user;
 pub mod burn_lp_shares;
 pub mod cancel_lp_orders;
+pub mod lp_margin;
+pub mod mark_to_market;
+pub mod withdrawal_requests;
+pub mod set_pause_flags;
+pub mod governance;
+pub mod update_funding;
+pub mod matcher_liveness;
 
 pub use initialize::*;
 pub use initialize_portfolio::*;
 pub use deposit::*;
 pub use withdraw::*;
 pub use execute_cross_slab::*;
+pub use execute_order::*;
 pub use liquidate_user::*;
 pub use burn_lp_shares::*;
 pub use cancel_lp_orders::*;
+pub use lp_margin::*;
+pub use mark_to_market::*;
+pub use withdrawal_requests::*;
+pub use set_pause_flags::*;
+pub use governance::*;
+pub use update_funding::*;
+pub use matcher_liveness::*;
 
 /// Instruction discriminator (v0 minimal)
 #[repr(u8)]
@@ -38,6 +54,42 @@ pub enum RouterInstruction {
     BurnLpShares = 6,
     /// Cancel Slab LP orders (ONLY way to reduce Slab LP exposure)
     CancelLpOrders = 7,
+    /// Queue a withdrawal that exceeds exit bucket limits
+    RequestWithdrawal = 8,
+    /// Cancel a queued withdrawal
+    CancelWithdrawal = 9,
+    /// Pay out queued withdrawals as exit capacity refills (permissionless)
+    ProcessWithdrawals = 10,
+    /// Set pause flags and emergency exit mode (governance only)
+    SetPauseFlags = 11,
+    /// Queue a slab (matcher) registration (governance only, time-locked)
+    RegisterSlab = 12,
+    /// Queue an update of a slab's parameters (governance only, time-locked)
+    UpdateSlab = 13,
+    /// Deactivate a registered slab immediately (governance only)
+    DeactivateSlab = 14,
+    /// Queue an update of global liquidation parameters (governance only, time-locked)
+    UpdateLiquidationParams = 15,
+    /// Queue a change of the proposal timelock (governance only, time-locked)
+    SetTimelock = 16,
+    /// Execute a queued proposal after its timelock (permissionless)
+    ExecuteProposal = 17,
+    /// Cancel a queued proposal (governance only)
+    CancelProposal = 18,
+    /// Propose a new governance authority (governance only)
+    ProposeGovernance = 19,
+    /// Accept governance (pending governance only)
+    AcceptGovernance = 20,
+    /// Accrue one period of funding for an instrument (permissionless crank)
+    UpdateFunding = 21,
+    /// Route an order across slabs from their quote caches
+    ExecuteOrder = 22,
+    /// Deactivate a matcher whose quotes are chronically stale (permissionless)
+    QuarantineSlab = 23,
+    /// Return a deactivated or quarantined slab to routing (governance only)
+    ReactivateSlab = 24,
+    /// Queue an update of withdrawal exit limits (governance only, time-locked)
+    UpdateExitConfig = 25,
 }
 
 // Note: Instruction dispatching is handled in entrypoint.rs
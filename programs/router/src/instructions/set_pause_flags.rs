@@ -0,0 +1,45 @@
+//! Set pause flags instruction - governance emergency controls
+
+use crate::events;
+use crate::instructions::require_governance;
+use crate::state::{EmergencyMode, SlabRegistry, PAUSE_ALL};
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg};
+
+/// Process set pause flags instruction
+///
+/// Replaces the registry pause flags and, if given, the emergency exit
+/// mode that scales down withdrawal caps. Clearing all flags resumes
+/// normal operation. Emits `PAUSE_FLAGS_SET`.
+///
+/// # Arguments
+/// * `registry` - Slab registry
+/// * `governance` - Governance authority (signer)
+/// * `pause_flags` - New PAUSE_* / REDUCE_ONLY bitmask
+/// * `emergency` - Optional new emergency exit mode
+pub fn process_set_pause_flags(
+    registry: &mut SlabRegistry,
+    governance: &AccountInfo,
+    pause_flags: u8,
+    emergency: Option<EmergencyMode>,
+) -> Result<(), PercolatorError> {
+    require_governance(registry, governance)?;
+
+    if pause_flags & !PAUSE_ALL != 0 {
+        msg!("Error: Unknown pause flags");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    if let Some(emergency) = emergency {
+        if emergency.exit_multiplier_bps > 10_000 {
+            msg!("Error: Exit multiplier above 100%");
+            return Err(PercolatorError::InvalidInstruction);
+        }
+        registry.exit_config.emergency = emergency;
+    }
+
+    registry.pause_flags = pause_flags;
+
+    events::emit_pause_flags(pause_flags, &registry.exit_config.emergency);
+    Ok(())
+}
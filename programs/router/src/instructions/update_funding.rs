@@ -0,0 +1,84 @@
+//! Update funding instruction - permissionless funding crank
+
+use crate::oracle::{CustomAdapter, OracleAdapter};
+use crate::state::SlabRegistry;
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg};
+
+/// Slab price used as the funding mark: the QuoteCache mid when both
+/// sides are quoted, else the header mark price
+fn read_slab_mark(slab_account: &AccountInfo) -> Result<i64, PercolatorError> {
+    let header = crate::matcher::read_slab_header(slab_account)?;
+
+    let data = slab_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+    if data.len() >= SlabHeader::LEN + QuoteCache::LEN {
+        let cache = unsafe {
+            core::ptr::read_unaligned(data[SlabHeader::LEN..].as_ptr() as *const QuoteCache)
+        };
+        let (bid, ask) = (cache.best_bids[0], cache.best_asks[0]);
+        if bid.px > 0 && ask.px > 0 && bid.avail_qty > 0 && ask.avail_qty > 0 {
+            return Ok(bid.px / 2 + ask.px / 2);
+        }
+    }
+
+    Ok(header.mark_px)
+}
+
+/// Process update funding instruction (permissionless)
+///
+/// Accrues one period of funding for a slab's instrument from the slab's
+/// premium over the oracle index, clamped and capped by the registry's
+/// funding parameters. Callable once per funding interval.
+///
+/// # Arguments
+/// * `registry` - Registry holding the instrument's funding state
+/// * `slab_account` - A registered slab trading the instrument (mark)
+/// * `oracle_account` - The instrument's pinned oracle (index), owned by
+///   the slab's oracle program
+/// * `current_ts` - Current Unix timestamp
+///
+/// # Returns
+/// * Funding rate applied (basis points, positive = longs pay)
+pub fn process_update_funding(
+    registry: &mut SlabRegistry,
+    slab_account: &AccountInfo,
+    oracle_account: &AccountInfo,
+    current_ts: u64,
+) -> Result<i64, PercolatorError> {
+    let (_, entry) = registry.find_slab(slab_account.key()).ok_or_else(|| {
+        msg!("Error: Slab not registered");
+        PercolatorError::SlabNotRegistered
+    })?;
+    let instrument_idx = entry.instrument_idx as usize;
+
+    if oracle_account.key() != &registry.instruments[instrument_idx].oracle {
+        msg!("Error: Oracle is not the instrument's pinned oracle");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if oracle_account.owner() != &entry.oracle_id {
+        msg!("Error: Oracle not owned by venue oracle program");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if crate::instructions::read_oracle_instrument(oracle_account)? != registry.instruments[instrument_idx].id {
+        msg!("Error: Oracle does not price the slab's instrument");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let index_px = CustomAdapter::new()
+        .read_price(oracle_account)
+        .map_err(|_| PercolatorError::InvalidAccount)?
+        .price;
+    let mark_px = read_slab_mark(slab_account)?;
+    if index_px <= 0 || mark_px <= 0 {
+        return Err(PercolatorError::InvalidPrice);
+    }
+
+    let params = registry.funding_params;
+    let rate = registry.instruments[instrument_idx]
+        .accrue_funding(mark_px, index_px, &params, current_ts)?;
+
+    msg!("Funding updated");
+    Ok(rate)
+}
@@ -1,24 +1,151 @@
 //! Withdraw instruction - withdraw collateral from vault
 
-use crate::state::Vault;
+use crate::instructions::{
+    mark_portfolio, refresh_amm_lp_margin, validate_vault_transfer, MarginAccounts, VaultTransferAccounts,
+};
+use crate::pda::VAULT_SEED;
+use crate::state::{on_user_touch, plan_exit, ExitAccount, Portfolio, SlabRegistry, Vault, PAUSE_WITHDRAWALS};
+use crate::token::transfer;
 use percolator_common::*;
+use pinocchio::{
+    instruction::{Seed, Signer},
+    msg,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+};
 
 /// Process withdraw instruction
 ///
 /// Withdraws collateral from the router vault to user's token account.
-/// Ensures sufficient available (non-pledged) balance exists.
+/// Vests PnL, revalues AMM LP buckets and marks the portfolio to oracle
+/// prices first, then caps the amount at the portfolio's free collateral
+/// after IM and its principal plus vested PnL. The amount must then fit the per-user and global exit
+/// buckets (after the free PnL and principal fast lane exemptions);
+/// larger amounts are queued with RequestWithdrawal instead.
+/// Transfers out of the vault token account signed by the vault PDA.
+///
+/// # Arguments
+/// * `vault` - Collateral vault
+/// * `portfolio` - User's portfolio (must belong to the signer)
+/// * `registry` - Registry (haircut, vesting, LP risk params and exit buckets)
+/// * `accounts` - Token accounts for the transfer
+/// * `margin` - One oracle per instrument the portfolio holds and its AMM LP triples
+/// * `program_id` - Router program ID
+/// * `amount` - Amount to withdraw
 pub fn process_withdraw(
     vault: &mut Vault,
+    portfolio: &mut Portfolio,
+    registry: &mut SlabRegistry,
+    accounts: &VaultTransferAccounts,
+    margin: MarginAccounts,
+    program_id: &Pubkey,
     amount: u128,
 ) -> Result<(), PercolatorError> {
+    if registry.is_paused(PAUSE_WITHDRAWALS) {
+        msg!("Error: Withdrawals are paused");
+        return Err(PercolatorError::ProtocolPaused);
+    }
+
     // Validate amount
     if amount == 0 {
         return Err(PercolatorError::InvalidQuantity);
     }
+    let token_amount = u64::try_from(amount).map_err(|_| PercolatorError::InvalidQuantity)?;
+
+    if !accounts.user.is_signer() {
+        msg!("Error: User must be signer");
+        return Err(PercolatorError::Unauthorized);
+    }
+    validate_vault_transfer(vault, portfolio, accounts, program_id)?;
+
+    // Apply PnL vesting and haircut catchup on user touch
+    let clock = Clock::get().ok();
+    let current_slot = clock.map(|c| c.slot).unwrap_or(portfolio.last_slot);
+    let current_ts = clock.map(|c| c.unix_timestamp as u64).unwrap_or(0);
+
+    on_user_touch(
+        portfolio.principal,
+        &mut portfolio.pnl,
+        &mut portfolio.vested_pnl,
+        &mut portfolio.last_slot,
+        &mut portfolio.pnl_index_checkpoint,
+        &registry.global_haircut,
+        &registry.pnl_vesting_params,
+        current_slot,
+    );
+
+    // Revalue AMM LP exposure and mark positions to the oracles so equity and IM are current
+    refresh_amm_lp_margin(portfolio, registry, margin.lp, current_ts)?;
+    mark_portfolio(portfolio, registry, margin.oracles, current_ts)?;
+
+    // Cap at free collateral and withdrawable cash
+    if amount > portfolio.withdrawable() {
+        msg!("Error: Withdrawal exceeds free collateral");
+        return Err(PercolatorError::PortfolioInsufficientMargin);
+    }
+
+    // Rate limit: the whole amount must pass the exit buckets now.
+    // While withdrawals are queued only threshold exemptions may skip
+    // ahead; bucket capacity is reserved for the queue.
+    registry.exit_config.emergency.refresh(current_ts);
+    let exit_config = if registry.queued_withdrawal_total > 0 {
+        registry.exit_config.exemptions_only()
+    } else {
+        registry.exit_config
+    };
+    let account = ExitAccount {
+        principal: portfolio.principal,
+        vested_pnl: portfolio.vested_pnl,
+        equity: portfolio.equity,
+    };
+    let plan = plan_exit(
+        &mut portfolio.exit_bucket,
+        &mut registry.global_exit_bucket,
+        &exit_config,
+        &account,
+        amount as i128,
+        vault.balance as i128,
+        current_ts,
+    );
+    if plan.queued > 0 {
+        msg!("Error: Withdrawal exceeds exit bucket allowance, use RequestWithdrawal to queue it");
+        return Err(PercolatorError::WithdrawalRateLimited);
+    }
 
     // Attempt withdrawal
     vault.withdraw(amount)
         .map_err(|_| PercolatorError::InsufficientFunds)?;
+    portfolio.debit_withdrawal(amount);
+
+    // Vault PDA signs the transfer out
+    transfer_from_vault(vault, accounts, token_amount)?;
 
     Ok(())
 }
+
+/// Transfer tokens out of the vault token account to the user
+///
+/// Signed by the vault PDA ["vault", mint, bump].
+pub fn transfer_from_vault(
+    vault: &Vault,
+    accounts: &VaultTransferAccounts,
+    amount: u64,
+) -> Result<(), PercolatorError> {
+    let mint = vault.mint;
+    let bump_array = [vault.bump];
+    let seeds = [
+        Seed::from(VAULT_SEED),
+        Seed::from(mint.as_ref()),
+        Seed::from(&bump_array[..]),
+    ];
+    let signer = Signer::from(&seeds);
+
+    transfer(
+        accounts.token_program,
+        accounts.vault_token,
+        accounts.user_token,
+        accounts.vault,
+        amount,
+        &[signer],
+    )
+}
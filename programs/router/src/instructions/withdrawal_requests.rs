@@ -0,0 +1,223 @@
+//! Withdrawal queue instructions - request, cancel and process
+//!
+//! Withdrawals above the exit bucket allowance are queued instead of
+//! failing. The amount leaves the portfolio at request time (so it cannot
+//! be traded away) and is paid from the vault as bucket capacity refills.
+
+use crate::instructions::{
+    mark_portfolio, refresh_amm_lp_margin, transfer_from_vault, validate_vault_transfer, MarginAccounts,
+    VaultTransferAccounts,
+};
+use crate::pda::derive_withdrawal_queue_pda;
+use crate::state::{
+    on_user_touch, plan_exit, ExitAccount, Portfolio, QueuedWithdrawal, SlabRegistry, Vault,
+    WithdrawalQueue, PAUSE_WITHDRAWALS,
+};
+use percolator_common::*;
+use pinocchio::{
+    account_info::AccountInfo,
+    msg,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+};
+
+/// Load a portfolio's withdrawal queue
+///
+/// Verifies the queue is the portfolio's PDA and initializes it on first use.
+pub fn load_withdrawal_queue<'a>(
+    queue_account: &'a AccountInfo,
+    portfolio_account: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<&'a mut WithdrawalQueue, PercolatorError> {
+    let (expected_pda, bump) = derive_withdrawal_queue_pda(portfolio_account.key(), program_id);
+    if queue_account.key() != &expected_pda {
+        msg!("Error: Withdrawal queue is not the correct PDA");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if queue_account.data_len() != WithdrawalQueue::LEN {
+        msg!("Error: Withdrawal queue account has incorrect size");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let queue = unsafe { borrow_account_data_mut::<WithdrawalQueue>(queue_account)? };
+    if !queue.is_initialized() {
+        queue.initialize_in_place(*program_id, *portfolio_account.key(), bump);
+    }
+    Ok(queue)
+}
+
+/// Process request withdrawal instruction
+///
+/// Vests PnL, revalues AMM LP buckets and marks the portfolio to oracle
+/// prices, checks the amount against the portfolio's withdrawable
+/// collateral, debits it and appends it to the back of the portfolio's
+/// queue with the next global ticket.
+///
+/// # Arguments
+/// * `queue` - Portfolio's withdrawal queue
+/// * `portfolio` - User's portfolio (must belong to `user`)
+/// * `user` - User (signer)
+/// * `registry` - Registry (vesting, LP risk params, queue totals)
+/// * `margin` - One oracle per instrument the portfolio holds and its AMM LP triples
+/// * `amount` - Amount to queue
+///
+/// # Returns
+/// * Ticket assigned to the queued withdrawal
+pub fn process_request_withdrawal(
+    queue: &mut WithdrawalQueue,
+    portfolio: &mut Portfolio,
+    user: &AccountInfo,
+    registry: &mut SlabRegistry,
+    margin: MarginAccounts,
+    amount: u128,
+) -> Result<u64, PercolatorError> {
+    if registry.is_paused(PAUSE_WITHDRAWALS) {
+        msg!("Error: Withdrawals are paused");
+        return Err(PercolatorError::ProtocolPaused);
+    }
+    if amount == 0 || amount > u64::MAX as u128 {
+        return Err(PercolatorError::InvalidQuantity);
+    }
+    if !user.is_signer() || &portfolio.user != user.key() {
+        msg!("Error: Portfolio owner must sign");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    // Apply PnL vesting and haircut catchup on user touch
+    let clock = Clock::get().ok();
+    let current_slot = clock.map(|c| c.slot).unwrap_or(portfolio.last_slot);
+    let current_ts = clock.map(|c| c.unix_timestamp as u64).unwrap_or(0);
+
+    on_user_touch(
+        portfolio.principal,
+        &mut portfolio.pnl,
+        &mut portfolio.vested_pnl,
+        &mut portfolio.last_slot,
+        &mut portfolio.pnl_index_checkpoint,
+        &registry.global_haircut,
+        &registry.pnl_vesting_params,
+        current_slot,
+    );
+
+    refresh_amm_lp_margin(portfolio, registry, margin.lp, current_ts)?;
+    mark_portfolio(portfolio, registry, margin.oracles, current_ts)?;
+
+    if amount > portfolio.withdrawable() {
+        msg!("Error: Withdrawal exceeds free collateral");
+        return Err(PercolatorError::PortfolioInsufficientMargin);
+    }
+
+    let ticket = registry.withdrawal_ticket;
+    let (principal_part, pnl_part) = portfolio.debit_withdrawal(amount);
+    queue
+        .push(QueuedWithdrawal {
+            ticket,
+            requested_ts: current_ts,
+            principal_part,
+            pnl_part,
+        })?;
+
+    registry.withdrawal_ticket = ticket.saturating_add(1);
+    registry.queued_withdrawal_total = registry.queued_withdrawal_total.saturating_add(amount);
+
+    Ok(ticket)
+}
+
+/// Process cancel withdrawal instruction
+///
+/// Removes a queued entry by ticket and returns its unpaid remainder to
+/// the portfolio (principal and vested PnL as originally debited).
+pub fn process_cancel_withdrawal(
+    queue: &mut WithdrawalQueue,
+    portfolio: &mut Portfolio,
+    user: &AccountInfo,
+    registry: &mut SlabRegistry,
+    ticket: u64,
+) -> Result<(), PercolatorError> {
+    if !user.is_signer() || &portfolio.user != user.key() {
+        msg!("Error: Portfolio owner must sign");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    let entry = queue.cancel(ticket).ok_or_else(|| {
+        msg!("Error: Queued withdrawal not found");
+        PercolatorError::InvalidInstruction
+    })?;
+
+    portfolio.restore_withdrawal(entry.principal_part, entry.pnl_part);
+    registry.queued_withdrawal_total = registry.queued_withdrawal_total.saturating_sub(entry.remaining());
+
+    Ok(())
+}
+
+/// Process queued withdrawals (permissionless crank)
+///
+/// Pays the portfolio's queue front to back through the exit buckets.
+/// An entry that only partly fits is paid in part and stays at the
+/// front; later entries wait behind it. Funds go only to the portfolio
+/// owner's token account.
+///
+/// # Returns
+/// * Total amount paid out (0 if no capacity was available)
+pub fn process_process_withdrawals(
+    queue: &mut WithdrawalQueue,
+    portfolio: &mut Portfolio,
+    vault: &mut Vault,
+    registry: &mut SlabRegistry,
+    accounts: &VaultTransferAccounts,
+    program_id: &Pubkey,
+) -> Result<u128, PercolatorError> {
+    if registry.is_paused(PAUSE_WITHDRAWALS) {
+        msg!("Error: Withdrawals are paused");
+        return Err(PercolatorError::ProtocolPaused);
+    }
+    validate_vault_transfer(vault, portfolio, accounts, program_id)?;
+
+    let current_ts = Clock::get().map(|c| c.unix_timestamp as u64).unwrap_or(0);
+    registry.exit_config.emergency.refresh(current_ts);
+
+    let mut paid: u128 = 0;
+    while let Some(entry) = queue.front_mut() {
+        let remaining = entry.remaining();
+
+        // Plan as if the queued amount were still in the portfolio
+        let account = ExitAccount {
+            principal: portfolio.principal.saturating_add(entry.principal_part as i128),
+            vested_pnl: portfolio.vested_pnl.saturating_add(entry.pnl_part as i128),
+            equity: portfolio.equity.saturating_add(remaining as i128),
+        };
+        let plan = plan_exit(
+            &mut portfolio.exit_bucket,
+            &mut registry.global_exit_bucket,
+            &registry.exit_config,
+            &account,
+            remaining as i128,
+            vault.balance.saturating_sub(paid) as i128,
+            current_ts,
+        );
+
+        let immediate = plan.immediate.max(0) as u128;
+        entry.drain(immediate);
+        paid = paid.saturating_add(immediate);
+
+        if plan.queued > 0 {
+            break;
+        }
+        queue.pop_front();
+    }
+
+    if paid == 0 {
+        msg!("No exit capacity available for queued withdrawals");
+        return Ok(0);
+    }
+
+    vault.withdraw(paid)
+        .map_err(|_| PercolatorError::InsufficientFunds)?;
+    registry.queued_withdrawal_total = registry.queued_withdrawal_total.saturating_sub(paid);
+
+    // Vault PDA signs the transfer out
+    let token_amount = u64::try_from(paid).map_err(|_| PercolatorError::Overflow)?;
+    transfer_from_vault(vault, accounts, token_amount)?;
+
+    Ok(paid)
+}
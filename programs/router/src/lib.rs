@@ -6,6 +6,9 @@ pub mod pda;
 pub mod liquidation;
 pub mod chooser;
 pub mod oracle;
+pub mod token;
+pub mod events;
+pub mod matcher;
 
 // Always expose entrypoint for testing, but only register as entrypoint when feature enabled
 pub mod entrypoint;
@@ -1,4 +1,4 @@
-//! Oracle alignment validation for liquidations
+//! Oracle alignment validation for liquidations and order routing
 
 use pinocchio::msg;
 
@@ -194,8 +194,8 @@ pub fn plan_reduce_only(
                 continue; // Skip misaligned slabs
             }
 
-            // Apply per-slab cap
-            let capped_qty = qty_to_reduce.min(registry.router_cap_per_slab as i64);
+            // Apply per-slab quantity cap
+            let capped_qty = qty_to_reduce.min(registry.router_cap_qty());
 
             msg!("Planner: Adding split to liquidation plan");
 
@@ -0,0 +1,220 @@
+//! Matcher (slab program) bytecode verification
+//!
+//! A slab's matcher program is identified by the SHA-256 of its deployed
+//! bytecode, read from the BPF upgradeable loader's programdata account.
+//! The hash is pinned in the registry at registration and re-checked on
+//! every CPI, so an upgraded matcher cannot silently change execution
+//! logic until governance re-registers it.
+
+use crate::state::SlabRegistry;
+use percolator_common::{PercolatorError, SlabHeader};
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
+
+/// BPF upgradeable loader program ID
+pub const BPF_LOADER_UPGRADEABLE_ID: Pubkey =
+    pinocchio_pubkey::pubkey!("BPFLoaderUpgradeab1e11111111111111111111111");
+
+/// Programdata metadata: state tag (u32) + slot (u64) + Option<Pubkey> upgrade authority
+pub const PROGRAMDATA_METADATA_LEN: usize = 4 + 8 + 1 + 32;
+
+/// `UpgradeableLoaderState::ProgramData` tag
+const PROGRAMDATA_TAG: u32 = 3;
+
+/// A slab as seen by the router: the instrument it trades, its contract
+/// size and the hash of its matcher's deployed bytecode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlabMatcher {
+    /// Slab account pubkey
+    pub slab_id: Pubkey,
+    /// Instrument ID from the slab header
+    pub instrument: Pubkey,
+    /// Contract size from the slab header (1e6 scale, 0 if not positive)
+    pub contract_size: u64,
+    /// SHA-256 of the programdata bytes after the loader metadata
+    pub version_hash: [u8; 32],
+}
+
+/// SHA-256 of a programdata account's bytecode region
+///
+/// Returns None if the data is not a ProgramData account.
+pub fn hash_programdata(data: &[u8]) -> Option<[u8; 32]> {
+    if data.len() < PROGRAMDATA_METADATA_LEN
+        || u32::from_le_bytes([data[0], data[1], data[2], data[3]]) != PROGRAMDATA_TAG
+    {
+        return None;
+    }
+    Some(sha256(&data[PROGRAMDATA_METADATA_LEN..]))
+}
+
+#[cfg(target_os = "solana")]
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    let vals: &[&[u8]] = &[bytes];
+    unsafe {
+        pinocchio::syscalls::sol_sha256(
+            vals as *const _ as *const u8,
+            vals.len() as u64,
+            hash.as_mut_ptr(),
+        );
+    }
+    hash
+}
+
+#[cfg(not(target_os = "solana"))]
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(bytes).into()
+}
+
+/// Read a slab's header
+pub fn read_slab_header(slab_account: &AccountInfo) -> Result<SlabHeader, PercolatorError> {
+    let data = slab_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+    if data.len() < SlabHeader::LEN {
+        msg!("Error: Invalid slab account data");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let header = unsafe { core::ptr::read_unaligned(data.as_ptr() as *const SlabHeader) };
+    if !header.validate() {
+        msg!("Error: Invalid slab header");
+        return Err(PercolatorError::InvalidSlab);
+    }
+    Ok(header)
+}
+
+/// Read a slab's instrument and the current bytecode hash of its matcher
+///
+/// The programdata account must be owned by the upgradeable loader and
+/// derived from the slab account's owner (the matcher program).
+pub fn read_slab_matcher(
+    slab_account: &AccountInfo,
+    programdata_account: &AccountInfo,
+) -> Result<SlabMatcher, PercolatorError> {
+    if programdata_account.owner() != &BPF_LOADER_UPGRADEABLE_ID {
+        msg!("Error: Matcher programdata not owned by the upgradeable loader");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let (expected, _) = pinocchio::pubkey::find_program_address(
+        &[slab_account.owner().as_ref()],
+        &BPF_LOADER_UPGRADEABLE_ID,
+    );
+    if programdata_account.key() != &expected {
+        msg!("Error: Programdata does not belong to the slab's matcher");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let data = programdata_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+    let version_hash = hash_programdata(&data).ok_or_else(|| {
+        msg!("Error: Invalid matcher programdata");
+        PercolatorError::InvalidAccount
+    })?;
+
+    let header = read_slab_header(slab_account)?;
+    Ok(SlabMatcher {
+        slab_id: *slab_account.key(),
+        instrument: header.instrument,
+        contract_size: header.contract_size.max(0) as u64,
+        version_hash,
+    })
+}
+
+/// Check a matcher version against the registry before CPI
+///
+/// # Returns
+/// * Registry index of the slab
+pub fn check_matcher_version(
+    registry: &SlabRegistry,
+    matcher: &SlabMatcher,
+) -> Result<u16, PercolatorError> {
+    let (idx, _) = registry.find_slab(&matcher.slab_id).ok_or_else(|| {
+        msg!("Error: Slab not registered");
+        PercolatorError::SlabNotRegistered
+    })?;
+
+    if !registry.validate_version(&matcher.slab_id, &matcher.version_hash) {
+        msg!("Error: Matcher bytecode changed since registration");
+        return Err(PercolatorError::SlabVersionMismatch);
+    }
+
+    Ok(idx)
+}
+
+/// Verify a slab's matcher bytecode against its registered hash
+pub fn verify_matcher(
+    registry: &SlabRegistry,
+    slab_account: &AccountInfo,
+    programdata_account: &AccountInfo,
+) -> Result<u16, PercolatorError> {
+    let matcher = read_slab_matcher(slab_account, programdata_account)?;
+    check_matcher_version(registry, &matcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn programdata(bytecode: &[u8]) -> [u8; 64] {
+        let mut data = [0u8; 64];
+        data[0..4].copy_from_slice(&PROGRAMDATA_TAG.to_le_bytes());
+        data[4..12].copy_from_slice(&77u64.to_le_bytes());
+        data[PROGRAMDATA_METADATA_LEN..PROGRAMDATA_METADATA_LEN + bytecode.len()].copy_from_slice(bytecode);
+        data
+    }
+
+    #[test]
+    fn test_hash_programdata_ignores_metadata() {
+        let a = programdata(b"matcher v1");
+        let mut b = a;
+        b[4..12].copy_from_slice(&99u64.to_le_bytes()); // redeployed at another slot
+
+        assert_eq!(hash_programdata(&a), hash_programdata(&b));
+        assert_ne!(hash_programdata(&a), hash_programdata(&programdata(b"matcher v2")));
+
+        // Not a ProgramData account
+        let mut program = a;
+        program[0] = 2;
+        assert_eq!(hash_programdata(&program), None);
+        assert_eq!(hash_programdata(&a[..10]), None);
+    }
+
+    #[test]
+    fn test_check_matcher_version() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        let slab_id = Pubkey::from([5; 32]);
+        let v1 = hash_programdata(&programdata(b"matcher v1")).unwrap();
+        let params = crate::instructions::SlabParams {
+            version_hash: v1,
+            oracle_id: Pubkey::default(),
+            oracle: Pubkey::default(),
+            imr: 500,
+            mmr: 250,
+            maker_fee_cap: 10,
+            taker_fee_cap: 20,
+            latency_sla_ms: 100,
+            max_exposure: 1_000_000,
+        };
+        registry.register_slab(slab_id, 0, &params, 0).unwrap();
+
+        let current = SlabMatcher {
+            slab_id,
+            instrument: Pubkey::default(),
+            contract_size: 1_000_000,
+            version_hash: v1,
+        };
+        assert_eq!(check_matcher_version(&registry, &current), Ok(0));
+
+        let upgraded = SlabMatcher {
+            version_hash: hash_programdata(&programdata(b"matcher v2")).unwrap(),
+            ..current
+        };
+        assert_eq!(check_matcher_version(&registry, &upgraded), Err(PercolatorError::SlabVersionMismatch));
+
+        let unknown = SlabMatcher { slab_id: Pubkey::from([6; 32]), ..current };
+        assert_eq!(check_matcher_version(&registry, &unknown), Err(PercolatorError::SlabNotRegistered));
+    }
+}
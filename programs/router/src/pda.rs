@@ -23,6 +23,9 @@ pub const REGISTRY_SEED: &[u8] = b"registry";
 /// Seed prefix for router authority (used for CPI signing)
 pub const AUTHORITY_SEED: &[u8] = b"authority";
 
+/// Seed prefix for withdrawal queue accounts
+pub const WITHDRAWAL_QUEUE_SEED: &[u8] = b"withdrawal_queue";
+
 /// Derive router authority PDA
 ///
 /// This PDA is used as the router's signing authority for CPIs to slabs.
@@ -134,6 +137,20 @@ pub fn derive_registry_pda(program_id: &Pubkey) -> (Pubkey, u8) {
     find_program_address(&[REGISTRY_SEED], program_id)
 }
 
+/// Derive withdrawal queue PDA for a portfolio
+///
+/// Queue holds the portfolio's rate-limited withdrawals in FIFO order
+///
+/// # Arguments
+/// * `portfolio` - The portfolio pubkey
+/// * `program_id` - The router program ID
+///
+/// # Returns
+/// * `(Pubkey, u8)` - The derived PDA and its bump seed
+pub fn derive_withdrawal_queue_pda(portfolio: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    find_program_address(&[WITHDRAWAL_QUEUE_SEED, portfolio.as_ref()], program_id)
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(target_os = "solana")]
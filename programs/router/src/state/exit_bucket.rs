@@ -0,0 +1,442 @@
+//! Withdrawal rate limits (exit buckets)
+//!
+//! Throttles how fast collateral can leave the router:
+//! - Per-user bucket: % of equity per rolling window, with a hard max
+//! - Global bucket: % of TVL per rolling window, with a hard max
+//! - Threshold exceptions that bypass the buckets (daily free PnL and
+//!   a principal fast lane)
+//! - Emergency mode scaling every cap down by a multiplier
+//!
+//! Amounts are in the 1e6 money scale.
+
+/// Money scale (1e6 for $1.00)
+const SCALE: i128 = 1_000_000;
+
+/// Seconds per day (daily threshold reset)
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Shortest rolling window governance may set
+pub const MIN_EXIT_WINDOW_SECS: u64 = 60;
+
+/// Longest rolling window governance may set
+pub const MAX_EXIT_WINDOW_SECS: u64 = 7 * SECS_PER_DAY;
+
+/// Exit bucket parameters (governance configurable)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ExitBucketParams {
+    /// Per-user percentage of equity per hour (basis points)
+    /// e.g., 2000 = 20% of equity per hour
+    pub user_pct_per_hour_bps: u64,
+
+    /// Per-user hard maximum per hour (in SCALE units)
+    /// e.g., 250_000 * SCALE = $250k per hour
+    pub user_hard_max_per_hour: i128,
+
+    /// Rolling window duration in seconds (typically 3600 = 1 hour)
+    pub rolling_window_secs: u64,
+
+    /// Global percentage of TVL per hour (basis points)
+    /// e.g., 500 = 5% of TVL per hour
+    pub tvl_pct_per_hour_bps: u64,
+
+    /// Global hard maximum per hour (as percentage of TVL, basis points)
+    /// e.g., 300 = 3% of TVL hard cap
+    pub global_hard_max_bps: u64,
+}
+
+impl Default for ExitBucketParams {
+    fn default() -> Self {
+        Self {
+            user_pct_per_hour_bps: 2000,  // 20%/h
+            user_hard_max_per_hour: 500_000 * SCALE,  // $500k/h (allows 20% up to $500k)
+            rolling_window_secs: 3600,  // 1 hour
+            tvl_pct_per_hour_bps: 500,  // 5%/h
+            global_hard_max_bps: 10000,  // 100% of TVL (effectively no hard max unless explicitly set)
+        }
+    }
+}
+
+impl ExitBucketParams {
+    /// Percentages at most 100%, caps non-negative, window within bounds
+    pub fn is_valid(&self) -> bool {
+        self.user_pct_per_hour_bps <= 10_000
+            && self.tvl_pct_per_hour_bps <= 10_000
+            && self.global_hard_max_bps <= 10_000
+            && self.user_hard_max_per_hour >= 0
+            && (MIN_EXIT_WINDOW_SECS..=MAX_EXIT_WINDOW_SECS).contains(&self.rolling_window_secs)
+    }
+}
+
+/// Withdrawal threshold exceptions
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WithdrawalThresholds {
+    /// Free PnL threshold per day (no bucket charge)
+    /// e.g., 500 * SCALE = $500/day free
+    pub free_pnl_threshold_per_day: i128,
+
+    /// Principal fast lane per day (no bucket charge)
+    /// min(pct_of_principal, hard_max)
+    pub principal_fast_lane_pct_bps: u64,  // e.g., 500 = 5%
+    pub principal_fast_lane_hard_max: i128,  // e.g., $10k
+}
+
+impl Default for WithdrawalThresholds {
+    fn default() -> Self {
+        Self {
+            free_pnl_threshold_per_day: 500 * SCALE,
+            principal_fast_lane_pct_bps: 500,  // 5%
+            principal_fast_lane_hard_max: 10_000 * SCALE,
+        }
+    }
+}
+
+impl WithdrawalThresholds {
+    /// Fast lane percentage at most 100%, amounts non-negative
+    pub fn is_valid(&self) -> bool {
+        self.principal_fast_lane_pct_bps <= 10_000
+            && self.free_pnl_threshold_per_day >= 0
+            && self.principal_fast_lane_hard_max >= 0
+    }
+}
+
+/// Emergency mode parameters
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EmergencyMode {
+    /// Is emergency mode active?
+    pub active: bool,
+
+    /// Exit multiplier during emergency (basis points)
+    /// e.g., 5000 = 50% of normal caps
+    pub exit_multiplier_bps: u64,
+
+    /// When emergency mode auto-expires (seconds, 0 = no expiry)
+    pub expires_at_secs: u64,
+}
+
+impl Default for EmergencyMode {
+    fn default() -> Self {
+        Self {
+            active: false,
+            exit_multiplier_bps: 10000,  // 100% (no reduction)
+            expires_at_secs: 0,
+        }
+    }
+}
+
+impl EmergencyMode {
+    /// Clear emergency mode once its expiry has passed
+    pub fn refresh(&mut self, now_secs: u64) {
+        if self.active && self.expires_at_secs != 0 && now_secs >= self.expires_at_secs {
+            self.active = false;
+        }
+    }
+
+    /// Scale a cap by the emergency multiplier (overflow-safe)
+    fn apply(&self, cap: i128) -> i128 {
+        if !self.active {
+            return cap;
+        }
+        mul_bps(cap, self.exit_multiplier_bps)
+    }
+}
+
+/// Exit limit configuration stored in the registry
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExitConfig {
+    /// Bucket caps and window
+    pub params: ExitBucketParams,
+    /// Bucket-exempt thresholds
+    pub thresholds: WithdrawalThresholds,
+    /// Emergency cap multiplier
+    pub emergency: EmergencyMode,
+}
+
+impl ExitConfig {
+    /// Config that only lets threshold exemptions through
+    ///
+    /// Used while withdrawals are queued, so fresh requests cannot take
+    /// bucket capacity ahead of the queue.
+    pub fn exemptions_only(&self) -> Self {
+        let mut config = *self;
+        config.params.user_pct_per_hour_bps = 0;
+        config.params.tvl_pct_per_hour_bps = 0;
+        config
+    }
+}
+
+/// Per-user exit bucket state (time-windowed rate limiter)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UserExitBucket {
+    /// Amount used in current window
+    pub amount_used: i128,
+
+    /// Free PnL used today
+    pub free_pnl_used_today: i128,
+
+    /// Principal fast lane used today
+    pub principal_fast_lane_used_today: i128,
+
+    /// Window start time (seconds since epoch)
+    pub window_start_secs: u64,
+
+    /// Last reset day (for daily threshold resets)
+    pub last_reset_day: u64,
+}
+
+/// Global exit bucket state (aggregate throttling)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalExitBucket {
+    /// Total amount used in current window across all users
+    pub amount_used: i128,
+
+    /// Window start time
+    pub window_start_secs: u64,
+}
+
+/// Withdrawal plan result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WithdrawalPlan {
+    /// Amount that can be withdrawn immediately
+    pub immediate: i128,
+
+    /// Amount that must be queued
+    pub queued: i128,
+
+    /// Estimated wait time in seconds (0 if immediate)
+    pub eta_secs: u64,
+}
+
+/// Account balances an exit is planned against
+#[derive(Debug, Clone, Copy)]
+pub struct ExitAccount {
+    /// Principal (fast lane base)
+    pub principal: i128,
+    /// Vested PnL (free PnL threshold base)
+    pub vested_pnl: i128,
+    /// Equity (user bucket base)
+    pub equity: i128,
+}
+
+/// value * bps / 10000, dividing first for very large values
+fn mul_bps(value: i128, bps: u64) -> i128 {
+    if value.abs() > i128::MAX / 10000 {
+        (value / 10000) * bps as i128
+    } else {
+        (value * bps as i128) / 10000
+    }
+}
+
+/// Reset bucket if window has expired
+pub fn maybe_reset_bucket(
+    bucket: &mut UserExitBucket,
+    now_secs: u64,
+    window_secs: u64,
+) {
+    if now_secs >= bucket.window_start_secs.saturating_add(window_secs) {
+        bucket.amount_used = 0;
+        bucket.window_start_secs = now_secs;
+    }
+}
+
+/// Reset daily thresholds if day boundary crossed
+pub fn maybe_reset_daily_thresholds(
+    bucket: &mut UserExitBucket,
+    now_secs: u64,
+) {
+    let current_day = now_secs / SECS_PER_DAY;
+    if current_day > bucket.last_reset_day {
+        bucket.free_pnl_used_today = 0;
+        bucket.principal_fast_lane_used_today = 0;
+        bucket.last_reset_day = current_day;
+    }
+}
+
+/// Compute per-user exit allowance
+pub fn compute_user_allowance(
+    equity: i128,
+    bucket: &UserExitBucket,
+    params: &ExitBucketParams,
+    emergency: &EmergencyMode,
+) -> i128 {
+    // Base cap: min(pct_of_equity, hard_max)
+    let cap = mul_bps(equity, params.user_pct_per_hour_bps).min(params.user_hard_max_per_hour);
+    let cap = emergency.apply(cap);
+
+    // Return remaining allowance
+    cap.saturating_sub(bucket.amount_used).max(0)
+}
+
+/// Compute global exit allowance
+pub fn compute_global_allowance(
+    tvl: i128,
+    global_bucket: &GlobalExitBucket,
+    params: &ExitBucketParams,
+    emergency: &EmergencyMode,
+    now_secs: u64,
+) -> i128 {
+    // Ignore usage from an expired window
+    let window_expired = now_secs >= global_bucket.window_start_secs.saturating_add(params.rolling_window_secs);
+    let bucket_used = if window_expired { 0 } else { global_bucket.amount_used };
+
+    // Base cap: min(pct_of_tvl, hard_max)
+    let pct_cap = mul_bps(tvl, params.tvl_pct_per_hour_bps);
+    let hard_max = mul_bps(tvl, params.global_hard_max_bps);
+    let cap = emergency.apply(pct_cap.min(hard_max));
+
+    cap.saturating_sub(bucket_used).max(0)
+}
+
+/// Plan an exit: split `requested` into immediate and queued amounts
+///
+/// Threshold exceptions are applied first (free PnL for vested PnL, then
+/// the principal fast lane); the rest is limited by min(user, global)
+/// bucket allowance. Buckets and daily thresholds are charged for the
+/// immediate portion only.
+///
+/// `requested` must already be capped at what the account may withdraw.
+pub fn plan_exit(
+    bucket: &mut UserExitBucket,
+    global_bucket: &mut GlobalExitBucket,
+    config: &ExitConfig,
+    account: &ExitAccount,
+    requested: i128,
+    tvl: i128,
+    now_secs: u64,
+) -> WithdrawalPlan {
+    if requested <= 0 {
+        return WithdrawalPlan { immediate: 0, queued: 0, eta_secs: 0 };
+    }
+
+    let params = &config.params;
+    let thresholds = &config.thresholds;
+
+    // Reset buckets if windows expired
+    maybe_reset_bucket(bucket, now_secs, params.rolling_window_secs);
+    maybe_reset_daily_thresholds(bucket, now_secs);
+
+    if global_bucket.window_start_secs == 0
+        || now_secs >= global_bucket.window_start_secs.saturating_add(params.rolling_window_secs)
+    {
+        global_bucket.amount_used = 0;
+        global_bucket.window_start_secs = now_secs;
+    }
+
+    // Threshold exceptions: free PnL first, then principal fast lane
+    let mut bypass_amount = 0i128;
+    let mut remaining_request = requested;
+
+    let free_pnl_remaining = thresholds.free_pnl_threshold_per_day.saturating_sub(bucket.free_pnl_used_today);
+    if free_pnl_remaining > 0 && account.vested_pnl > 0 {
+        let from_free_pnl = free_pnl_remaining.min(remaining_request).min(account.vested_pnl);
+        bypass_amount += from_free_pnl;
+        bucket.free_pnl_used_today += from_free_pnl;
+        remaining_request -= from_free_pnl;
+    }
+
+    let principal_fast_lane_cap = mul_bps(account.principal, thresholds.principal_fast_lane_pct_bps)
+        .min(thresholds.principal_fast_lane_hard_max);
+    let principal_fast_lane_remaining = principal_fast_lane_cap.saturating_sub(bucket.principal_fast_lane_used_today);
+    if principal_fast_lane_remaining > 0 && remaining_request > 0 {
+        let from_fast_lane = principal_fast_lane_remaining.min(remaining_request);
+        bypass_amount += from_fast_lane;
+        bucket.principal_fast_lane_used_today += from_fast_lane;
+        remaining_request -= from_fast_lane;
+    }
+
+    if remaining_request == 0 {
+        return WithdrawalPlan { immediate: requested, queued: 0, eta_secs: 0 };
+    }
+
+    // Remaining amount is limited by min(user, global) allowance
+    let user_allowance = compute_user_allowance(account.equity, bucket, params, &config.emergency);
+    let global_allowance = compute_global_allowance(tvl, global_bucket, params, &config.emergency, now_secs);
+    let through_buckets = remaining_request.min(user_allowance.min(global_allowance));
+
+    bucket.amount_used += through_buckets;
+    global_bucket.amount_used += through_buckets;
+
+    let immediate = bypass_amount + through_buckets;
+    let queued = requested - immediate;
+
+    // Caps refill when the window rolls over
+    let eta_secs = if queued > 0 { params.rolling_window_secs } else { 0 };
+
+    WithdrawalPlan { immediate, queued, eta_secs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(principal: i128, vested_pnl: i128) -> ExitAccount {
+        ExitAccount { principal, vested_pnl, equity: principal + vested_pnl }
+    }
+
+    #[test]
+    fn test_fast_lane_then_bucket() {
+        let config = ExitConfig::default();
+        let mut bucket = UserExitBucket::default();
+        let mut global = GlobalExitBucket::default();
+
+        // 100k principal: fast lane 5k, then 20% of equity = 20k
+        let plan = plan_exit(&mut bucket, &mut global, &config, &account(100_000 * SCALE, 0),
+            50_000 * SCALE, 10_000_000 * SCALE, 1000);
+
+        assert_eq!(plan.immediate, 25_000 * SCALE);
+        assert_eq!(plan.queued, 25_000 * SCALE);
+        assert_eq!(bucket.principal_fast_lane_used_today, 5_000 * SCALE);
+        assert_eq!(bucket.amount_used, 20_000 * SCALE);
+        assert_eq!(global.amount_used, 20_000 * SCALE);
+    }
+
+    #[test]
+    fn test_global_bucket_limits_and_rolls_over() {
+        let mut config = ExitConfig::default();
+        config.thresholds.principal_fast_lane_pct_bps = 0;
+        let mut bucket = UserExitBucket::default();
+        let mut global = GlobalExitBucket::default();
+
+        // TVL 100k: global cap 5% = 5k, below the user cap
+        let plan = plan_exit(&mut bucket, &mut global, &config, &account(100_000 * SCALE, 0),
+            10_000 * SCALE, 100_000 * SCALE, 1000);
+        assert_eq!(plan.immediate, 5_000 * SCALE);
+
+        // Next window refills the global bucket
+        let mut bucket = UserExitBucket::default();
+        let plan = plan_exit(&mut bucket, &mut global, &config, &account(100_000 * SCALE, 0),
+            10_000 * SCALE, 100_000 * SCALE, 1000 + 3600);
+        assert_eq!(plan.immediate, 5_000 * SCALE);
+    }
+
+    #[test]
+    fn test_exemptions_only_blocks_bucket_capacity() {
+        let config = ExitConfig::default().exemptions_only();
+        let mut bucket = UserExitBucket::default();
+        let mut global = GlobalExitBucket::default();
+
+        // Only the 5k principal fast lane passes
+        let plan = plan_exit(&mut bucket, &mut global, &config, &account(100_000 * SCALE, 0),
+            50_000 * SCALE, 10_000_000 * SCALE, 1000);
+        assert_eq!(plan.immediate, 5_000 * SCALE);
+        assert_eq!(bucket.amount_used, 0);
+    }
+
+    #[test]
+    fn test_emergency_expiry() {
+        let mut emergency = EmergencyMode { active: true, exit_multiplier_bps: 5000, expires_at_secs: 100 };
+
+        emergency.refresh(99);
+        assert!(emergency.active);
+        assert_eq!(emergency.apply(1000), 500);
+
+        emergency.refresh(100);
+        assert!(!emergency.active);
+        assert_eq!(emergency.apply(1000), 1000);
+    }
+}
@@ -0,0 +1,138 @@
+//! Perpetual funding
+//!
+//! A permissionless crank accrues funding per instrument once per
+//! interval from the premium of the slab price over the oracle index:
+//! - Premium inside ±`clamp_bps` of the index pays no funding (dead band)
+//! - Outside it, the rate is the premium beyond the band, capped at
+//!   ±`max_rate_bps` per period
+//! - Cumulative funding grows by rate × index price each period
+//!
+//! Positions checkpoint the cumulative funding and settle the difference
+//! into PnL on each portfolio touch: longs pay and shorts receive when
+//! the slab trades above the index, pulling it back toward the index.
+
+use crate::state::InstrumentEntry;
+use percolator_common::PercolatorError;
+use pinocchio::msg;
+
+/// Funding parameters
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FundingParams {
+    /// Minimum seconds between funding accruals (one period)
+    pub interval_secs: u64,
+    /// Premium dead band (basis points of index)
+    pub clamp_bps: u64,
+    /// Maximum funding rate per period (basis points of index)
+    pub max_rate_bps: u64,
+}
+
+impl Default for FundingParams {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3_600, // Hourly
+            clamp_bps: 5,         // 0.05% dead band
+            max_rate_bps: 75,     // 0.75% per hour cap
+        }
+    }
+}
+
+/// Funding rate for one period (basis points, positive = longs pay)
+pub fn funding_rate_bps(mark_px: i64, index_px: i64, params: &FundingParams) -> i64 {
+    if index_px <= 0 {
+        return 0;
+    }
+
+    let premium_bps = ((mark_px as i128 - index_px as i128) * 10_000 / index_px as i128)
+        .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+    let clamp = params.clamp_bps.min(i64::MAX as u64) as i64;
+    let cap = params.max_rate_bps.min(i64::MAX as u64) as i64;
+
+    // premium + clamp(-premium, ±clamp): zero inside the band
+    let rate = premium_bps + (-premium_bps).clamp(-clamp, clamp);
+    rate.clamp(-cap, cap)
+}
+
+impl InstrumentEntry {
+    /// Accrue one period of funding
+    ///
+    /// The first call only starts the clock. Periods missed between cranks
+    /// are not back-charged: each call accrues at most one period.
+    ///
+    /// # Returns
+    /// * Funding rate applied (basis points)
+    pub fn accrue_funding(
+        &mut self,
+        mark_px: i64,
+        index_px: i64,
+        params: &FundingParams,
+        current_ts: u64,
+    ) -> Result<i64, PercolatorError> {
+        if self.last_funding_ts == 0 {
+            self.last_funding_ts = current_ts;
+            return Ok(0);
+        }
+        if current_ts < self.last_funding_ts.saturating_add(params.interval_secs) {
+            msg!("Error: Funding period has not elapsed");
+            return Err(PercolatorError::FundingNotDue);
+        }
+
+        let rate = funding_rate_bps(mark_px, index_px, params);
+        self.funding_rate = rate;
+        self.cum_funding = self
+            .cum_funding
+            .saturating_add(rate as i128 * index_px as i128 / 10_000);
+        self.last_funding_ts = current_ts;
+        Ok(rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P: i64 = 1_000_000;
+
+    #[test]
+    fn test_funding_rate_band_and_cap() {
+        let params = FundingParams::default();
+
+        // Within ±5 bps of index: no funding
+        assert_eq!(funding_rate_bps(10_004 * P / 100, 100 * P, &params), 0);
+        assert_eq!(funding_rate_bps(100 * P, 100 * P, &params), 0);
+
+        // 0.5% premium: 50 - 5 = 45 bps, longs pay
+        assert_eq!(funding_rate_bps(1005 * P / 10, 100 * P, &params), 45);
+        // 0.5% discount: shorts pay
+        assert_eq!(funding_rate_bps(995 * P / 10, 100 * P, &params), -45);
+
+        // 10% premium is capped
+        assert_eq!(funding_rate_bps(110 * P, 100 * P, &params), 75);
+        assert_eq!(funding_rate_bps(90 * P, 100 * P, &params), -75);
+
+        // No index: no funding
+        assert_eq!(funding_rate_bps(100 * P, 0, &params), 0);
+    }
+
+    #[test]
+    fn test_accrue_funding_once_per_period() {
+        let params = FundingParams::default();
+        let mut instrument = InstrumentEntry { contract_size: 1_000_000, ..InstrumentEntry::empty() };
+
+        // First crank starts the clock
+        assert_eq!(instrument.accrue_funding(110 * P, 100 * P, &params, 1_000), Ok(0));
+        assert_eq!(instrument.cum_funding, 0);
+
+        // Not due yet
+        assert_eq!(instrument.accrue_funding(110 * P, 100 * P, &params, 1_000 + 3_599), Err(PercolatorError::FundingNotDue));
+
+        // One period at the 75 bps cap of a 100 index: 0.75 per unit
+        assert_eq!(instrument.accrue_funding(110 * P, 100 * P, &params, 1_000 + 3_600), Ok(75));
+        assert_eq!(instrument.cum_funding, 750_000);
+        assert_eq!(instrument.funding_rate, 75);
+
+        // A day late still accrues a single period
+        assert_eq!(instrument.accrue_funding(995 * P / 10, 100 * P, &params, 1_000 + 90_000), Ok(-45));
+        assert_eq!(instrument.cum_funding, 750_000 - 450_000);
+    }
+}
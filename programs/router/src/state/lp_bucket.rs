@@ -160,6 +160,17 @@ impl SlabLp {
     }
 }
 
+/// AMM pool inventory snapshot used to value LP buckets
+#[derive(Debug, Clone, Copy)]
+pub struct AmmInventory {
+    /// Pool base inventory (contracts, 1e6 scale)
+    pub base: i64,
+    /// Pool quote inventory (1e6 scale)
+    pub quote: i64,
+    /// Total LP shares outstanding (1e6 scale)
+    pub total_shares: u64,
+}
+
 /// LP bucket for a specific venue
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -217,6 +228,47 @@ impl LpBucket {
         self.mm = mm;
     }
 
+    /// Refresh AMM LP margin from the LP's share of pool inventory
+    ///
+    /// The LP carries `lp_shares / total_shares` of the pool's net base
+    /// inventory; that directional exposure is valued at `oracle_px` and
+    /// margined at `imr_bps` / `mmr_bps`. The cached share price is
+    /// refreshed at the same time.
+    pub fn refresh_amm_margin(
+        &mut self,
+        pool: &AmmInventory,
+        oracle_px: i64,
+        imr_bps: u64,
+        mmr_bps: u64,
+        timestamp: u64,
+    ) {
+        let Some(amm) = self.amm.as_mut() else {
+            return;
+        };
+
+        let total_shares = pool.total_shares;
+        if total_shares == 0 {
+            self.im = 0;
+            self.mm = 0;
+            return;
+        }
+
+        // LP's slice of the pool's base inventory (1e6 scale)
+        let lp_base = pool.base as i128 * amm.lp_shares as i128 / total_shares as i128;
+        let notional = lp_base.unsigned_abs() * oracle_px.max(0) as u128 / 1_000_000;
+
+        // Pool value per share at the oracle price (shares are 1e6 scale,
+        // matching the redemption math in burn_lp_shares)
+        let pool_value = pool.base as i128 * oracle_px as i128 / 1_000_000 + pool.quote as i128;
+        amm.share_price_cached = (pool_value * 1_000_000 / total_shares as i128) as i64;
+        amm.last_update_ts = timestamp;
+
+        self.update_margin(
+            notional * imr_bps as u128 / 10_000,
+            notional * mmr_bps as u128 / 10_000,
+        );
+    }
+
     /// Check if bucket is AMM
     pub fn is_amm(&self) -> bool {
         self.venue.venue_kind == VenueKind::Amm
@@ -310,4 +362,53 @@ mod tests {
         assert!(slab.add_reservation(999, 100, 50).is_err());
         assert_eq!(slab.open_order_count, MAX_OPEN_ORDERS as u16);
     }
+
+    #[test]
+    fn test_refresh_amm_margin() {
+        let market = Pubkey::from([1; 32]);
+        let mut bucket = LpBucket::new_amm(VenueId::new_amm(market), 250_000, 0, 0);
+
+        // Pool holds 100 contracts + $6M quote; LP owns 0.25 of 1.0 shares (1e6 scale)
+        let pool = AmmInventory {
+            base: 100_000_000,
+            quote: 6_000_000_000_000,
+            total_shares: 1_000_000,
+        };
+        bucket.refresh_amm_margin(&pool, 60_000_000_000, 500, 250, 42);
+
+        // 25 contracts * $60k = $1.5M notional
+        assert_eq!(bucket.im, 75_000_000_000); // 5%
+        assert_eq!(bucket.mm, 37_500_000_000); // 2.5%
+
+        // Pool value $12M per whole share
+        let amm = bucket.amm.unwrap();
+        assert_eq!(amm.share_price_cached, 12_000_000_000_000);
+        assert_eq!(amm.last_update_ts, 42);
+
+        // Inventory sold down: margin follows
+        let pool = AmmInventory {
+            base: 0,
+            quote: 12_000_000_000_000,
+            total_shares: 1_000_000,
+        };
+        bucket.refresh_amm_margin(&pool, 60_000_000_000, 500, 250, 43);
+        assert_eq!(bucket.im, 0);
+        assert_eq!(bucket.mm, 0);
+    }
+
+    #[test]
+    fn test_refresh_amm_margin_ignores_slab_bucket() {
+        let market = Pubkey::from([1; 32]);
+        let mut bucket = LpBucket::new_slab(VenueId::new_slab(market));
+        bucket.update_margin(10, 5);
+
+        let pool = AmmInventory {
+            base: 100_000_000,
+            quote: 0,
+            total_shares: 1_000_000,
+        };
+        bucket.refresh_amm_margin(&pool, 60_000_000_000, 500, 250, 1);
+        assert_eq!(bucket.im, 10);
+        assert_eq!(bucket.mm, 5);
+    }
 }
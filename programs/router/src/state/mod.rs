@@ -5,6 +5,10 @@ pub mod lp_bucket;
 pub mod insurance;
 pub mod pnl_vesting;
 pub mod model_bridge;
+pub mod exit_bucket;
+pub mod withdrawal_queue;
+pub mod proposal;
+pub mod funding;
 
 #[cfg(test)]
 pub mod withdrawal_limits_test;
@@ -16,3 +20,7 @@ pub use lp_bucket::*;
 pub use insurance::*;
 pub use pnl_vesting::*;
 pub use model_bridge::*;
+pub use exit_bucket::*;
+pub use withdrawal_queue::*;
+pub use proposal::*;
+pub use funding::*;
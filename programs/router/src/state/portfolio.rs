@@ -3,6 +3,7 @@
 use pinocchio::pubkey::Pubkey;
 use percolator_common::{MAX_INSTRUMENTS, MAX_SLABS};
 use crate::state::lp_bucket::{LpBucket, VenueId, MAX_LP_BUCKETS};
+use crate::state::exit_bucket::UserExitBucket;
 
 /// Exposure key: (slab_index, instrument_index)
 pub type ExposureKey = (u16, u16);
@@ -56,10 +57,17 @@ pub struct Portfolio {
     /// Padding for alignment
     pub _padding4: [u8; 8],
 
+    /// Per-user withdrawal rate limit state
+    pub exit_bucket: UserExitBucket,
+
     /// Principal exposures: (slab_idx, instrument_idx) -> position qty
     /// These are TRADER positions, separate from LP exposure
     /// Using fixed-size array for simplicity (can optimize with HashMap-like structure)
     pub exposures: [(u16, u16, i64); MAX_SLABS * MAX_INSTRUMENTS],
+    /// Average entry price (1e6 scale) for each entry in `exposures`
+    pub entry_prices: [u64; MAX_SLABS * MAX_INSTRUMENTS],
+    /// Instrument cumulative funding last settled, for each entry in `exposures`
+    pub funding_checkpoints: [i128; MAX_SLABS * MAX_INSTRUMENTS],
 
     /// LP buckets: venue-scoped liquidity provider exposure
     /// AMM LP reduced ONLY by burn_lp_shares()
@@ -104,6 +112,9 @@ impl Portfolio {
         self.pnl_index_checkpoint = crate::state::pnl_vesting::FP_ONE;  // Start at 1.0 (no haircut)
         self._padding4 = [0; 8];
 
+        // Initialize exit bucket (no withdrawals yet)
+        self.exit_bucket = UserExitBucket::default();
+
         // Zero out the exposures array using ptr::write_bytes (efficient and stack-safe)
         unsafe {
             core::ptr::write_bytes(
@@ -111,6 +122,16 @@ impl Portfolio {
                 0,
                 MAX_SLABS * MAX_INSTRUMENTS,
             );
+            core::ptr::write_bytes(
+                self.entry_prices.as_mut_ptr(),
+                0,
+                MAX_SLABS * MAX_INSTRUMENTS,
+            );
+            core::ptr::write_bytes(
+                self.funding_checkpoints.as_mut_ptr(),
+                0,
+                MAX_SLABS * MAX_INSTRUMENTS,
+            );
         }
 
         // Initialize LP buckets
@@ -153,7 +174,10 @@ impl Portfolio {
             last_slot: 0,
             pnl_index_checkpoint: crate::state::pnl_vesting::FP_ONE,
             _padding4: [0; 8],
+            exit_bucket: UserExitBucket::default(),
             exposures: [(0, 0, 0); MAX_SLABS * MAX_INSTRUMENTS],
+            entry_prices: [0; MAX_SLABS * MAX_INSTRUMENTS],
+            funding_checkpoints: [0; MAX_SLABS * MAX_INSTRUMENTS],
             lp_buckets: [zero_bucket; MAX_LP_BUCKETS],
             lp_bucket_count: 0,
             _padding3: [0; 6],
@@ -189,8 +213,12 @@ impl Portfolio {
             let last_idx = (self.exposure_count - 1) as usize;
             if idx != last_idx {
                 self.exposures[idx] = self.exposures[last_idx];
+                self.entry_prices[idx] = self.entry_prices[last_idx];
+                self.funding_checkpoints[idx] = self.funding_checkpoints[last_idx];
             }
             self.exposures[last_idx] = (0, 0, 0);
+            self.entry_prices[last_idx] = 0;
+            self.funding_checkpoints[last_idx] = 0;
             self.exposure_count -= 1;
         }
     }
@@ -205,6 +233,102 @@ impl Portfolio {
         0
     }
 
+    /// Notional of the (slab, instrument) position at its entry price:
+    /// |qty| * entry / 1e6
+    pub fn position_notional(&self, slab_idx: u16, instrument_idx: u16) -> u128 {
+        let qty = self.get_exposure(slab_idx, instrument_idx).unsigned_abs() as u128;
+        qty * self.get_entry_price(slab_idx, instrument_idx) as u128 / 1_000_000
+    }
+
+    /// Get average entry price for (slab, instrument), 0 if flat
+    pub fn get_entry_price(&self, slab_idx: u16, instrument_idx: u16) -> u64 {
+        for i in 0..self.exposure_count as usize {
+            if self.exposures[i].0 == slab_idx && self.exposures[i].1 == instrument_idx {
+                return self.entry_prices[i];
+            }
+        }
+        0
+    }
+
+    /// Apply a fill to the (slab, instrument) position
+    ///
+    /// Adding to a position moves its average entry price; reducing or
+    /// flipping realizes PnL on the closed quantity, which is settled into
+    /// `pnl` (and equity) so vesting and haircuts apply to it. Funding
+    /// owed on the position up to `cum_funding` (the instrument's current
+    /// cumulative funding) is settled first, at its old size.
+    ///
+    /// # Returns
+    /// * Realized PnL (1e6 scale)
+    pub fn apply_fill(
+        &mut self,
+        slab_idx: u16,
+        instrument_idx: u16,
+        fill_qty: i64,
+        price: u64,
+        cum_funding: i128,
+    ) -> i128 {
+        use percolator_common::{calculate_funding_payment, PRICE_MULTIPLIER};
+
+        let mut funding = 0i128;
+        for i in 0..self.exposure_count as usize {
+            if self.exposures[i].0 == slab_idx && self.exposures[i].1 == instrument_idx {
+                funding = calculate_funding_payment(self.exposures[i].2, cum_funding, self.funding_checkpoints[i])
+                    / PRICE_MULTIPLIER as i128;
+            }
+        }
+
+        let qty = self.get_exposure(slab_idx, instrument_idx);
+        let entry = self.get_entry_price(slab_idx, instrument_idx);
+        let (realized, new_entry) = position_fill(qty, entry, fill_qty, price);
+
+        self.update_exposure(slab_idx, instrument_idx, qty + fill_qty);
+        for i in 0..self.exposure_count as usize {
+            if self.exposures[i].0 == slab_idx && self.exposures[i].1 == instrument_idx {
+                self.entry_prices[i] = new_entry;
+                self.funding_checkpoints[i] = cum_funding;
+            }
+        }
+
+        let settled = realized - funding;
+        if settled != 0 {
+            use model_safety::math::add_i128;
+            self.pnl = add_i128(self.pnl, settled);
+            self.update_equity(add_i128(self.equity, settled));
+        }
+        realized
+    }
+
+    /// Settle funding on every position
+    ///
+    /// Longs pay and shorts receive as an instrument's cumulative funding
+    /// (`cum_funding`, indexed by instrument) rises. Payments are settled
+    /// into `pnl` (and equity) and each position is checkpointed.
+    ///
+    /// # Returns
+    /// * Net funding paid (1e6 scale, negative = received)
+    pub fn settle_funding(&mut self, cum_funding: &[i128; MAX_INSTRUMENTS]) -> i128 {
+        use model_safety::math::sub_i128;
+        use percolator_common::{calculate_funding_payment, PRICE_MULTIPLIER};
+
+        let mut paid = 0i128;
+        for i in 0..self.exposure_count as usize {
+            let (_, instrument_idx, qty) = self.exposures[i];
+            let Some(&cum) = cum_funding.get(instrument_idx as usize) else {
+                continue;
+            };
+            paid = paid.saturating_add(calculate_funding_payment(qty, cum, self.funding_checkpoints[i]));
+            self.funding_checkpoints[i] = cum;
+        }
+
+        let paid = paid / PRICE_MULTIPLIER as i128;
+        if paid != 0 {
+            self.pnl = sub_i128(self.pnl, paid);
+            self.update_equity(sub_i128(self.equity, paid));
+        }
+        paid
+    }
+
     /// Update margin requirements (using verified math)
     ///
     /// # Safety
@@ -232,6 +356,34 @@ impl Portfolio {
         self.free_collateral = sub_i128(equity, u128_to_i128(self.im));
     }
 
+    /// Unrealized PnL of all positions at `marks` (1e6 scale, indexed by instrument)
+    ///
+    /// Positions on an instrument without a mark are valued at 0; their
+    /// PnL is still exact when the instrument's net position is flat.
+    pub fn unrealized_pnl(&self, marks: &[u64; MAX_INSTRUMENTS]) -> i128 {
+        use percolator_common::{calculate_pnl, PRICE_MULTIPLIER};
+
+        let mut total = 0i128;
+        for i in 0..self.exposure_count as usize {
+            let (_, instrument_idx, qty) = self.exposures[i];
+            let mark = marks.get(instrument_idx as usize).copied().unwrap_or(0);
+            total = total.saturating_add(calculate_pnl(qty, self.entry_prices[i], mark));
+        }
+        total / PRICE_MULTIPLIER as i128
+    }
+
+    /// Mark equity to market
+    ///
+    /// Equity = principal + realized PnL (net of fees and settled funding) +
+    /// unrealized PnL at `marks`, replacing the incrementally tracked value.
+    pub fn mark_to_market(&mut self, marks: &[u64; MAX_INSTRUMENTS], current_ts: u64) {
+        use model_safety::math::add_i128;
+
+        let equity = add_i128(add_i128(self.principal, self.pnl), self.unrealized_pnl(marks));
+        self.update_equity(equity);
+        self.last_mark_ts = current_ts;
+    }
+
     /// Check if sufficient margin
     pub fn has_sufficient_margin(&self) -> bool {
         self.equity >= self.im as i128
@@ -348,6 +500,78 @@ impl Portfolio {
         total_im
     }
 
+    /// Credit deposited collateral to principal and equity
+    pub fn credit_deposit(&mut self, amount: u128) {
+        use model_safety::math::{add_i128, u128_to_i128};
+
+        let amount = u128_to_i128(amount);
+        self.principal = add_i128(self.principal, amount);
+        self.update_equity(add_i128(self.equity, amount));
+    }
+
+    /// Maximum amount withdrawable right now
+    ///
+    /// Capped by free collateral after venue-aware IM and by the cash
+    /// backing the account (principal + vested PnL). Unvested PnL is
+    /// never withdrawable.
+    pub fn withdrawable(&self) -> u128 {
+        use model_safety::math::{add_i128, min_i128, sub_i128, u128_to_i128};
+
+        let free = sub_i128(self.equity, u128_to_i128(self.calculate_total_im()));
+        let cash = add_i128(self.principal, self.vested_pnl.max(0));
+        min_i128(free, cash).max(0) as u128
+    }
+
+    /// Debit a withdrawal, drawing on principal first and then vested PnL
+    ///
+    /// Caller must check `amount <= withdrawable()` first.
+    /// Returns the (principal, PnL) split of the debit.
+    pub fn debit_withdrawal(&mut self, amount: u128) -> (u128, u128) {
+        use model_safety::math::{sub_i128, u128_to_i128};
+
+        let amount = u128_to_i128(amount);
+        let from_principal = amount.min(self.principal.max(0));
+        let from_pnl = amount - from_principal;
+
+        self.principal = sub_i128(self.principal, from_principal);
+        self.pnl = sub_i128(self.pnl, from_pnl);
+        self.vested_pnl = sub_i128(self.vested_pnl, from_pnl);
+        self.update_equity(sub_i128(self.equity, amount));
+
+        (from_principal as u128, from_pnl as u128)
+    }
+
+    /// Return a debited withdrawal that was not paid out (e.g. cancelled)
+    pub fn restore_withdrawal(&mut self, principal_part: u128, pnl_part: u128) {
+        use model_safety::math::{add_i128, u128_to_i128};
+
+        let principal_part = u128_to_i128(principal_part);
+        let pnl_part = u128_to_i128(pnl_part);
+
+        self.principal = add_i128(self.principal, principal_part);
+        self.pnl = add_i128(self.pnl, pnl_part);
+        self.vested_pnl = add_i128(self.vested_pnl, pnl_part);
+        self.update_equity(add_i128(self.equity, add_i128(principal_part, pnl_part)));
+    }
+
+    /// Debit a trading fee from realized PnL and equity
+    pub fn charge_fee(&mut self, fee: u128) {
+        use model_safety::math::{sub_i128, u128_to_i128};
+
+        let fee = u128_to_i128(fee);
+        self.pnl = sub_i128(self.pnl, fee);
+        self.update_equity(sub_i128(self.equity, fee));
+    }
+
+    /// Credit a maker fee to realized PnL and equity
+    pub fn credit_fee(&mut self, fee: u128) {
+        use model_safety::math::{add_i128, u128_to_i128};
+
+        let fee = u128_to_i128(fee);
+        self.pnl = add_i128(self.pnl, fee);
+        self.update_equity(add_i128(self.equity, fee));
+    }
+
     /// Check if sufficient margin using venue-aware calculation
     pub fn has_sufficient_margin_venue_aware(&self) -> bool {
         self.equity >= self.calculate_total_im() as i128
@@ -359,6 +583,40 @@ impl Portfolio {
     }
 }
 
+/// Fill a position of `qty` at `entry`, returning (realized PnL, new entry price)
+///
+/// Quantities and prices are 1e6 scale; PnL is realized on the closed
+/// quantity via `calculate_pnl` and rescaled to 1e6.
+pub fn position_fill(qty: i64, entry: u64, fill_qty: i64, price: u64) -> (i128, u64) {
+    use percolator_common::{calculate_pnl, calculate_vwap, update_vwap, PRICE_MULTIPLIER};
+
+    let new_qty = qty + fill_qty;
+
+    // Opening or adding: volume-weighted entry
+    if qty == 0 || qty.signum() == fill_qty.signum() {
+        let (total_qty, total_notional) = update_vwap(
+            qty.unsigned_abs(),
+            entry as u128 * qty.unsigned_abs() as u128,
+            fill_qty.unsigned_abs(),
+            price,
+        );
+        return (0, calculate_vwap(total_notional, total_qty));
+    }
+
+    // Reducing, closing or flipping: realize PnL on the closed quantity
+    let closed = fill_qty.unsigned_abs().min(qty.unsigned_abs()) as i64 * qty.signum();
+    let realized = calculate_pnl(closed, entry, price) / PRICE_MULTIPLIER as i128;
+
+    let new_entry = if new_qty == 0 {
+        0
+    } else if new_qty.signum() == qty.signum() {
+        entry
+    } else {
+        price
+    };
+    (realized, new_entry)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -703,4 +961,174 @@ mod tests {
         let expected_mm_after_partial = (5_000 * 700) / 1000;
         assert_eq!(expected_mm_after_partial, 3_500);
     }
+
+    #[test]
+    fn test_deposit_credits_principal_and_equity() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        portfolio.credit_deposit(50_000);
+        assert_eq!(portfolio.principal, 50_000);
+        assert_eq!(portfolio.equity, 50_000);
+        assert_eq!(portfolio.free_collateral, 50_000);
+        assert_eq!(portfolio.withdrawable(), 50_000);
+    }
+
+    #[test]
+    fn test_withdrawable_capped_by_im_and_vested_pnl() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_deposit(100_000);
+
+        // IM locks part of the equity
+        portfolio.update_margin(30_000, 15_000);
+        assert_eq!(portfolio.withdrawable(), 70_000);
+
+        // Unvested PnL raises equity but not withdrawable cash
+        portfolio.pnl = 40_000;
+        portfolio.vested_pnl = 10_000;
+        portfolio.update_equity(140_000);
+        assert_eq!(portfolio.withdrawable(), 110_000);
+
+        // Underwater: nothing withdrawable
+        portfolio.update_equity(20_000);
+        assert_eq!(portfolio.withdrawable(), 0);
+    }
+
+    #[test]
+    fn test_debit_withdrawal_uses_principal_then_vested_pnl() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_deposit(10_000);
+        portfolio.pnl = 5_000;
+        portfolio.vested_pnl = 5_000;
+        portfolio.update_equity(15_000);
+
+        assert_eq!(portfolio.debit_withdrawal(12_000), (10_000, 2_000));
+        assert_eq!(portfolio.principal, 0);
+        assert_eq!(portfolio.pnl, 3_000);
+        assert_eq!(portfolio.vested_pnl, 3_000);
+        assert_eq!(portfolio.equity, 3_000);
+
+        // Restoring undoes the debit
+        portfolio.restore_withdrawal(10_000, 2_000);
+        assert_eq!(portfolio.principal, 10_000);
+        assert_eq!(portfolio.pnl, 5_000);
+        assert_eq!(portfolio.vested_pnl, 5_000);
+        assert_eq!(portfolio.equity, 15_000);
+    }
+
+    #[test]
+    fn test_fees_move_pnl_and_equity() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_deposit(10_000);
+
+        portfolio.charge_fee(25);
+        assert_eq!(portfolio.principal, 10_000);
+        assert_eq!(portfolio.pnl, -25);
+        assert_eq!(portfolio.equity, 9_975);
+
+        portfolio.credit_fee(40);
+        assert_eq!(portfolio.pnl, 15);
+        assert_eq!(portfolio.equity, 10_015);
+    }
+
+    #[test]
+    fn test_position_fill_entry_and_realized_pnl() {
+        const S: i64 = 1_000_000;
+        const P: u64 = 1_000_000;
+
+        // Open 1 @ 100, add 3 @ 200: entry 175
+        assert_eq!(position_fill(0, 0, S, 100 * P), (0, 100 * P));
+        assert_eq!(position_fill(S, 100 * P, 3 * S, 200 * P), (0, 175 * P));
+
+        // Reduce long 4 @ 175 by 1 @ 195: +20 realized, entry unchanged
+        assert_eq!(position_fill(4 * S, 175 * P, -S, 195 * P), (20 * S as i128, 175 * P));
+
+        // Close short 2 @ 100 at 110: -20 realized, flat
+        assert_eq!(position_fill(-2 * S, 100 * P, 2 * S, 110 * P), (-20 * S as i128, 0));
+
+        // Flip long 1 @ 100 to short 1 at 90: -10 realized, new entry 90
+        assert_eq!(position_fill(S, 100 * P, -2 * S, 90 * P), (-10 * S as i128, 90 * P));
+    }
+
+    #[test]
+    fn test_apply_fill_settles_realized_pnl() {
+        const S: i64 = 1_000_000;
+        const P: u64 = 1_000_000;
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_deposit(1_000 * P as u128);
+
+        assert_eq!(portfolio.apply_fill(0, 0, 2 * S, 100 * P, 0), 0);
+        assert_eq!(portfolio.apply_fill(1, 0, -S, 50 * P, 0), 0);
+        assert_eq!(portfolio.get_entry_price(0, 0), 100 * P);
+
+        // Closing slab 0 removes it; slab 1's entry must survive the swap-remove
+        assert_eq!(portfolio.apply_fill(0, 0, -2 * S, 130 * P, 0), 60 * S as i128);
+        assert_eq!(portfolio.get_exposure(0, 0), 0);
+        assert_eq!(portfolio.get_entry_price(1, 0), 50 * P);
+        assert_eq!(portfolio.position_notional(1, 0), 50 * P as u128);
+        assert_eq!(portfolio.position_notional(0, 0), 0);
+
+        assert_eq!(portfolio.pnl, 60 * S as i128);
+        assert_eq!(portfolio.equity, 1_060 * S as i128);
+        assert_eq!(portfolio.principal, 1_000 * S as i128);
+    }
+
+    #[test]
+    fn test_mark_to_market_equity() {
+        const S: i64 = 1_000_000;
+        const P: u64 = 1_000_000;
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_deposit(1_000 * P as u128);
+
+        // Long 2 BTC @ 100, short 10 ETH @ 20, 5 of fees paid
+        portfolio.apply_fill(0, 0, 2 * S, 100 * P, 0);
+        portfolio.apply_fill(1, 1, -10 * S, 20 * P, 0);
+        portfolio.charge_fee(5 * P as u128);
+
+        let mut marks = [0u64; MAX_INSTRUMENTS];
+        marks[0] = 110 * P;
+        marks[1] = 25 * P;
+
+        // +20 on BTC, -50 on ETH
+        assert_eq!(portfolio.unrealized_pnl(&marks), -30 * S as i128);
+        portfolio.mark_to_market(&marks, 77);
+        assert_eq!(portfolio.equity, (1_000 - 5 - 30) * S as i128);
+        assert_eq!(portfolio.last_mark_ts, 77);
+
+        // A hedged instrument's PnL is locked in and needs no mark
+        let mut hedged = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        hedged.apply_fill(0, 0, 5 * S, 40 * P, 0);
+        hedged.apply_fill(1, 0, -5 * S, 50 * P, 0);
+        assert_eq!(hedged.unrealized_pnl(&[0; MAX_INSTRUMENTS]), 50 * S as i128);
+        assert_eq!(hedged.unrealized_pnl(&marks), 50 * S as i128);
+    }
+
+    #[test]
+    fn test_funding_settlement() {
+        const S: i64 = 1_000_000;
+        const P: u64 = 1_000_000;
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_deposit(1_000 * P as u128);
+
+        // Long 2 BTC opened at cumulative funding 0.5, short 4 ETH at 0
+        portfolio.apply_fill(0, 0, 2 * S, 100 * P, 500_000);
+        portfolio.apply_fill(1, 1, -4 * S, 20 * P, 0);
+
+        // BTC funding rises by 0.75 per unit: the long pays 1.5;
+        // ETH funding falls by 0.1: the short pays 0.4
+        let mut cum = [0i128; MAX_INSTRUMENTS];
+        cum[0] = 1_250_000;
+        cum[1] = -100_000;
+        assert_eq!(portfolio.settle_funding(&cum), 1_900_000);
+        assert_eq!(portfolio.pnl, -1_900_000);
+        assert_eq!(portfolio.equity, 1_000 * S as i128 - 1_900_000);
+
+        // Checkpointed: nothing more to pay
+        assert_eq!(portfolio.settle_funding(&cum), 0);
+
+        // A fill settles funding on the position at its old size first
+        cum[0] = 1_750_000;
+        portfolio.apply_fill(0, 0, -S, 100 * P, cum[0]);
+        assert_eq!(portfolio.pnl, -2_900_000);
+        assert_eq!(portfolio.settle_funding(&cum), 0);
+    }
 }
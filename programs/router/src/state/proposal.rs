@@ -0,0 +1,165 @@
+//! Time-locked governance proposals
+//!
+//! Parameter and registry changes are queued with an ETA of
+//! `now + timelock_secs`, can be executed by anyone between the ETA and
+//! the end of the grace period, and can be cancelled by governance until
+//! then. Emergency actions (pause flags, slab deactivation) stay instant.
+
+use percolator_common::PercolatorError;
+use pinocchio::msg;
+
+/// Maximum concurrently queued proposals
+pub const MAX_PROPOSALS: usize = 8;
+
+/// Maximum encoded proposal payload (largest: slab_id + slab params)
+pub const MAX_PROPOSAL_PAYLOAD: usize = 200;
+
+/// Default timelock (24 hours)
+pub const DEFAULT_TIMELOCK_SECS: u64 = 86_400;
+
+/// Upper bound on the timelock (30 days) so governance cannot brick itself
+pub const MAX_TIMELOCK_SECS: u64 = 30 * 86_400;
+
+/// Window after the ETA during which a proposal can still execute (14 days)
+pub const PROPOSAL_GRACE_SECS: u64 = 14 * 86_400;
+
+/// A queued governance proposal
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Proposal {
+    /// Proposal ID (monotonic)
+    pub id: u64,
+    /// Earliest execution time (unix seconds)
+    pub eta: u64,
+    /// Action kind (the queuing instruction's discriminator)
+    pub kind: u8,
+    /// Slot in use
+    pub active: bool,
+    /// Payload length
+    pub payload_len: u16,
+    /// Padding
+    pub _padding: [u8; 4],
+    /// Encoded action (the queuing instruction's data)
+    pub payload: [u8; MAX_PROPOSAL_PAYLOAD],
+}
+
+impl Proposal {
+    const EMPTY: Proposal = Proposal {
+        id: 0,
+        eta: 0,
+        kind: 0,
+        active: false,
+        payload_len: 0,
+        _padding: [0; 4],
+        payload: [0; MAX_PROPOSAL_PAYLOAD],
+    };
+
+    /// Encoded action bytes
+    pub fn payload(&self) -> &[u8] {
+        &self.payload[..self.payload_len as usize]
+    }
+}
+
+/// Proposal queue stored in the registry
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Proposals {
+    /// Delay between queuing and execution (seconds)
+    pub timelock_secs: u64,
+    /// Next proposal ID
+    pub next_id: u64,
+    /// Proposal slots
+    pub entries: [Proposal; MAX_PROPOSALS],
+}
+
+impl Proposals {
+    /// Empty queue with the given timelock
+    pub fn new(timelock_secs: u64) -> Self {
+        Self {
+            timelock_secs,
+            next_id: 0,
+            entries: [Proposal::EMPTY; MAX_PROPOSALS],
+        }
+    }
+
+    /// Queue an action, returning (id, eta)
+    pub fn queue(&mut self, kind: u8, payload: &[u8], now: u64) -> Result<(u64, u64), PercolatorError> {
+        if payload.len() > MAX_PROPOSAL_PAYLOAD {
+            msg!("Error: Proposal payload too large");
+            return Err(PercolatorError::InvalidInstruction);
+        }
+        let slot = self.entries.iter_mut().find(|p| !p.active).ok_or_else(|| {
+            msg!("Error: Proposal queue is full");
+            PercolatorError::PoolFull
+        })?;
+
+        let id = self.next_id;
+        let eta = now.saturating_add(self.timelock_secs);
+        *slot = Proposal::EMPTY;
+        slot.id = id;
+        slot.eta = eta;
+        slot.kind = kind;
+        slot.active = true;
+        slot.payload_len = payload.len() as u16;
+        slot.payload[..payload.len()].copy_from_slice(payload);
+
+        self.next_id = id.saturating_add(1);
+        Ok((id, eta))
+    }
+
+    /// Find an active proposal by ID
+    pub fn find(&self, id: u64) -> Option<&Proposal> {
+        self.entries.iter().find(|p| p.active && p.id == id)
+    }
+
+    /// Remove an active proposal by ID, returning it
+    pub fn take(&mut self, id: u64) -> Option<Proposal> {
+        let slot = self.entries.iter_mut().find(|p| p.active && p.id == id)?;
+        let proposal = *slot;
+        *slot = Proposal::EMPTY;
+        Some(proposal)
+    }
+
+    /// Check whether a proposal may execute at `now`
+    pub fn is_executable(proposal: &Proposal, now: u64) -> bool {
+        now >= proposal.eta && now <= proposal.eta.saturating_add(PROPOSAL_GRACE_SECS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_take_and_timelock() {
+        let mut proposals = Proposals::new(100);
+
+        let (id, eta) = proposals.queue(13, &[1, 2, 3], 1_000).unwrap();
+        assert_eq!((id, eta), (0, 1_100));
+        assert_eq!(proposals.find(id).unwrap().payload(), &[1, 2, 3]);
+
+        let proposal = *proposals.find(id).unwrap();
+        assert!(!Proposals::is_executable(&proposal, 1_099));
+        assert!(Proposals::is_executable(&proposal, 1_100));
+        assert!(!Proposals::is_executable(&proposal, 1_100 + PROPOSAL_GRACE_SECS + 1));
+
+        assert_eq!(proposals.take(id).unwrap().kind, 13);
+        assert!(proposals.take(id).is_none());
+        assert_eq!(proposals.queue(13, &[], 0).unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_queue_full_and_oversized() {
+        let mut proposals = Proposals::new(0);
+        for _ in 0..MAX_PROPOSALS {
+            proposals.queue(12, &[], 0).unwrap();
+        }
+        assert_eq!(proposals.queue(12, &[], 0), Err(PercolatorError::PoolFull));
+
+        let mut proposals = Proposals::new(0);
+        assert_eq!(
+            proposals.queue(12, &[0; MAX_PROPOSAL_PAYLOAD + 1], 0),
+            Err(PercolatorError::InvalidInstruction)
+        );
+    }
+}
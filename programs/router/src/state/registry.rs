@@ -1,7 +1,26 @@
 //! Slab registry for governance and validation
 
-use pinocchio::pubkey::Pubkey;
-use percolator_common::MAX_SLABS;
+use pinocchio::{msg, pubkey::Pubkey};
+use percolator_common::{PercolatorError, MAX_INSTRUMENTS, MAX_SLABS};
+
+/// Pause flags (bitmask in `SlabRegistry::pause_flags`, governance controlled)
+///
+/// - Trading: ExecuteCrossSlab rejected
+/// - New positions: trades may not open or flip a position
+/// - Withdrawals: Withdraw, RequestWithdrawal and ProcessWithdrawals rejected
+/// - Liquidations: LiquidateUser rejected
+/// - Reduce-only: trades may only shrink existing positions
+///
+/// Deposits, LP burns/cancels and withdrawal cancels are always allowed
+/// since they only reduce risk.
+pub const PAUSE_TRADING: u8 = 1 << 0;
+pub const PAUSE_NEW_POSITIONS: u8 = 1 << 1;
+pub const PAUSE_WITHDRAWALS: u8 = 1 << 2;
+pub const PAUSE_LIQUIDATIONS: u8 = 1 << 3;
+pub const REDUCE_ONLY: u8 = 1 << 4;
+/// All defined pause flags
+pub const PAUSE_ALL: u8 =
+    PAUSE_TRADING | PAUSE_NEW_POSITIONS | PAUSE_WITHDRAWALS | PAUSE_LIQUIDATIONS | REDUCE_ONLY;
 
 /// Slab registration entry
 #[repr(C)]
@@ -21,16 +40,117 @@ pub struct SlabEntry {
     pub maker_fee_cap: u64,
     /// Maximum taker fee (basis points)
     pub taker_fee_cap: u64,
-    /// Latency SLA (milliseconds)
+    /// Latency SLA: maximum age of the slab's quotes (milliseconds, 0 = none)
     pub latency_sla_ms: u64,
-    /// Maximum exposure per user (per instrument)
+    /// Maximum router-routed notional through this slab (E_max, 0 = uncapped)
     pub max_exposure: u128,
+    /// Open taker notional routed through this slab, at entry prices (1e6 scale)
+    pub routed_notional: u128,
     /// Registered timestamp
     pub registered_ts: u64,
+    /// Slot at which the router last saw the QuoteCache change (0 = never observed)
+    pub last_quote_slot: u64,
+    /// QuoteCache seqno when last observed
+    pub last_quote_seqno: u32,
+    /// Index of the slab's instrument in `SlabRegistry::instruments`
+    pub instrument_idx: u16,
     /// Active flag
     pub active: bool,
     /// Padding
-    pub _padding: [u8; 7],
+    pub _padding: [u8; 9],
+}
+
+/// Approximate slot duration used to age quotes against the latency SLA
+pub const SLOT_MS: u64 = 400;
+
+/// A matcher whose quotes are older than this many SLAs may be quarantined
+pub const QUARANTINE_SLA_MULTIPLE: u64 = 10;
+
+impl SlabEntry {
+    /// Record an observation of the slab's QuoteCache seqno
+    ///
+    /// A changed seqno, or the first observation, stamps the current slot.
+    pub fn observe_quotes(&mut self, seqno: u32, current_slot: u64) {
+        if self.last_quote_slot == 0 || seqno != self.last_quote_seqno {
+            self.last_quote_seqno = seqno;
+            self.last_quote_slot = current_slot;
+        }
+    }
+
+    /// Record a QuoteCache change made by the router's own fill, which
+    /// says nothing about the matcher's liveness
+    pub fn acknowledge_quotes(&mut self, seqno: u32) {
+        self.last_quote_seqno = seqno;
+    }
+
+    /// Age of the slab's quotes (milliseconds)
+    pub fn quote_age_ms(&self, current_slot: u64) -> u64 {
+        current_slot.saturating_sub(self.last_quote_slot).saturating_mul(SLOT_MS)
+    }
+
+    /// Quotes older than the latency SLA (never with no SLA)
+    pub fn quotes_stale(&self, current_slot: u64) -> bool {
+        self.latency_sla_ms != 0 && self.quote_age_ms(current_slot) > self.latency_sla_ms
+    }
+
+    /// Quotes stale long enough to quarantine the matcher
+    pub fn quotes_chronically_stale(&self, current_slot: u64) -> bool {
+        self.latency_sla_ms != 0
+            && self.quote_age_ms(current_slot)
+                > self.latency_sla_ms.saturating_mul(QUARANTINE_SLA_MULTIPLE)
+    }
+
+    /// Replace one position's contribution to the routed notional
+    ///
+    /// Fails if the change grows the total beyond `max_exposure`; changes
+    /// that shrink a position are always allowed.
+    pub fn route_notional(&mut self, old_notional: u128, new_notional: u128) -> Result<(), PercolatorError> {
+        let routed = self
+            .routed_notional
+            .saturating_sub(old_notional)
+            .saturating_add(new_notional);
+        if new_notional > old_notional && self.max_exposure != 0 && routed > self.max_exposure {
+            msg!("Error: Slab exposure cap exceeded");
+            return Err(PercolatorError::ExposureCapExceeded);
+        }
+        self.routed_notional = routed;
+        Ok(())
+    }
+}
+
+/// Instrument traded by one or more registered slabs
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstrumentEntry {
+    /// Instrument ID (as in `SlabHeader::instrument`)
+    pub id: Pubkey,
+    /// Oracle price account pinned for the instrument (set by governance)
+    pub oracle: Pubkey,
+    /// Cumulative funding per unit of base (1e6 scale, positive = longs have paid)
+    pub cum_funding: i128,
+    /// Contract size shared by every slab trading the instrument (1e6 scale)
+    pub contract_size: u64,
+    /// Funding rate applied in the last period (basis points)
+    pub funding_rate: i64,
+    /// Last funding accrual timestamp (0 = not started)
+    pub last_funding_ts: u64,
+    /// Padding
+    pub _padding: [u8; 8],
+}
+
+impl InstrumentEntry {
+    /// Empty instrument slot
+    pub const fn empty() -> Self {
+        Self {
+            id: [0; 32],
+            oracle: [0; 32],
+            cum_funding: 0,
+            contract_size: 0,
+            funding_rate: 0,
+            last_funding_ts: 0,
+            _padding: [0; 8],
+        }
+    }
 }
 
 /// Slab registry account
@@ -45,8 +165,10 @@ pub struct SlabRegistry {
     pub slab_count: u16,
     /// Bump seed
     pub bump: u8,
+    /// Pause flags (PAUSE_* / REDUCE_ONLY bitmask)
+    pub pause_flags: u8,
     /// Padding
-    pub _padding: [u8; 5],
+    pub _padding: [u8; 4],
 
     // Liquidation parameters (global)
     /// Initial margin ratio (basis points, e.g., 500 = 5%)
@@ -59,7 +181,8 @@ pub struct SlabRegistry {
     pub preliq_buffer: i128,
     /// Pre-liquidation tighter band (basis points, e.g., 100 = 1%)
     pub preliq_band_bps: u64,
-    /// Maximum size router can execute per slab in one tx
+    /// Maximum quantity (1e6 scale, sum of |qty| over all splits to the
+    /// slab) the router executes per slab in one tx
     pub router_cap_per_slab: u64,
     /// Minimum equity required to provide quotes
     pub min_equity_to_quote: i128,
@@ -80,6 +203,34 @@ pub struct SlabRegistry {
     /// Global haircut state (runtime tracking)
     pub global_haircut: crate::state::pnl_vesting::GlobalHaircut,
 
+    // Withdrawal rate limits
+    /// Exit bucket caps, thresholds and emergency mode (configurable by governance)
+    pub exit_config: crate::state::exit_bucket::ExitConfig,
+    /// Global exit bucket (runtime tracking)
+    pub global_exit_bucket: crate::state::exit_bucket::GlobalExitBucket,
+    /// Total amount waiting in withdrawal queues
+    pub queued_withdrawal_total: u128,
+    /// Next withdrawal queue ticket (router-wide request order)
+    pub withdrawal_ticket: u64,
+    /// Padding for alignment
+    pub _padding3: [u8; 8],
+
+    // Governance
+    /// Proposed new governance (must accept), default = none
+    pub pending_governance: Pubkey,
+    /// Time-locked parameter and registry change proposals
+    pub proposals: crate::state::proposal::Proposals,
+
+    // Instruments
+    /// Number of registered instruments
+    pub instrument_count: u16,
+    /// Padding for alignment
+    pub _padding4: [u8; 6],
+    /// Funding crank parameters
+    pub funding_params: crate::state::funding::FundingParams,
+    /// Registered instruments, indexed by instrument_idx
+    pub instruments: [InstrumentEntry; MAX_INSTRUMENTS],
+
     /// Registered slabs
     pub slabs: [SlabEntry; MAX_SLABS],
 }
@@ -96,7 +247,8 @@ impl SlabRegistry {
         self.governance = governance;
         self.slab_count = 0;
         self.bump = bump;
-        self._padding = [0; 5];
+        self.pause_flags = 0;
+        self._padding = [0; 4];
 
         // Initialize liquidation parameters with defaults
         self.imr = 500;  // 5% initial margin
@@ -117,6 +269,37 @@ impl SlabRegistry {
         self.pnl_vesting_params = crate::state::pnl_vesting::PnlVestingParams::default();
         self.global_haircut = crate::state::pnl_vesting::GlobalHaircut::default();
 
+        // Initialize withdrawal rate limits with defaults
+        self.exit_config = crate::state::exit_bucket::ExitConfig::default();
+        self.global_exit_bucket = crate::state::exit_bucket::GlobalExitBucket::default();
+        self.queued_withdrawal_total = 0;
+        self.withdrawal_ticket = 0;
+        self._padding3 = [0; 8];
+
+        // Initialize governance with the default timelock
+        self.pending_governance = Pubkey::default();
+        self.proposals.timelock_secs = crate::state::proposal::DEFAULT_TIMELOCK_SECS;
+        self.proposals.next_id = 0;
+        unsafe {
+            core::ptr::write_bytes(
+                self.proposals.entries.as_mut_ptr(),
+                0,
+                crate::state::proposal::MAX_PROPOSALS,
+            );
+        }
+
+        // No instruments until the first slab is registered
+        self.instrument_count = 0;
+        self._padding4 = [0; 6];
+        self.funding_params = crate::state::funding::FundingParams::default();
+        unsafe {
+            core::ptr::write_bytes(
+                self.instruments.as_mut_ptr(),
+                0,
+                MAX_INSTRUMENTS,
+            );
+        }
+
         // Zero out the slabs array using ptr::write_bytes (efficient and stack-safe)
         unsafe {
             core::ptr::write_bytes(
@@ -136,7 +319,8 @@ impl SlabRegistry {
             governance,
             slab_count: 0,
             bump,
-            _padding: [0; 5],
+            pause_flags: 0,
+            _padding: [0; 4],
             imr: 500,
             mmr: 250,
             liq_band_bps: 200,
@@ -150,6 +334,17 @@ impl SlabRegistry {
             insurance_state: crate::state::insurance::InsuranceState::default(),
             pnl_vesting_params: crate::state::pnl_vesting::PnlVestingParams::default(),
             global_haircut: crate::state::pnl_vesting::GlobalHaircut::default(),
+            exit_config: crate::state::exit_bucket::ExitConfig::default(),
+            global_exit_bucket: crate::state::exit_bucket::GlobalExitBucket::default(),
+            queued_withdrawal_total: 0,
+            withdrawal_ticket: 0,
+            _padding3: [0; 8],
+            pending_governance: Pubkey::default(),
+            proposals: crate::state::proposal::Proposals::new(crate::state::proposal::DEFAULT_TIMELOCK_SECS),
+            instrument_count: 0,
+            _padding4: [0; 6],
+            funding_params: crate::state::funding::FundingParams::default(),
+            instruments: [InstrumentEntry::empty(); MAX_INSTRUMENTS],
             slabs: [SlabEntry {
                 slab_id: Pubkey::default(),
                 version_hash: [0; 32],
@@ -160,25 +355,26 @@ impl SlabRegistry {
                 taker_fee_cap: 0,
                 latency_sla_ms: 0,
                 max_exposure: 0,
+                routed_notional: 0,
                 registered_ts: 0,
+                last_quote_slot: 0,
+                last_quote_seqno: 0,
+                instrument_idx: 0,
                 active: false,
-                _padding: [0; 7],
+                _padding: [0; 9],
             }; MAX_SLABS],
         }
     }
 
     /// Register a new slab
+    ///
+    /// `params.oracle` is pinned on the instrument, not the slab entry
+    /// (see `add_instrument`).
     pub fn register_slab(
         &mut self,
         slab_id: Pubkey,
-        version_hash: [u8; 32],
-        oracle_id: Pubkey,
-        imr: u64,
-        mmr: u64,
-        maker_fee_cap: u64,
-        taker_fee_cap: u64,
-        latency_sla_ms: u64,
-        max_exposure: u128,
+        instrument_idx: u16,
+        params: &crate::instructions::SlabParams,
         current_ts: u64,
     ) -> Result<u16, ()> {
         if (self.slab_count as usize) >= MAX_SLABS {
@@ -188,23 +384,93 @@ impl SlabRegistry {
         let idx = self.slab_count;
         self.slabs[idx as usize] = SlabEntry {
             slab_id,
-            version_hash,
-            oracle_id,
-            imr,
-            mmr,
-            maker_fee_cap,
-            taker_fee_cap,
-            latency_sla_ms,
-            max_exposure,
+            version_hash: params.version_hash,
+            oracle_id: params.oracle_id,
+            imr: params.imr,
+            mmr: params.mmr,
+            maker_fee_cap: params.maker_fee_cap,
+            taker_fee_cap: params.taker_fee_cap,
+            latency_sla_ms: params.latency_sla_ms,
+            max_exposure: params.max_exposure,
+            routed_notional: 0,
             registered_ts: current_ts,
+            last_quote_slot: 0,
+            last_quote_seqno: 0,
+            instrument_idx,
             active: true,
-            _padding: [0; 7],
+            _padding: [0; 9],
         };
         self.slab_count += 1;
 
         Ok(idx)
     }
 
+    /// Find an instrument's index
+    pub fn find_instrument(&self, instrument: &Pubkey) -> Option<u16> {
+        (0..self.instrument_count as usize)
+            .find(|&i| &self.instruments[i].id == instrument)
+            .map(|i| i as u16)
+    }
+
+    /// Find the instrument an oracle account is pinned to
+    pub fn find_instrument_by_oracle(&self, oracle: &Pubkey) -> Option<u16> {
+        (0..self.instrument_count as usize)
+            .find(|&i| &self.instruments[i].oracle == oracle)
+            .map(|i| i as u16)
+    }
+
+    /// Cumulative funding of every instrument, indexed by instrument_idx
+    pub fn cum_funding(&self) -> [i128; MAX_INSTRUMENTS] {
+        let mut cum = [0i128; MAX_INSTRUMENTS];
+        for (c, instrument) in cum.iter_mut().zip(self.instruments.iter()) {
+            *c = instrument.cum_funding;
+        }
+        cum
+    }
+
+    /// Find an instrument's index, adding it if new
+    ///
+    /// Fails if the table is full or the instrument is already registered
+    /// with a different contract size (positions would not net) or oracle,
+    /// or if the oracle is already pinned to another instrument.
+    pub fn add_instrument(
+        &mut self,
+        instrument: &Pubkey,
+        contract_size: u64,
+        oracle: &Pubkey,
+    ) -> Result<u16, PercolatorError> {
+        if let Some(idx) = self.find_instrument(instrument) {
+            let entry = &self.instruments[idx as usize];
+            if entry.contract_size != contract_size {
+                msg!("Error: Contract size differs from the instrument's other slabs");
+                return Err(PercolatorError::InvalidInstrument);
+            }
+            if &entry.oracle != oracle {
+                msg!("Error: Oracle differs from the instrument's other slabs");
+                return Err(PercolatorError::InvalidInstrument);
+            }
+            return Ok(idx);
+        }
+        if self.find_instrument_by_oracle(oracle).is_some() {
+            msg!("Error: Oracle already pinned to another instrument");
+            return Err(PercolatorError::InvalidInstrument);
+        }
+        if (self.instrument_count as usize) >= MAX_INSTRUMENTS {
+            msg!("Error: Instrument registry is full");
+            return Err(PercolatorError::PoolFull);
+        }
+
+        let idx = self.instrument_count;
+        self.instruments[idx as usize] = InstrumentEntry {
+            id: *instrument,
+            oracle: *oracle,
+            contract_size,
+            ..InstrumentEntry::empty()
+        };
+        self.instrument_count += 1;
+        Ok(idx)
+    }
+
     /// Find slab by ID
     pub fn find_slab(&self, slab_id: &Pubkey) -> Option<(u16, &SlabEntry)> {
         for i in 0..self.slab_count as usize {
@@ -234,6 +500,62 @@ impl SlabRegistry {
         }
     }
 
+    /// Reactivate a deactivated slab in place, returning its index
+    ///
+    /// Keeps the slab's index (positions are keyed by it) and restarts
+    /// liveness tracking. Fails if the slab is active or unknown.
+    pub fn reactivate_slab(&mut self, slab_id: &Pubkey) -> Result<u16, PercolatorError> {
+        let unavailable = || {
+            msg!("Error: Slab not registered or already active");
+            PercolatorError::SlabNotRegistered
+        };
+        if self.find_slab(slab_id).is_some() {
+            return Err(unavailable());
+        }
+        let idx = (0..self.slab_count as usize)
+            .rev()
+            .find(|&i| &self.slabs[i].slab_id == slab_id)
+            .ok_or_else(unavailable)?;
+
+        let entry = &mut self.slabs[idx];
+        entry.active = true;
+        entry.last_quote_slot = 0;
+        Ok(idx as u16)
+    }
+
+    /// Replace an active slab's parameters, returning its index
+    ///
+    /// The oracle account is pinned per instrument, so it changes for every
+    /// slab trading the instrument. Fails if it is pinned to another one.
+    pub fn update_slab(
+        &mut self,
+        slab_id: &Pubkey,
+        params: &crate::instructions::SlabParams,
+    ) -> Result<u16, PercolatorError> {
+        let (idx, slab) = self.find_slab(slab_id).ok_or_else(|| {
+            msg!("Error: Slab not registered");
+            PercolatorError::SlabNotRegistered
+        })?;
+        if let Some(pinned) = self.find_instrument_by_oracle(&params.oracle) {
+            if pinned != slab.instrument_idx {
+                msg!("Error: Oracle already pinned to another instrument");
+                return Err(PercolatorError::InvalidInstrument);
+            }
+        }
+        let entry = &mut self.slabs[idx as usize];
+        entry.version_hash = params.version_hash;
+        entry.oracle_id = params.oracle_id;
+        entry.imr = params.imr;
+        entry.mmr = params.mmr;
+        entry.maker_fee_cap = params.maker_fee_cap;
+        entry.taker_fee_cap = params.taker_fee_cap;
+        entry.latency_sla_ms = params.latency_sla_ms;
+        entry.max_exposure = params.max_exposure;
+        let instrument_idx = entry.instrument_idx as usize;
+        self.instruments[instrument_idx].oracle = params.oracle;
+        Ok(idx)
+    }
+
     /// Update slab risk params
     pub fn update_risk_params(&mut self, slab_id: &Pubkey, imr: u64, mmr: u64) -> Result<(), ()> {
         if let Some((idx, _)) = self.find_slab(slab_id) {
@@ -264,11 +586,60 @@ impl SlabRegistry {
         self.router_cap_per_slab = router_cap_per_slab;
         self.oracle_tolerance_bps = oracle_tolerance_bps;
     }
+
+    /// `router_cap_per_slab` as a signed quantity for split sizing
+    pub fn router_cap_qty(&self) -> i64 {
+        self.router_cap_per_slab.min(i64::MAX as u64) as i64
+    }
+
+    /// Check if any of the given pause flags is set
+    pub fn is_paused(&self, flags: u8) -> bool {
+        self.pause_flags & flags != 0
+    }
+
+    /// Check whether a position change is allowed under the pause flags
+    ///
+    /// Reduce-only permits only moves toward zero without crossing it;
+    /// the new-positions pause additionally permits growing a position
+    /// that is already open on the same side.
+    pub fn allows_position_change(&self, current: i64, new: i64) -> bool {
+        let same_side = (current > 0 && new >= 0) || (current < 0 && new <= 0);
+
+        if self.is_paused(REDUCE_ONLY) && !reduces_position(current, new) {
+            return false;
+        }
+        if self.is_paused(PAUSE_NEW_POSITIONS) && new != 0 && !same_side {
+            return false;
+        }
+        true
+    }
+}
+
+/// True if a position change moves toward zero without crossing it
+/// (or leaves the position unchanged)
+pub fn reduces_position(current: i64, new: i64) -> bool {
+    let same_side = (current > 0 && new >= 0) || (current < 0 && new <= 0);
+    new == current || (same_side && new.unsigned_abs() <= current.unsigned_abs())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instructions::SlabParams;
+
+    fn slab_params(latency_sla_ms: u64, max_exposure: u128) -> SlabParams {
+        SlabParams {
+            version_hash: [0; 32],
+            oracle_id: Pubkey::default(),
+            oracle: Pubkey::default(),
+            imr: 500,          // 5% IMR
+            mmr: 250,          // 2.5% MMR
+            maker_fee_cap: 10, // 0.1% maker fee cap
+            taker_fee_cap: 20, // 0.2% taker fee cap
+            latency_sla_ms,
+            max_exposure,
+        }
+    }
 
     #[test]
     fn test_registry_operations() {
@@ -280,14 +651,8 @@ mod tests {
         let idx = registry
             .register_slab(
                 slab_id,
-                version_hash,
-                Pubkey::default(),
-                500,  // 5% IMR
-                250,  // 2.5% MMR
-                10,   // 0.1% maker fee cap
-                20,   // 0.2% taker fee cap
-                1000, // 1s latency SLA
-                1_000_000,
+                0,
+                &SlabParams { version_hash, ..slab_params(1000, 1_000_000) }, // 1s latency SLA
                 12345,
             )
             .unwrap();
@@ -305,4 +670,135 @@ mod tests {
         registry.deactivate_slab(&slab_id).unwrap();
         assert!(registry.find_slab(&slab_id).is_none());
     }
+
+    #[test]
+    fn test_instrument_registry() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        let btc = Pubkey::from([1; 32]);
+        let eth = Pubkey::from([2; 32]);
+
+        let btc_oracle = Pubkey::from([11; 32]);
+        let eth_oracle = Pubkey::from([12; 32]);
+
+        assert_eq!(registry.find_instrument(&btc), None);
+        assert_eq!(registry.add_instrument(&btc, 1_000_000, &btc_oracle), Ok(0));
+        assert_eq!(registry.add_instrument(&eth, 1_000_000, &eth_oracle), Ok(1));
+        // Slabs trading the same instrument share its index
+        assert_eq!(registry.add_instrument(&btc, 1_000_000, &btc_oracle), Ok(0));
+        assert_eq!(registry.instrument_count, 2);
+        assert_eq!(registry.find_instrument(&eth), Some(1));
+        assert_eq!(registry.find_instrument_by_oracle(&eth_oracle), Some(1));
+        assert_eq!(registry.find_instrument_by_oracle(&Pubkey::from([13; 32])), None);
+        // ...but only with the same contract size and oracle
+        assert_eq!(registry.add_instrument(&btc, 100_000, &btc_oracle), Err(PercolatorError::InvalidInstrument));
+        assert_eq!(registry.add_instrument(&btc, 1_000_000, &eth_oracle), Err(PercolatorError::InvalidInstrument));
+
+        // ...and an oracle prices a single instrument
+        assert_eq!(
+            registry.add_instrument(&Pubkey::from([3; 32]), 1_000_000, &btc_oracle),
+            Err(PercolatorError::InvalidInstrument)
+        );
+
+        for i in 2..MAX_INSTRUMENTS {
+            let id = Pubkey::from([i as u8 + 1; 32]);
+            registry.add_instrument(&id, 1_000_000, &Pubkey::from([i as u8 + 101; 32])).unwrap();
+        }
+        assert_eq!(
+            registry.add_instrument(&Pubkey::from([0xff; 32]), 1_000_000, &[0xfe; 32]),
+            Err(PercolatorError::PoolFull)
+        );
+    }
+
+    #[test]
+    fn test_pause_flags_position_changes() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+
+        // No flags: anything goes
+        assert!(registry.allows_position_change(0, 100));
+        assert!(registry.allows_position_change(100, -50));
+
+        // New positions paused: grow or shrink existing, no open/flip
+        registry.pause_flags = PAUSE_NEW_POSITIONS;
+        assert!(!registry.allows_position_change(0, 100));
+        assert!(registry.allows_position_change(100, 150));
+        assert!(registry.allows_position_change(100, 0));
+        assert!(!registry.allows_position_change(100, -50));
+
+        // Reduce-only: shrink toward zero only
+        registry.pause_flags = REDUCE_ONLY;
+        assert!(registry.allows_position_change(-100, -40));
+        assert!(registry.allows_position_change(-100, 0));
+        assert!(registry.allows_position_change(0, 0));
+        assert!(!registry.allows_position_change(-100, -150));
+        assert!(!registry.allows_position_change(-100, 50));
+        assert!(!registry.allows_position_change(0, 10));
+
+        assert!(registry.is_paused(REDUCE_ONLY));
+        assert!(!registry.is_paused(PAUSE_TRADING | PAUSE_WITHDRAWALS));
+    }
+
+    #[test]
+    fn test_route_notional_cap() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        registry
+            .register_slab(Pubkey::from([1; 32]), 0, &slab_params(100, 1_000), 0)
+            .unwrap();
+        let entry = &mut registry.slabs[0];
+
+        // Two users open 600 and 400: at the cap
+        assert_eq!(entry.route_notional(0, 600), Ok(()));
+        assert_eq!(entry.route_notional(0, 400), Ok(()));
+        assert_eq!(entry.routed_notional, 1_000);
+
+        // Growing past the cap is rejected and leaves the total unchanged
+        assert_eq!(entry.route_notional(400, 450), Err(PercolatorError::ExposureCapExceeded));
+        assert_eq!(entry.routed_notional, 1_000);
+
+        // Reducing always works and frees room
+        assert_eq!(entry.route_notional(600, 100), Ok(()));
+        assert_eq!(entry.route_notional(400, 900), Ok(()));
+        assert_eq!(entry.routed_notional, 1_000);
+
+        // No cap
+        entry.max_exposure = 0;
+        assert_eq!(entry.route_notional(0, u128::MAX), Ok(()));
+    }
+
+    #[test]
+    fn test_quote_liveness_and_reactivation() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        let slab_id = Pubkey::from([1; 32]);
+        registry
+            .register_slab(slab_id, 0, &slab_params(1_000, 0), 0)
+            .unwrap();
+        let entry = &mut registry.slabs[0];
+
+        // First observation starts the clock
+        entry.observe_quotes(5, 100);
+        assert!(!entry.quotes_stale(102)); // 800ms
+        assert!(entry.quotes_stale(103)); // 1.2s > 1s SLA
+
+        // Fills by the router do not count as matcher activity
+        entry.acknowledge_quotes(6);
+        entry.observe_quotes(6, 103);
+        assert!(entry.quotes_stale(103));
+
+        // A matcher update refreshes the quotes
+        entry.observe_quotes(7, 103);
+        assert!(!entry.quotes_stale(103));
+        assert!(!entry.quotes_chronically_stale(103 + 25)); // 10s
+        assert!(entry.quotes_chronically_stale(103 + 26));
+
+        // No SLA: never stale
+        entry.latency_sla_ms = 0;
+        assert!(!entry.quotes_chronically_stale(u64::MAX));
+
+        // Quarantined slabs come back in place with fresh liveness tracking
+        assert_eq!(registry.reactivate_slab(&slab_id), Err(PercolatorError::SlabNotRegistered));
+        registry.deactivate_slab(&slab_id).unwrap();
+        assert_eq!(registry.reactivate_slab(&slab_id), Ok(0));
+        assert_eq!(registry.slabs[0].last_quote_slot, 0);
+        assert_eq!(registry.find_slab(&slab_id).map(|(idx, _)| idx), Some(0));
+        assert_eq!(registry.reactivate_slab(&Pubkey::from([2; 32])), Err(PercolatorError::SlabNotRegistered));
+    }
 }
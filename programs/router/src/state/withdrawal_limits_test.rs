@@ -13,157 +13,12 @@
 use super::*;
 
 // ═══════════════════════════════════════════════════════════════════════════
-// DATA STRUCTURES FOR EXIT BUCKETS AND WITHDRAWAL LIMITS
+// TEST FIXTURES (exit bucket types live in exit_bucket.rs)
 // ═══════════════════════════════════════════════════════════════════════════
 
 /// Money scale (1e6 for $1.00)
 const SCALE: i128 = 1_000_000;
 
-/// Exit bucket parameters (governance configurable)
-#[derive(Debug, Clone, Copy)]
-pub struct ExitBucketParams {
-    /// Per-user percentage of equity per hour (basis points)
-    /// e.g., 2000 = 20% of equity per hour
-    pub user_pct_per_hour_bps: u64,
-
-    /// Per-user hard maximum per hour (in SCALE units)
-    /// e.g., 250_000 * SCALE = $250k per hour
-    pub user_hard_max_per_hour: i128,
-
-    /// Rolling window duration in seconds (typically 3600 = 1 hour)
-    pub rolling_window_secs: u64,
-
-    /// Global percentage of TVL per hour (basis points)
-    /// e.g., 500 = 5% of TVL per hour
-    pub tvl_pct_per_hour_bps: u64,
-
-    /// Global hard maximum per hour (as percentage of TVL, basis points)
-    /// e.g., 300 = 3% of TVL hard cap
-    pub global_hard_max_bps: u64,
-}
-
-impl Default for ExitBucketParams {
-    fn default() -> Self {
-        Self {
-            user_pct_per_hour_bps: 2000,  // 20%/h
-            user_hard_max_per_hour: 500_000 * SCALE,  // $500k/h (allows 20% up to $500k)
-            rolling_window_secs: 3600,  // 1 hour
-            tvl_pct_per_hour_bps: 500,  // 5%/h
-            global_hard_max_bps: 10000,  // 100% of TVL (effectively no hard max unless explicitly set)
-        }
-    }
-}
-
-/// Withdrawal threshold exceptions
-#[derive(Debug, Clone, Copy)]
-pub struct WithdrawalThresholds {
-    /// Free PnL threshold per day (no bucket charge)
-    /// e.g., 500 * SCALE = $500/day free
-    pub free_pnl_threshold_per_day: i128,
-
-    /// Principal fast lane per day (no bucket charge)
-    /// min(pct_of_principal, hard_max)
-    pub principal_fast_lane_pct_bps: u64,  // e.g., 500 = 5%
-    pub principal_fast_lane_hard_max: i128,  // e.g., $10k
-}
-
-impl Default for WithdrawalThresholds {
-    fn default() -> Self {
-        Self {
-            free_pnl_threshold_per_day: 500 * SCALE,
-            principal_fast_lane_pct_bps: 500,  // 5%
-            principal_fast_lane_hard_max: 10_000 * SCALE,
-        }
-    }
-}
-
-/// Per-user exit bucket state (time-windowed rate limiter)
-#[derive(Debug, Clone, Copy)]
-pub struct UserExitBucket {
-    /// Amount used in current window
-    pub amount_used: i128,
-
-    /// Window start time (seconds since epoch or slot-based)
-    pub window_start_secs: u64,
-
-    /// Free PnL used today
-    pub free_pnl_used_today: i128,
-
-    /// Principal fast lane used today
-    pub principal_fast_lane_used_today: i128,
-
-    /// Last reset day (for daily threshold resets)
-    pub last_reset_day: u64,
-}
-
-impl Default for UserExitBucket {
-    fn default() -> Self {
-        Self {
-            amount_used: 0,
-            window_start_secs: 0,
-            free_pnl_used_today: 0,
-            principal_fast_lane_used_today: 0,
-            last_reset_day: 0,
-        }
-    }
-}
-
-/// Global exit bucket state (aggregate throttling)
-#[derive(Debug, Clone, Copy)]
-pub struct GlobalExitBucket {
-    /// Total amount used in current window across all users
-    pub amount_used: i128,
-
-    /// Window start time
-    pub window_start_secs: u64,
-}
-
-impl Default for GlobalExitBucket {
-    fn default() -> Self {
-        Self {
-            amount_used: 0,
-            window_start_secs: 0,
-        }
-    }
-}
-
-/// Emergency mode parameters
-#[derive(Debug, Clone, Copy)]
-pub struct EmergencyMode {
-    /// Is emergency mode active?
-    pub active: bool,
-
-    /// Exit multiplier during emergency (basis points)
-    /// e.g., 5000 = 50% of normal caps
-    pub exit_multiplier_bps: u64,
-
-    /// When emergency mode auto-expires (seconds)
-    pub expires_at_secs: u64,
-}
-
-impl Default for EmergencyMode {
-    fn default() -> Self {
-        Self {
-            active: false,
-            exit_multiplier_bps: 10000,  // 100% (no reduction)
-            expires_at_secs: 0,
-        }
-    }
-}
-
-/// Withdrawal plan result
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct WithdrawalPlan {
-    /// Amount that can be withdrawn immediately
-    pub immediate: i128,
-
-    /// Amount that must be queued
-    pub queued: i128,
-
-    /// Estimated wait time in seconds (0 if immediate)
-    pub eta_secs: u64,
-}
-
 /// User state for testing
 #[derive(Debug, Clone, Copy)]
 pub struct TestUser {
@@ -197,101 +52,9 @@ impl TestUser {
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
-// CORE WITHDRAWAL LOGIC
+// TEST HARNESS (vesting + exit planning)
 // ═══════════════════════════════════════════════════════════════════════════
 
-/// Reset bucket if window has expired
-fn maybe_reset_bucket(
-    bucket: &mut UserExitBucket,
-    now_secs: u64,
-    window_secs: u64,
-) {
-    if now_secs >= bucket.window_start_secs.saturating_add(window_secs) {
-        bucket.amount_used = 0;
-        bucket.window_start_secs = now_secs;
-    }
-}
-
-/// Reset daily thresholds if day boundary crossed
-fn maybe_reset_daily_thresholds(
-    bucket: &mut UserExitBucket,
-    now_secs: u64,
-) {
-    let current_day = now_secs / 86400;  // 86400 secs per day
-    if current_day > bucket.last_reset_day {
-        bucket.free_pnl_used_today = 0;
-        bucket.principal_fast_lane_used_today = 0;
-        bucket.last_reset_day = current_day;
-    }
-}
-
-/// Compute per-user exit allowance
-fn compute_user_allowance(
-    equity: i128,
-    bucket: &UserExitBucket,
-    params: &ExitBucketParams,
-    emergency: &EmergencyMode,
-) -> i128 {
-    // Compute base cap: min(pct_of_equity, hard_max)
-    // Overflow-safe: check if multiplication would overflow
-    let pct_cap = if equity.abs() > i128::MAX / 10000 {
-        // For very large values, do division first
-        (equity / 10000) * params.user_pct_per_hour_bps as i128
-    } else {
-        (equity * params.user_pct_per_hour_bps as i128) / 10000
-    };
-    let mut cap = pct_cap.min(params.user_hard_max_per_hour);
-
-    // Apply emergency multiplier (also overflow-safe)
-    if emergency.active {
-        cap = if cap.abs() > i128::MAX / 10000 {
-            (cap / 10000) * emergency.exit_multiplier_bps as i128
-        } else {
-            (cap * emergency.exit_multiplier_bps as i128) / 10000
-        };
-    }
-
-    // Return remaining allowance
-    cap.saturating_sub(bucket.amount_used).max(0)
-}
-
-/// Compute global exit allowance
-fn compute_global_allowance(
-    tvl: i128,
-    global_bucket: &GlobalExitBucket,
-    params: &ExitBucketParams,
-    emergency: &EmergencyMode,
-    now_secs: u64,
-) -> i128 {
-    // Reset global bucket if window expired
-    let window_expired = now_secs >= global_bucket.window_start_secs.saturating_add(params.rolling_window_secs);
-    let bucket_used = if window_expired { 0 } else { global_bucket.amount_used };
-
-    // Compute base cap: min(pct_of_tvl, hard_max) - overflow-safe
-    let pct_cap = if tvl.abs() > i128::MAX / 10000 {
-        (tvl / 10000) * params.tvl_pct_per_hour_bps as i128
-    } else {
-        (tvl * params.tvl_pct_per_hour_bps as i128) / 10000
-    };
-    let hard_max = if tvl.abs() > i128::MAX / 10000 {
-        (tvl / 10000) * params.global_hard_max_bps as i128
-    } else {
-        (tvl * params.global_hard_max_bps as i128) / 10000
-    };
-    let mut cap = pct_cap.min(hard_max);
-
-    // Apply emergency multiplier (overflow-safe)
-    if emergency.active {
-        cap = if cap.abs() > i128::MAX / 10000 {
-            (cap / 10000) * emergency.exit_multiplier_bps as i128
-        } else {
-            (cap * emergency.exit_multiplier_bps as i128) / 10000
-        };
-    }
-
-    cap.saturating_sub(bucket_used).max(0)
-}
-
 /// Plan a withdrawal: compute immediate vs queued amounts
 pub fn plan_withdrawal(
     user: &mut TestUser,
@@ -322,92 +85,19 @@ pub fn plan_withdrawal(
     let withdrawable = user.withdrawable_now();
     let requested = amount.min(withdrawable);  // Can't withdraw more than available
 
-    if requested == 0 {
-        return WithdrawalPlan { immediate: 0, queued: 0, eta_secs: 0 };
-    }
-
-    // Step 3: Reset buckets if windows expired
-    maybe_reset_bucket(&mut user.exit_bucket, now_secs, params.rolling_window_secs);
-    maybe_reset_daily_thresholds(&mut user.exit_bucket, now_secs);
-
-    // Initialize or reset global bucket
-    if global_bucket.window_start_secs == 0 {
-        // First use - initialize window
-        global_bucket.window_start_secs = now_secs;
-        global_bucket.amount_used = 0;
-    } else {
-        let window_expired = now_secs >= global_bucket.window_start_secs.saturating_add(params.rolling_window_secs);
-        if window_expired {
-            global_bucket.amount_used = 0;
-            global_bucket.window_start_secs = now_secs;
-        }
-    }
-
-    // Step 4: Check threshold exceptions
-    let mut bypass_amount = 0i128;
-    let mut remaining_request = requested;
-
-    // Apply thresholds intelligently based on what's available
-    // Priority: free PnL first (for vested_pnl withdrawals), then principal fast lane
-
-    // Free PnL threshold (for vested_pnl withdrawals)
-    let free_pnl_remaining = thresholds.free_pnl_threshold_per_day.saturating_sub(user.exit_bucket.free_pnl_used_today);
-    if free_pnl_remaining > 0 && remaining_request > 0 && user.vested_pnl > 0 {
-        // Can use free PnL threshold for vested_pnl portion
-        let pnl_available = user.vested_pnl;
-        let from_free_pnl = free_pnl_remaining.min(remaining_request).min(pnl_available);
-        if from_free_pnl > 0 {
-            bypass_amount += from_free_pnl;
-            user.exit_bucket.free_pnl_used_today += from_free_pnl;
-            remaining_request -= from_free_pnl;
-        }
-    }
-
-    // Principal fast lane (for principal withdrawals)
-    let principal_fast_lane_cap = {
-        let pct_cap = (user.principal * thresholds.principal_fast_lane_pct_bps as i128) / 10000;
-        pct_cap.min(thresholds.principal_fast_lane_hard_max)
+    // Step 3: Plan against buckets and thresholds
+    let config = ExitConfig {
+        params: *params,
+        thresholds: *thresholds,
+        emergency: *emergency,
     };
-    let principal_fast_lane_remaining = principal_fast_lane_cap.saturating_sub(user.exit_bucket.principal_fast_lane_used_today);
-    if principal_fast_lane_remaining > 0 && remaining_request > 0 {
-        let from_fast_lane = principal_fast_lane_remaining.min(remaining_request);
-        bypass_amount += from_fast_lane;
-        user.exit_bucket.principal_fast_lane_used_today += from_fast_lane;
-        remaining_request -= from_fast_lane;
-    }
-
-    // Step 5: Apply exit buckets to remaining amount
-    if remaining_request == 0 {
-        // All bypassed!
-        return WithdrawalPlan { immediate: requested, queued: 0, eta_secs: 0 };
-    }
-
-    // Compute allowances
-    let user_allowance = compute_user_allowance(user.equity(), &user.exit_bucket, params, emergency);
-    let global_allowance = compute_global_allowance(tvl, global_bucket, params, emergency, now_secs);
-
-    // Min of user and global allowances
-    let bucket_allowance = user_allowance.min(global_allowance);
-
-    // How much can pass through buckets?
-    let through_buckets = remaining_request.min(bucket_allowance);
-
-    // Update buckets
-    user.exit_bucket.amount_used += through_buckets;
-    global_bucket.amount_used += through_buckets;
-
-    // Total immediate
-    let immediate = bypass_amount + through_buckets;
-    let queued = requested - immediate;
-
-    // Estimate ETA (simple: assume cap refills linearly)
-    let eta_secs = if queued > 0 {
-        params.rolling_window_secs
-    } else {
-        0
+    let account = ExitAccount {
+        principal: user.principal,
+        vested_pnl: user.vested_pnl,
+        equity: user.equity(),
     };
 
-    WithdrawalPlan { immediate, queued, eta_secs }
+    plan_exit(&mut user.exit_bucket, global_bucket, &config, &account, requested, tvl, now_secs)
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
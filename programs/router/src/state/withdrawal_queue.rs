@@ -0,0 +1,180 @@
+//! Withdrawal queue for rate-limited exits
+//!
+//! One queue per portfolio (PDA ["withdrawal_queue", portfolio]). Amounts
+//! are debited from the portfolio when queued and held in the vault
+//! until processing pays them out as exit bucket capacity refills.
+//! Entries drain strictly in FIFO order within a queue, and queues are
+//! served independently so one blocked portfolio never holds up another.
+//! Each entry carries a global ticket recording router-wide request order,
+//! which crankers can use to serve queues oldest first.
+
+use percolator_common::PercolatorError;
+use pinocchio::{msg, pubkey::Pubkey};
+
+/// Maximum queued withdrawals per portfolio
+pub const MAX_QUEUED_WITHDRAWALS: usize = 8;
+
+/// A queued withdrawal
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueuedWithdrawal {
+    /// Global ticket (router-wide request order)
+    pub ticket: u64,
+    /// Request timestamp
+    pub requested_ts: u64,
+    /// Remaining amount debited from principal
+    pub principal_part: u128,
+    /// Remaining amount debited from vested PnL
+    pub pnl_part: u128,
+}
+
+impl QueuedWithdrawal {
+    /// Remaining amount to pay out
+    pub fn remaining(&self) -> u128 {
+        self.principal_part.saturating_add(self.pnl_part)
+    }
+
+    /// Pay out part of the entry, principal first
+    pub fn drain(&mut self, amount: u128) {
+        let from_principal = amount.min(self.principal_part);
+        self.principal_part -= from_principal;
+        self.pnl_part = self.pnl_part.saturating_sub(amount - from_principal);
+    }
+}
+
+/// Withdrawal queue account
+#[repr(C)]
+pub struct WithdrawalQueue {
+    /// Router program ID
+    pub router_id: Pubkey,
+    /// Portfolio this queue belongs to
+    pub portfolio: Pubkey,
+    /// Number of queued entries
+    pub count: u16,
+    /// Bump seed
+    pub bump: u8,
+    /// Padding
+    pub _padding: [u8; 13],
+    /// Entries, oldest first
+    pub entries: [QueuedWithdrawal; MAX_QUEUED_WITHDRAWALS],
+}
+
+impl WithdrawalQueue {
+    pub const LEN: usize = core::mem::size_of::<Self>();
+
+    /// Initialize queue in-place
+    pub fn initialize_in_place(&mut self, router_id: Pubkey, portfolio: Pubkey, bump: u8) {
+        self.router_id = router_id;
+        self.portfolio = portfolio;
+        self.count = 0;
+        self.bump = bump;
+        self._padding = [0; 13];
+        self.entries = [QueuedWithdrawal::default(); MAX_QUEUED_WITHDRAWALS];
+    }
+
+    /// Check if the queue has been initialized
+    pub fn is_initialized(&self) -> bool {
+        self.router_id != Pubkey::default()
+    }
+
+    /// Total remaining amount across all entries
+    pub fn total_pending(&self) -> u128 {
+        self.entries[..self.count as usize]
+            .iter()
+            .fold(0u128, |acc, e| acc.saturating_add(e.remaining()))
+    }
+
+    /// Append an entry at the back
+    pub fn push(&mut self, entry: QueuedWithdrawal) -> Result<(), PercolatorError> {
+        if (self.count as usize) >= MAX_QUEUED_WITHDRAWALS {
+            msg!("Error: Withdrawal queue is full");
+            return Err(PercolatorError::PoolFull);
+        }
+        self.entries[self.count as usize] = entry;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Oldest entry (mutable)
+    pub fn front_mut(&mut self) -> Option<&mut QueuedWithdrawal> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(&mut self.entries[0])
+    }
+
+    /// Remove the entry at `idx`, keeping the rest in order
+    fn remove_at(&mut self, idx: usize) -> QueuedWithdrawal {
+        let count = self.count as usize;
+        let entry = self.entries[idx];
+        self.entries.copy_within(idx + 1..count, idx);
+        self.entries[count - 1] = QueuedWithdrawal::default();
+        self.count -= 1;
+        entry
+    }
+
+    /// Remove the oldest entry
+    pub fn pop_front(&mut self) -> Option<QueuedWithdrawal> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(self.remove_at(0))
+    }
+
+    /// Remove an entry by ticket
+    pub fn cancel(&mut self, ticket: u64) -> Option<QueuedWithdrawal> {
+        let idx = self.entries[..self.count as usize]
+            .iter()
+            .position(|e| e.ticket == ticket)?;
+        Some(self.remove_at(idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue() -> WithdrawalQueue {
+        let mut queue: WithdrawalQueue = unsafe { core::mem::zeroed() };
+        queue.initialize_in_place(Pubkey::from([1; 32]), Pubkey::from([2; 32]), 255);
+        queue
+    }
+
+    fn entry(ticket: u64, principal_part: u128, pnl_part: u128) -> QueuedWithdrawal {
+        QueuedWithdrawal { ticket, requested_ts: 0, principal_part, pnl_part }
+    }
+
+    #[test]
+    fn test_fifo_order_and_cancel() {
+        let mut q = queue();
+        q.push(entry(1, 100, 0)).unwrap();
+        q.push(entry(2, 200, 0)).unwrap();
+        q.push(entry(3, 300, 0)).unwrap();
+        assert_eq!(q.total_pending(), 600);
+
+        // Cancel from the middle keeps order
+        assert_eq!(q.cancel(2).unwrap().ticket, 2);
+        assert!(q.cancel(2).is_none());
+        assert_eq!(q.pop_front().unwrap().ticket, 1);
+        assert_eq!(q.pop_front().unwrap().ticket, 3);
+        assert!(q.pop_front().is_none());
+    }
+
+    #[test]
+    fn test_queue_full() {
+        let mut q = queue();
+        for i in 0..MAX_QUEUED_WITHDRAWALS as u64 {
+            q.push(entry(i, 1, 0)).unwrap();
+        }
+        assert_eq!(q.push(entry(99, 1, 0)), Err(PercolatorError::PoolFull));
+    }
+
+    #[test]
+    fn test_drain_principal_first() {
+        let mut e = entry(1, 100, 50);
+        e.drain(120);
+        assert_eq!(e.principal_part, 0);
+        assert_eq!(e.pnl_part, 30);
+        assert_eq!(e.remaining(), 30);
+    }
+}
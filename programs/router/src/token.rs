@@ -0,0 +1,94 @@
+//! Minimal SPL Token helpers
+//!
+//! Parses token accounts and issues Transfer CPIs directly so the router
+//! does not need to link the token program crate.
+
+use percolator_common::PercolatorError;
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction, Signer},
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+
+/// SPL Token program ID
+pub const TOKEN_PROGRAM_ID: Pubkey =
+    pinocchio_pubkey::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// SPL Token account size
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// SPL Token Transfer instruction discriminator
+const TRANSFER_IX: u8 = 3;
+
+/// Token account layout: mint [0..32], owner [32..64], amount [64..72], state [108]
+const MINT_OFFSET: usize = 0;
+const OWNER_OFFSET: usize = 32;
+const AMOUNT_OFFSET: usize = 64;
+const STATE_OFFSET: usize = 108;
+
+/// Fields of an SPL token account the router checks
+#[derive(Debug, Clone, Copy)]
+pub struct TokenAccountData {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+/// Read and validate an SPL token account
+///
+/// Requires the account to be owned by the token program and initialized.
+pub fn read_token_account(account: &AccountInfo) -> Result<TokenAccountData, PercolatorError> {
+    if account.owner() != &TOKEN_PROGRAM_ID {
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let data = account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+    if data.len() != TOKEN_ACCOUNT_LEN || data[STATE_OFFSET] == 0 {
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    Ok(TokenAccountData {
+        mint: Pubkey::from(<[u8; 32]>::try_from(&data[MINT_OFFSET..MINT_OFFSET + 32]).unwrap()),
+        owner: Pubkey::from(<[u8; 32]>::try_from(&data[OWNER_OFFSET..OWNER_OFFSET + 32]).unwrap()),
+        amount: u64::from_le_bytes(data[AMOUNT_OFFSET..AMOUNT_OFFSET + 8].try_into().unwrap()),
+    })
+}
+
+/// Transfer `amount` tokens from `source` to `destination`
+///
+/// `authority` must sign the outer transaction, or be a PDA covered by
+/// `signers`.
+pub fn transfer(
+    token_program: &AccountInfo,
+    source: &AccountInfo,
+    destination: &AccountInfo,
+    authority: &AccountInfo,
+    amount: u64,
+    signers: &[Signer],
+) -> Result<(), PercolatorError> {
+    if token_program.key() != &TOKEN_PROGRAM_ID {
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let mut data = [0u8; 9];
+    data[0] = TRANSFER_IX;
+    data[1..9].copy_from_slice(&amount.to_le_bytes());
+
+    let account_metas = [
+        AccountMeta::writable(source.key()),
+        AccountMeta::writable(destination.key()),
+        AccountMeta::readonly_signer(authority.key()),
+    ];
+
+    let instruction = Instruction {
+        program_id: &TOKEN_PROGRAM_ID,
+        accounts: &account_metas,
+        data: &data,
+    };
+
+    invoke_signed(&instruction, &[source, destination, authority], signers)
+        .map_err(|_| PercolatorError::CpiFailed)
+}